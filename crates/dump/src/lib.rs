@@ -18,11 +18,29 @@ pub fn dump_wasm_into(bytes: &[u8], into: impl Write) -> Result<()> {
     Ok(())
 }
 
+/// Same as [`dump_wasm_into`], but only sections for which `filter` returns
+/// `true` are written to `into`.
+///
+/// The headers and ends of modules and components are always written.
+pub fn dump_wasm_into_filtered(
+    bytes: &[u8],
+    into: impl Write,
+    filter: impl FnMut(&Payload<'_>) -> bool,
+) -> Result<()> {
+    let mut d = Dump::new(bytes, into);
+    d.filter = Some(Box::new(filter));
+    d.run()?;
+    Ok(())
+}
+
+type SectionFilter<'a> = dyn FnMut(&Payload<'_>) -> bool + 'a;
+
 struct Dump<'a> {
     bytes: &'a [u8],
     cur: usize,
     state: String,
     dst: Box<dyn Write + 'a>,
+    filter: Option<Box<SectionFilter<'a>>>,
     nesting: u32,
     offset_width: usize,
 }
@@ -64,6 +82,7 @@ impl<'a> Dump<'a> {
             nesting: 0,
             state: String::new(),
             dst: Box::new(dst) as _,
+            filter: None,
             offset_width: format!("{:x}", bytes.len()).len() + 1,
         }
     }
@@ -81,7 +100,27 @@ impl<'a> Dump<'a> {
         self.nesting += 1;
 
         for item in Parser::new(0).parse_all(self.bytes) {
-            match item? {
+            let payload = item?;
+
+            // Omitted sections are still processed to keep track of offsets
+            // and indices, but their output is discarded.
+            let omitted = match &payload {
+                Payload::Version { .. }
+                | Payload::ModuleSection { .. }
+                | Payload::ComponentSection { .. }
+                | Payload::End(_) => false,
+                _ => match &mut self.filter {
+                    Some(filter) => !filter(&payload),
+                    None => false,
+                },
+            };
+            let dst = if omitted {
+                Some(std::mem::replace(&mut self.dst, Box::new(std::io::sink())))
+            } else {
+                None
+            };
+
+            match payload {
                 Payload::Version {
                     num,
                     encoding,
@@ -401,6 +440,20 @@ impl<'a> Dump<'a> {
                         while !iter.eof() {
                             self.print_custom_name_section(iter.read()?, iter.original_position())?;
                         }
+                    } else if c.name() == "build_id" {
+                        let section = BuildIdSection::new(c.data(), c.data_offset())?;
+                        write!(self.state, "build id: {}", section.to_hex())?;
+                        self.print(c.range().end)?;
+                    } else if c.name() == "linking" {
+                        let mut iter = LinkingSectionReader::new(c.data(), c.data_offset())?;
+                        write!(self.state, "linking version {}", iter.version())?;
+                        self.print(iter.original_position())?;
+                        while !iter.eof() {
+                            self.print_custom_linking_section(
+                                iter.read()?,
+                                iter.original_position(),
+                            )?;
+                        }
                     } else {
                         self.print_byte_header()?;
                         for _ in 0..NBYTES {
@@ -431,6 +484,10 @@ impl<'a> Dump<'a> {
                     }
                 }
             }
+
+            if let Some(dst) = dst {
+                self.dst = dst;
+            }
         }
 
         Ok(())
@@ -505,6 +562,25 @@ impl<'a> Dump<'a> {
         Ok(())
     }
 
+    fn print_custom_linking_section(&mut self, linking: Linking<'_>, end: usize) -> Result<()> {
+        match linking {
+            Linking::SymbolTable(s) => {
+                write!(self.state, "symbol table")?;
+                self.print(s.range().start)?;
+                self.print_iter(s, |me, end, symbol| {
+                    write!(me.state, "{:?}", symbol)?;
+                    me.print(end)
+                })?;
+            }
+            Linking::Unknown { ty, range, .. } => {
+                write!(self.state, "unknown linking subsection: {}", ty)?;
+                self.print(range.start)?;
+                self.print(end)?;
+            }
+        }
+        Ok(())
+    }
+
     fn section<T>(
         &mut self,
         iter: T,
@@ -219,6 +219,7 @@ impl<'a> TypeEncoder<'a> {
             maximum: ty.maximum,
             memory64: ty.memory64,
             shared: ty.shared,
+            page_size_log2: ty.page_size_log2,
         }
     }
 
@@ -142,6 +142,7 @@
       (with "a" (instance 0))
     )
   )
+  ;; component 2
   (component (;2;)
     (type (;0;) 
       (instance
@@ -1,4 +1,5 @@
 (component
+  ;; component 0
   (component (;0;)
     (type (;0;) (func))
     (type (;1;) (func (param "x" s8)))
@@ -35,6 +36,7 @@
     (export "enum1" (type 21))
     (export "union1" (type 23))
     (export "variant1" (type 25))
+    ;; component 0 / core module 0
     (core module (;0;)
       (type (;0;) (func))
       (type (;1;) (func (param i32)))
@@ -207,6 +209,7 @@
     (export "u" (func 20))
   )
   (instance (;0;) (instantiate 0))
+  ;; component 1
   (component (;1;)
     (type (;0;) (func))
     (type (;1;) (func (param "x" s8)))
@@ -296,6 +299,7 @@
     )
     (type (;31;) (func (param "x" string) (result string)))
     (import "a" (instance (;0;) (type 30)))
+    ;; component 1 / core module 0
     (core module (;0;)
       (type (;0;) (func (param i32 i32)))
       (type (;1;) (func (param i32 i32) (result i32)))
@@ -312,6 +316,7 @@
       (export "m" (func $m))
       (export "canonical_abi_realloc" (func $canonical_abi_realloc))
     )
+    ;; component 1 / core module 1
     (core module (;1;)
       (type (;0;) (func (param i32 i32)))
       (type (;1;) (func (param i32 i32 i32 i32 i32 i32 i64 i64 f32 f64 i32 i32 i32)))
@@ -419,6 +424,7 @@
       (export "6" (func 6))
       (export "$imports" (table 0))
     )
+    ;; component 1 / core module 2
     (core module (;2;)
       (type (;0;) (func (param i32 i32)))
       (type (;1;) (func (param i32 i32 i32 i32 i32 i32 i64 i64 f32 f64 i32 i32 i32)))
@@ -545,6 +551,7 @@
       (with "a" (instance 0))
     )
   )
+  ;; component 2
   (component (;2;)
     (type (;0;) 
       (instance
@@ -10,6 +10,7 @@
     )
   )
   (import "a" (instance (;0;) (type 0)))
+  ;; component 0
   (component (;0;)
     (type (;0;) 
       (instance
@@ -25,6 +26,7 @@
       (with "a" (instance 0))
     )
   )
+  ;; component 1
   (component (;1;)
     (type (;0;) 
       (instance
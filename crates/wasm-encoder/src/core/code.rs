@@ -38,6 +38,7 @@ use std::borrow::Cow;
 pub struct CodeSection {
     bytes: Vec<u8>,
     num_added: u32,
+    offsets: Vec<usize>,
 }
 
 impl CodeSection {
@@ -65,8 +66,24 @@ impl CodeSection {
         self.num_added == 0
     }
 
+    /// The offset of each function added to this section, in the order they
+    /// were added.
+    ///
+    /// Each offset is that of the function's size prefix, in bytes from the
+    /// start of the first function. As with [`CodeSection::byte_len`] this
+    /// doesn't include the vector length that precedes the code entries, so
+    /// within the encoded section's contents each function starts at its
+    /// offset plus the size of the LEB128-encoded [`CodeSection::len`].
+    ///
+    /// This can be used by producers to lay out or describe functions for
+    /// streaming compilation, for example in a custom section.
+    pub fn function_offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
     /// Write a function body into this code section.
     pub fn function(&mut self, func: &Function) -> &mut Self {
+        self.offsets.push(self.bytes.len());
         func.encode(&mut self.bytes);
         self.num_added += 1;
         self
@@ -97,6 +114,7 @@ impl CodeSection {
     /// encoder.raw(&code_section[body_range.start..body_range.end]);
     /// ```
     pub fn raw(&mut self, data: &[u8]) -> &mut Self {
+        self.offsets.push(self.bytes.len());
         data.encode(&mut self.bytes);
         self.num_added += 1;
         self
@@ -2865,4 +2883,59 @@ mod tests {
 
         assert_eq!(f1.bytes, f2.bytes)
     }
+
+    #[test]
+    fn function_offsets_test() {
+        use super::*;
+        use crate::{FunctionSection, Module, TypeSection};
+
+        let mut types = TypeSection::new();
+        types.function([], []);
+        let mut functions = FunctionSection::new();
+        let mut code = CodeSection::new();
+        for i in 0..200 {
+            functions.function(0);
+            let mut func = Function::new([(i, ValType::I32)]);
+            for _ in 0..i {
+                func.instruction(&Instruction::Nop);
+            }
+            func.instruction(&Instruction::End);
+            if i % 2 == 0 {
+                code.function(&func);
+            } else {
+                code.raw(&func.bytes);
+            }
+        }
+        assert_eq!(code.function_offsets().len(), 200);
+
+        let mut module = Module::new();
+        module.section(&types).section(&functions).section(&code);
+        let wasm = module.finish();
+
+        let mut count = Vec::new();
+        code.len().encode(&mut count);
+        let mut bodies = Vec::new();
+        for payload in wasmparser::Parser::new(0).parse_all(&wasm) {
+            match payload.unwrap() {
+                wasmparser::Payload::CodeSectionStart { range, .. } => {
+                    bodies.push(range.start + count.len());
+                }
+                wasmparser::Payload::CodeSectionEntry(body) => {
+                    bodies.push(body.range().start);
+                }
+                _ => {}
+            }
+        }
+
+        // Each body starts after its size prefix, which is at the reported
+        // offset from the first function.
+        let start = bodies.remove(0);
+        assert_eq!(bodies.len(), 200);
+        for (offset, body) in code.function_offsets().iter().zip(bodies) {
+            let position = start + offset;
+            let mut reader = wasmparser::BinaryReader::new_with_offset(&wasm[position..], position);
+            reader.read_var_u32().unwrap();
+            assert_eq!(reader.original_position(), body);
+        }
+    }
 }
@@ -89,6 +89,7 @@ impl From<TagType> for EntityType {
 ///         maximum: None,
 ///         memory64: false,
 ///         shared: false,
+///         page_size_log2: None,
 ///     }
 /// );
 ///
@@ -15,6 +15,7 @@ use crate::{encode_section, Encode, Section, SectionId};
 ///     maximum: None,
 ///     memory64: false,
 ///     shared: false,
+///     page_size_log2: None,
 /// });
 ///
 /// let mut module = Module::new();
@@ -75,6 +76,9 @@ pub struct MemoryType {
     pub memory64: bool,
     /// Whether or not this memory is shared.
     pub shared: bool,
+    /// The log base 2 of a custom page size for this memory, or `None` for
+    /// the default page size of 64KiB.
+    pub page_size_log2: Option<u32>,
 }
 
 impl Encode for MemoryType {
@@ -89,11 +93,17 @@ impl Encode for MemoryType {
         if self.memory64 {
             flags |= 0b100;
         }
+        if self.page_size_log2.is_some() {
+            flags |= 0b1000;
+        }
 
         sink.push(flags);
         self.minimum.encode(sink);
         if let Some(max) = self.maximum {
             max.encode(sink);
         }
+        if let Some(log2) = self.page_size_log2 {
+            log2.encode(sink);
+        }
     }
 }
@@ -145,6 +145,15 @@ impl NameSection {
         names.encode(&mut self.bytes);
     }
 
+    /// Appends a subsection with the raw identifier `id` and contents `data`.
+    ///
+    /// This can be used to preserve subsections which aren't otherwise
+    /// understood by this encoder.
+    pub fn raw(&mut self, id: u8, data: &[u8]) {
+        self.bytes.push(id);
+        data.encode(&mut self.bytes);
+    }
+
     fn subsection_header(&mut self, id: Subsection, len: usize) {
         self.bytes.push(id as u8);
         len.encode(&mut self.bytes);
@@ -13,6 +13,7 @@ mod error;
 mod info;
 mod module;
 mod mutators;
+pub mod translate;
 
 pub use error::*;
 
@@ -35,9 +35,8 @@ pub mod rename_export;
 pub mod snip_function;
 pub mod start;
 
-mod translate;
-pub use self::translate::Item;
-use self::translate::{DefaultTranslator, Translator};
+pub use crate::translate::Item;
+use crate::translate::{DefaultTranslator, Translator};
 
 use std::borrow::Cow;
 
@@ -1,7 +1,7 @@
 //! This mutator modifies the constant initializer expressions between various valid forms in
 //! entities which require constant initializers.
 
-use crate::mutators::translate::{self, ConstExprKind, Item, Translator};
+use crate::translate::{self, ConstExprKind, Item, Translator};
 use crate::{Error, Mutator, Result};
 use rand::Rng;
 use wasm_encoder::{ElementSection, GlobalSection};
@@ -1,6 +1,6 @@
-use super::translate::ConstExprKind;
 use super::Mutator;
 use crate::mutators::{DefaultTranslator, Translator};
+use crate::translate::ConstExprKind;
 use crate::{Result, WasmMutate};
 use rand::Rng;
 use wasm_encoder::{DataSection, DataSegment, DataSegmentMode, Module};
@@ -7,7 +7,8 @@
 //! mutator largely translates between `wasmparser` structures and
 //! `wasm_encoder` structures.
 
-use crate::mutators::{translate, Item, Mutator, Translator};
+use crate::mutators::{Item, Mutator, Translator};
+use crate::translate;
 use crate::Error;
 use crate::{ModuleInfo, Result, WasmMutate};
 use rand::Rng;
@@ -0,0 +1,893 @@
+//! Translation of core wasm modules from `wasmparser` types into
+//! `wasm-encoder` types.
+//!
+//! This is the shared foundation for mutators, and for `wasm-tools`
+//! subcommands, which rewrite a module: the input is parsed with `wasmparser`
+//! and each item is re-encoded through `wasm-encoder`, with the [`Translator`]
+//! trait acting as a hook to rewrite items or remap indices along the way. The
+//! default implementation of every method is an identity translation.
+
+pub use crate::{Error, Result};
+use std::collections::HashMap;
+use wasm_encoder::*;
+use wasmparser::{
+    DataKind, ElementItem, ElementKind, FunctionBody, Global, Name, Operator, Parser, Payload, Type,
+};
+
+/// The index spaces of a core wasm module which can be remapped.
+#[derive(Debug, Hash, Eq, PartialEq, Copy, Clone)]
+pub enum Item {
+    /// The function index space.
+    Function,
+    /// The table index space.
+    Table,
+    /// The memory index space.
+    Memory,
+    /// The tag index space.
+    Tag,
+    /// The global index space.
+    Global,
+    /// The type index space.
+    Type,
+    /// The data segment index space.
+    Data,
+    /// The element segment index space.
+    Element,
+}
+
+/// The context in which a constant expression is being translated.
+#[derive(Debug, Hash, Eq, PartialEq, Copy, Clone)]
+pub enum ConstExprKind {
+    /// The initializer of a global.
+    Global,
+    /// The offset of an active element segment.
+    ElementOffset,
+    /// An item of an element segment.
+    ElementFunction,
+    /// The offset of an active data segment.
+    DataOffset,
+}
+
+/// Hooks for translating each part of a module, where each method defaults to
+/// the free function of the same name in this module.
+///
+/// Implementors override the methods for the items they rewrite, usually
+/// calling back into the free function for everything else, and override
+/// [`Translator::remap`] to renumber indices.
+pub trait Translator {
+    /// Returns `self` as a trait object to pass to the free functions of this
+    /// module.
+    fn as_obj(&mut self) -> &mut dyn Translator;
+
+    /// Defaults to [`type_def`].
+    fn translate_type_def(&mut self, ty: Type, s: &mut TypeSection) -> Result<()> {
+        type_def(self.as_obj(), ty, s)
+    }
+
+    /// Defaults to [`import_def`].
+    fn translate_import(
+        &mut self,
+        import: wasmparser::Import<'_>,
+        s: &mut ImportSection,
+    ) -> Result<()> {
+        import_def(self.as_obj(), import, s)
+    }
+
+    /// Defaults to [`function`].
+    fn translate_function(&mut self, ty: u32, s: &mut FunctionSection) -> Result<()> {
+        function(self.as_obj(), ty, s)
+    }
+
+    /// Defaults to [`table`].
+    fn translate_table(&mut self, ty: wasmparser::TableType, s: &mut TableSection) -> Result<()> {
+        table(self.as_obj(), ty, s)
+    }
+
+    /// Defaults to [`memory`].
+    fn translate_memory(
+        &mut self,
+        ty: wasmparser::MemoryType,
+        s: &mut MemorySection,
+    ) -> Result<()> {
+        memory(self.as_obj(), ty, s)
+    }
+
+    /// Defaults to [`tag`].
+    fn translate_tag(&mut self, ty: wasmparser::TagType, s: &mut TagSection) -> Result<()> {
+        tag(self.as_obj(), ty, s)
+    }
+
+    /// Defaults to [`entity_type`].
+    fn translate_entity_type(&mut self, ty: &wasmparser::TypeRef) -> Result<EntityType> {
+        entity_type(self.as_obj(), ty)
+    }
+
+    /// Defaults to [`table_type`].
+    fn translate_table_type(
+        &mut self,
+        ty: &wasmparser::TableType,
+    ) -> Result<wasm_encoder::TableType> {
+        table_type(self.as_obj(), ty)
+    }
+
+    /// Defaults to [`memory_type`].
+    fn translate_memory_type(
+        &mut self,
+        ty: &wasmparser::MemoryType,
+    ) -> Result<wasm_encoder::MemoryType> {
+        memory_type(self.as_obj(), ty)
+    }
+
+    /// Defaults to [`global_type`].
+    fn translate_global_type(
+        &mut self,
+        ty: &wasmparser::GlobalType,
+    ) -> Result<wasm_encoder::GlobalType> {
+        global_type(self.as_obj(), ty)
+    }
+
+    /// Defaults to [`tag_type`].
+    fn translate_tag_type(&mut self, ty: &wasmparser::TagType) -> Result<wasm_encoder::TagType> {
+        tag_type(self.as_obj(), ty)
+    }
+
+    /// Defaults to [`ty`].
+    fn translate_ty(&mut self, t: &wasmparser::ValType) -> Result<ValType> {
+        ty(self.as_obj(), t)
+    }
+
+    /// Defaults to [`global`].
+    fn translate_global(&mut self, g: Global, s: &mut GlobalSection) -> Result<()> {
+        global(self.as_obj(), g, s)
+    }
+
+    /// Defaults to [`export`].
+    fn translate_export(
+        &mut self,
+        e: &wasmparser::Export<'_>,
+        s: &mut ExportSection,
+    ) -> Result<()> {
+        export(self.as_obj(), e, s)
+    }
+
+    /// Defaults to [`const_expr`].
+    fn translate_const_expr(
+        &mut self,
+        e: &wasmparser::ConstExpr<'_>,
+        _ty: &wasmparser::ValType,
+        ctx: ConstExprKind,
+    ) -> Result<wasm_encoder::ConstExpr> {
+        const_expr(self.as_obj(), e, ctx)
+    }
+
+    /// Defaults to [`element`].
+    fn translate_element(
+        &mut self,
+        e: wasmparser::Element<'_>,
+        s: &mut ElementSection,
+    ) -> Result<()> {
+        element(self.as_obj(), e, s)
+    }
+
+    /// Defaults to [`data`].
+    fn translate_data(&mut self, d: wasmparser::Data<'_>, s: &mut DataSection) -> Result<()> {
+        data(self.as_obj(), d, s)
+    }
+
+    /// Translates the count of the data count section.
+    fn translate_data_count(&mut self, count: u32) -> Result<u32> {
+        Ok(count)
+    }
+
+    /// Defaults to [`code`].
+    fn translate_code(&mut self, body: FunctionBody<'_>, s: &mut CodeSection) -> Result<()> {
+        code(self.as_obj(), body, s)
+    }
+
+    /// Defaults to [`op`].
+    fn translate_op(&mut self, e: &Operator<'_>) -> Result<Instruction<'static>> {
+        op(self.as_obj(), e)
+    }
+
+    /// Defaults to [`block_type`].
+    fn translate_block_type(&mut self, ty: &wasmparser::BlockType) -> Result<BlockType> {
+        block_type(self.as_obj(), ty)
+    }
+
+    /// Defaults to [`memarg`].
+    fn translate_memarg(&mut self, arg: &wasmparser::MemArg) -> Result<MemArg> {
+        memarg(self.as_obj(), arg)
+    }
+
+    /// Defaults to [`custom_section`].
+    fn translate_custom_section(
+        &mut self,
+        section: &wasmparser::CustomSectionReader<'_>,
+        module: &mut Module,
+    ) -> Result<()> {
+        custom_section(self.as_obj(), section, module)
+    }
+
+    /// Returns the new index of the item `idx` of the `item` index space.
+    fn remap(&mut self, _item: Item, idx: u32) -> Result<u32> {
+        Ok(idx)
+    }
+}
+
+/// A [`Translator`] which leaves every item unchanged.
+pub struct DefaultTranslator;
+
+impl Translator for DefaultTranslator {
+    fn as_obj(&mut self) -> &mut dyn Translator {
+        self
+    }
+}
+
+/// Translates the entire core wasm module `wasm` through `t`, returning the
+/// newly encoded module.
+///
+/// Sections are emitted in the same order they're found in the input. Custom
+/// sections are handed to [`Translator::translate_custom_section`] and unknown
+/// sections are copied verbatim.
+pub fn module(t: &mut dyn Translator, wasm: &[u8]) -> Result<Vec<u8>> {
+    let mut module = Module::new();
+    let mut code = None;
+    let mut remaining_bodies = 0;
+
+    for payload in Parser::new(0).parse_all(wasm) {
+        let payload = payload?;
+        match payload {
+            Payload::Version { encoding, .. } => {
+                if encoding != wasmparser::Encoding::Module {
+                    return Err(Error::unsupported(
+                        "components are not supported, only core wasm modules",
+                    ));
+                }
+            }
+            Payload::TypeSection(reader) => {
+                let mut s = TypeSection::new();
+                for ty in reader {
+                    t.translate_type_def(ty?, &mut s)?;
+                }
+                module.section(&s);
+            }
+            Payload::ImportSection(reader) => {
+                let mut s = ImportSection::new();
+                for import in reader {
+                    t.translate_import(import?, &mut s)?;
+                }
+                module.section(&s);
+            }
+            Payload::FunctionSection(reader) => {
+                let mut s = FunctionSection::new();
+                for ty in reader {
+                    t.translate_function(ty?, &mut s)?;
+                }
+                module.section(&s);
+            }
+            Payload::TableSection(reader) => {
+                let mut s = TableSection::new();
+                for ty in reader {
+                    t.translate_table(ty?, &mut s)?;
+                }
+                module.section(&s);
+            }
+            Payload::MemorySection(reader) => {
+                let mut s = MemorySection::new();
+                for ty in reader {
+                    t.translate_memory(ty?, &mut s)?;
+                }
+                module.section(&s);
+            }
+            Payload::TagSection(reader) => {
+                let mut s = TagSection::new();
+                for ty in reader {
+                    t.translate_tag(ty?, &mut s)?;
+                }
+                module.section(&s);
+            }
+            Payload::GlobalSection(reader) => {
+                let mut s = GlobalSection::new();
+                for global in reader {
+                    t.translate_global(global?, &mut s)?;
+                }
+                module.section(&s);
+            }
+            Payload::ExportSection(reader) => {
+                let mut s = ExportSection::new();
+                for export in reader {
+                    t.translate_export(&export?, &mut s)?;
+                }
+                module.section(&s);
+            }
+            Payload::StartSection { func, .. } => {
+                module.section(&StartSection {
+                    function_index: t.remap(Item::Function, func)?,
+                });
+            }
+            Payload::ElementSection(reader) => {
+                let mut s = ElementSection::new();
+                for element in reader {
+                    t.translate_element(element?, &mut s)?;
+                }
+                module.section(&s);
+            }
+            Payload::DataCountSection { count, .. } => {
+                module.section(&DataCountSection {
+                    count: t.translate_data_count(count)?,
+                });
+            }
+            Payload::DataSection(reader) => {
+                let mut s = DataSection::new();
+                for data in reader {
+                    t.translate_data(data?, &mut s)?;
+                }
+                module.section(&s);
+            }
+            Payload::CodeSectionStart { count, .. } => {
+                remaining_bodies = count;
+                code = Some(CodeSection::new());
+                if count == 0 {
+                    module.section(&code.take().unwrap());
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                let s = code.as_mut().unwrap();
+                t.translate_code(body, s)?;
+                remaining_bodies -= 1;
+                if remaining_bodies == 0 {
+                    module.section(&code.take().unwrap());
+                }
+            }
+            Payload::CustomSection(c) => t.translate_custom_section(&c, &mut module)?,
+            Payload::UnknownSection { id, contents, .. } => {
+                module.section(&RawSection { id, data: contents });
+            }
+            Payload::End(_) => {}
+
+            // Component sections are rejected when the version header is
+            // parsed above.
+            _ => unreachable!(),
+        }
+    }
+
+    Ok(module.finish())
+}
+
+/// Translates the type definition `ty` into `s`.
+pub fn type_def(t: &mut dyn Translator, ty: Type, s: &mut TypeSection) -> Result<()> {
+    match ty {
+        Type::Func(f) => {
+            s.function(
+                f.params()
+                    .iter()
+                    .map(|ty| t.translate_ty(ty))
+                    .collect::<Result<Vec<_>>>()?,
+                f.results()
+                    .iter()
+                    .map(|ty| t.translate_ty(ty))
+                    .collect::<Result<Vec<_>>>()?,
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Translates `import` into `s`.
+pub fn import_def(
+    t: &mut dyn Translator,
+    import: wasmparser::Import<'_>,
+    s: &mut ImportSection,
+) -> Result<()> {
+    let ty = t.translate_entity_type(&import.ty)?;
+    s.import(import.module, import.name, ty);
+    Ok(())
+}
+
+/// Translates the declaration of a function of type `ty` into `s`.
+pub fn function(t: &mut dyn Translator, ty: u32, s: &mut FunctionSection) -> Result<()> {
+    s.function(t.remap(Item::Type, ty)?);
+    Ok(())
+}
+
+/// Translates the table of type `ty` into `s`.
+pub fn table(
+    t: &mut dyn Translator,
+    ty: wasmparser::TableType,
+    s: &mut TableSection,
+) -> Result<()> {
+    s.table(t.translate_table_type(&ty)?);
+    Ok(())
+}
+
+/// Translates the memory of type `ty` into `s`.
+pub fn memory(
+    t: &mut dyn Translator,
+    ty: wasmparser::MemoryType,
+    s: &mut MemorySection,
+) -> Result<()> {
+    s.memory(t.translate_memory_type(&ty)?);
+    Ok(())
+}
+
+/// Translates the tag of type `ty` into `s`.
+pub fn tag(t: &mut dyn Translator, ty: wasmparser::TagType, s: &mut TagSection) -> Result<()> {
+    s.tag(t.translate_tag_type(&ty)?);
+    Ok(())
+}
+
+/// Translates the type of an import.
+pub fn entity_type(t: &mut dyn Translator, ty: &wasmparser::TypeRef) -> Result<EntityType> {
+    Ok(match ty {
+        wasmparser::TypeRef::Func(i) => EntityType::Function(t.remap(Item::Type, *i)?),
+        wasmparser::TypeRef::Table(ty) => EntityType::Table(t.translate_table_type(ty)?),
+        wasmparser::TypeRef::Memory(ty) => EntityType::Memory(t.translate_memory_type(ty)?),
+        wasmparser::TypeRef::Global(ty) => EntityType::Global(t.translate_global_type(ty)?),
+        wasmparser::TypeRef::Tag(ty) => EntityType::Tag(t.translate_tag_type(ty)?),
+    })
+}
+
+/// Translates a table type.
+pub fn table_type(
+    t: &mut dyn Translator,
+    ty: &wasmparser::TableType,
+) -> Result<wasm_encoder::TableType> {
+    Ok(wasm_encoder::TableType {
+        element_type: t.translate_ty(&ty.element_type)?,
+        minimum: ty.initial,
+        maximum: ty.maximum,
+    })
+}
+
+/// Translates a memory type.
+pub fn memory_type(
+    _t: &mut dyn Translator,
+    ty: &wasmparser::MemoryType,
+) -> Result<wasm_encoder::MemoryType> {
+    Ok(wasm_encoder::MemoryType {
+        memory64: ty.memory64,
+        minimum: ty.initial,
+        maximum: ty.maximum,
+        shared: ty.shared,
+        page_size_log2: ty.page_size_log2,
+    })
+}
+
+/// Translates a global type.
+pub fn global_type(
+    t: &mut dyn Translator,
+    ty: &wasmparser::GlobalType,
+) -> Result<wasm_encoder::GlobalType> {
+    Ok(wasm_encoder::GlobalType {
+        val_type: t.translate_ty(&ty.content_type)?,
+        mutable: ty.mutable,
+    })
+}
+
+/// Translates a tag type.
+pub fn tag_type(t: &mut dyn Translator, ty: &wasmparser::TagType) -> Result<wasm_encoder::TagType> {
+    Ok(wasm_encoder::TagType {
+        kind: TagKind::Exception,
+        func_type_idx: t.remap(Item::Type, ty.func_type_idx)?,
+    })
+}
+
+/// Translates a value type.
+pub fn ty(_t: &mut dyn Translator, ty: &wasmparser::ValType) -> Result<ValType> {
+    match ty {
+        wasmparser::ValType::I32 => Ok(ValType::I32),
+        wasmparser::ValType::I64 => Ok(ValType::I64),
+        wasmparser::ValType::F32 => Ok(ValType::F32),
+        wasmparser::ValType::F64 => Ok(ValType::F64),
+        wasmparser::ValType::V128 => Ok(ValType::V128),
+        wasmparser::ValType::FuncRef => Ok(ValType::FuncRef),
+        wasmparser::ValType::ExternRef => Ok(ValType::ExternRef),
+    }
+}
+
+/// Translates `global` into `s`.
+pub fn global(t: &mut dyn Translator, global: Global, s: &mut GlobalSection) -> Result<()> {
+    let ty = t.translate_global_type(&global.ty)?;
+    let insn = t.translate_const_expr(
+        &global.init_expr,
+        &global.ty.content_type,
+        ConstExprKind::Global,
+    )?;
+    s.global(ty, &insn);
+    Ok(())
+}
+
+/// Translates `export` into `s`.
+pub fn export(
+    t: &mut dyn Translator,
+    export: &wasmparser::Export<'_>,
+    s: &mut ExportSection,
+) -> Result<()> {
+    let (kind, index) = match export.kind {
+        wasmparser::ExternalKind::Func => {
+            (ExportKind::Func, t.remap(Item::Function, export.index)?)
+        }
+        wasmparser::ExternalKind::Table => (ExportKind::Table, t.remap(Item::Table, export.index)?),
+        wasmparser::ExternalKind::Memory => {
+            (ExportKind::Memory, t.remap(Item::Memory, export.index)?)
+        }
+        wasmparser::ExternalKind::Global => {
+            (ExportKind::Global, t.remap(Item::Global, export.index)?)
+        }
+        wasmparser::ExternalKind::Tag => (ExportKind::Tag, t.remap(Item::Tag, export.index)?),
+    };
+    s.export(export.name, kind, index);
+    Ok(())
+}
+
+/// Translates the constant expression `e`, which may have any number of
+/// instructions.
+pub fn const_expr(
+    t: &mut dyn Translator,
+    e: &wasmparser::ConstExpr<'_>,
+    _ctx: ConstExprKind,
+) -> Result<wasm_encoder::ConstExpr> {
+    let mut bytes = Vec::new();
+    let mut reader = e.get_operators_reader();
+    loop {
+        let op = reader.read()?;
+        if let Operator::End = op {
+            if !reader.eof() {
+                return Err(Error::other(
+                    "trailing bytes after the end of a constant expression",
+                ));
+            }
+            break;
+        }
+        t.translate_op(&op)?.encode(&mut bytes);
+    }
+    Ok(wasm_encoder::ConstExpr::raw(bytes))
+}
+
+/// Translates the element segment `element` into `s`.
+///
+/// Active segments of `funcref`s for table 0 are always written without an
+/// explicit table index since that's the most compact encoding.
+pub fn element(
+    t: &mut dyn Translator,
+    element: wasmparser::Element<'_>,
+    s: &mut ElementSection,
+) -> Result<()> {
+    let offset;
+    let mode = match &element.kind {
+        ElementKind::Active {
+            table_index,
+            offset_expr,
+        } => {
+            offset = t.translate_const_expr(
+                offset_expr,
+                &wasmparser::ValType::I32,
+                ConstExprKind::ElementOffset,
+            )?;
+            let table = t.remap(Item::Table, *table_index)?;
+            ElementMode::Active {
+                table: if table == 0 && element.ty == wasmparser::ValType::FuncRef {
+                    None
+                } else {
+                    Some(table)
+                },
+                offset: &offset,
+            }
+        }
+        ElementKind::Passive => ElementMode::Passive,
+        ElementKind::Declared => ElementMode::Declared,
+    };
+    let element_type = t.translate_ty(&element.ty)?;
+    let mut functions = Vec::new();
+    let mut exprs = Vec::new();
+    let mut reader = element.items.get_items_reader()?;
+    for _ in 0..reader.get_count() {
+        match reader.read()? {
+            ElementItem::Func(idx) => {
+                functions.push(t.remap(Item::Function, idx)?);
+            }
+            ElementItem::Expr(expr) => {
+                exprs.push(t.translate_const_expr(
+                    &expr,
+                    &element.ty,
+                    ConstExprKind::ElementFunction,
+                )?);
+            }
+        }
+    }
+    s.segment(ElementSegment {
+        mode,
+        element_type,
+        elements: if reader.uses_exprs() {
+            Elements::Expressions(&exprs)
+        } else {
+            Elements::Functions(&functions)
+        },
+    });
+    Ok(())
+}
+
+/// Translates a single instruction.
+#[allow(unused_variables)]
+pub fn op(t: &mut dyn Translator, op: &Operator<'_>) -> Result<Instruction<'static>> {
+    use wasm_encoder::Instruction as I;
+
+    macro_rules! translate {
+        ($( @$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident)*) => {
+            Ok(match op {
+                $(
+                    wasmparser::Operator::$op $({ $($arg),* })? => {
+                        $(
+                            $(let $arg = translate!(map $arg $arg);)*
+                        )?
+                        translate!(build $op $($($arg)*)?)
+                    }
+                )*
+            })
+        };
+
+        // This case is used to map, based on the name of the field, from the
+        // wasmparser payload type to the wasm-encoder payload type through
+        // `Translator` as applicable.
+        (map $arg:ident tag_index) => (t.remap(Item::Tag, *$arg)?);
+        (map $arg:ident function_index) => (t.remap(Item::Function, *$arg)?);
+        (map $arg:ident table) => (t.remap(Item::Table, *$arg)?);
+        (map $arg:ident table_index) => (t.remap(Item::Table, *$arg)?);
+        (map $arg:ident dst_table) => (t.remap(Item::Table, *$arg)?);
+        (map $arg:ident src_table) => (t.remap(Item::Table, *$arg)?);
+        (map $arg:ident type_index) => (t.remap(Item::Type, *$arg)?);
+        (map $arg:ident global_index) => (t.remap(Item::Global, *$arg)?);
+        (map $arg:ident mem) => (t.remap(Item::Memory, *$arg)?);
+        (map $arg:ident src_mem) => (t.remap(Item::Memory, *$arg)?);
+        (map $arg:ident dst_mem) => (t.remap(Item::Memory, *$arg)?);
+        (map $arg:ident data_index) => (t.remap(Item::Data, *$arg)?);
+        (map $arg:ident elem_index) => (t.remap(Item::Element, *$arg)?);
+        (map $arg:ident blockty) => (t.translate_block_type($arg)?);
+        (map $arg:ident relative_depth) => (*$arg);
+        (map $arg:ident targets) => ((
+            $arg
+                .targets()
+                .collect::<Result<Vec<_>, wasmparser::BinaryReaderError>>()?
+                .into(),
+            $arg.default(),
+        ));
+        (map $arg:ident table_byte) => (());
+        (map $arg:ident mem_byte) => (());
+        (map $arg:ident flags) => (());
+        (map $arg:ident ty) => (t.translate_ty($arg)?);
+        (map $arg:ident memarg) => (t.translate_memarg($arg)?);
+        (map $arg:ident local_index) => (*$arg);
+        (map $arg:ident value) => ($arg);
+        (map $arg:ident lane) => (*$arg);
+        (map $arg:ident lanes) => (*$arg);
+
+        // This case takes the arguments of a wasmparser instruction and creates
+        // a wasm-encoder instruction. There are a few special cases for where
+        // the structure of a wasmparser instruction differs from that of
+        // wasm-encoder.
+        (build $op:ident) => (I::$op);
+        (build BrTable $arg:ident) => (I::BrTable($arg.0, $arg.1));
+        (build I32Const $arg:ident) => (I::I32Const(*$arg));
+        (build I64Const $arg:ident) => (I::I64Const(*$arg));
+        (build F32Const $arg:ident) => (I::F32Const(f32::from_bits($arg.bits())));
+        (build F64Const $arg:ident) => (I::F64Const(f64::from_bits($arg.bits())));
+        (build V128Const $arg:ident) => (I::V128Const($arg.i128()));
+        (build $op:ident $arg:ident) => (I::$op($arg));
+        (build CallIndirect $ty:ident $table:ident $_:ident) => (I::CallIndirect {
+            ty: $ty,
+            table: $table,
+        });
+        (build ReturnCallIndirect $ty:ident $table:ident) => (I::ReturnCallIndirect {
+            ty: $ty,
+            table: $table,
+        });
+        (build MemoryGrow $mem:ident $_:ident) => (I::MemoryGrow($mem));
+        (build MemorySize $mem:ident $_:ident) => (I::MemorySize($mem));
+        (build $op:ident $($arg:ident)*) => (I::$op { $($arg),* });
+    }
+
+    wasmparser::for_each_operator!(translate)
+}
+
+/// Translates the type of a block.
+pub fn block_type(t: &mut dyn Translator, ty: &wasmparser::BlockType) -> Result<BlockType> {
+    match ty {
+        wasmparser::BlockType::Empty => Ok(BlockType::Empty),
+        wasmparser::BlockType::Type(ty) => Ok(BlockType::Result(t.translate_ty(ty)?)),
+        wasmparser::BlockType::FuncType(f) => Ok(BlockType::FunctionType(t.remap(Item::Type, *f)?)),
+    }
+}
+
+/// Translates the immediate of a memory instruction.
+pub fn memarg(t: &mut dyn Translator, memarg: &wasmparser::MemArg) -> Result<MemArg> {
+    Ok(MemArg {
+        offset: memarg.offset,
+        align: memarg.align.into(),
+        memory_index: t.remap(Item::Memory, memarg.memory)?,
+    })
+}
+
+/// Translates the data segment `data` into `s`.
+pub fn data(t: &mut dyn Translator, data: wasmparser::Data<'_>, s: &mut DataSection) -> Result<()> {
+    let offset;
+    let mode = match &data.kind {
+        DataKind::Active {
+            memory_index,
+            offset_expr,
+        } => {
+            offset = t.translate_const_expr(
+                offset_expr,
+                &wasmparser::ValType::I32,
+                ConstExprKind::DataOffset,
+            )?;
+            DataSegmentMode::Active {
+                memory_index: t.remap(Item::Memory, *memory_index)?,
+                offset: &offset,
+            }
+        }
+        DataKind::Passive => DataSegmentMode::Passive,
+    };
+    s.segment(DataSegment {
+        mode,
+        data: data.data.iter().copied(),
+    });
+    Ok(())
+}
+
+/// Translates the function body `body` into `s`.
+pub fn code(t: &mut dyn Translator, body: FunctionBody<'_>, s: &mut CodeSection) -> Result<()> {
+    let locals = body
+        .get_locals_reader()?
+        .into_iter()
+        .map(|local| {
+            let (cnt, ty) = local?;
+            Ok((cnt, t.translate_ty(&ty)?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut func = Function::new(locals);
+
+    let mut reader = body.get_operators_reader()?;
+    reader.allow_memarg64(true);
+    for op in reader {
+        let op = op?;
+        func.instruction(&t.translate_op(&op)?);
+    }
+    s.function(&func);
+    Ok(())
+}
+
+/// Translates `section` into `module`, remapping the indices in the `name`
+/// section and copying any other custom section verbatim.
+pub fn custom_section(
+    t: &mut dyn Translator,
+    section: &wasmparser::CustomSectionReader<'_>,
+    module: &mut Module,
+) -> Result<()> {
+    if section.name() == "name" {
+        let reader = wasmparser::NameSectionReader::new(section.data(), section.data_offset())?;
+        module.section(&names(t, reader)?);
+    } else {
+        module.section(&CustomSection {
+            name: section.name(),
+            data: section.data(),
+        });
+    }
+    Ok(())
+}
+
+/// Translates the `name` custom section, remapping the indices of each named
+/// item through `t`.
+pub fn names(
+    t: &mut dyn Translator,
+    reader: wasmparser::NameSectionReader<'_>,
+) -> Result<NameSection> {
+    remap_names_with(reader, &mut |item, idx| t.remap(item, idx).map(Some))
+}
+
+/// Rewrites the `name` section for a module whose functions have been
+/// renumbered according to `old_to_new`.
+///
+/// Names of functions, along with their local and label names, are moved to
+/// the function's new index. Functions which aren't present in `old_to_new`
+/// are assumed to have been removed and their names are dropped. Names of
+/// other items are left as-is.
+pub fn remap_names(
+    reader: wasmparser::NameSectionReader<'_>,
+    old_to_new: &HashMap<u32, u32>,
+) -> Result<NameSection> {
+    remap_item_names(reader, Item::Function, old_to_new)
+}
+
+/// Same as [`remap_names`], but for a module whose items in the `item` index
+/// space have been renumbered rather than its functions.
+pub fn remap_item_names(
+    reader: wasmparser::NameSectionReader<'_>,
+    item: Item,
+    old_to_new: &HashMap<u32, u32>,
+) -> Result<NameSection> {
+    remap_names_with(reader, &mut |i, idx| {
+        if i == item {
+            Ok(old_to_new.get(&idx).copied())
+        } else {
+            Ok(Some(idx))
+        }
+    })
+}
+
+/// A remapping of the indices of items, returning `None` for items which have
+/// been removed.
+pub type Remap<'a> = dyn FnMut(Item, u32) -> Result<Option<u32>> + 'a;
+
+/// Rewrites the `name` section with the indices of every item remapped by
+/// `remap`, dropping the names of items for which it returns `None`.
+pub fn remap_names_with(
+    reader: wasmparser::NameSectionReader<'_>,
+    remap: &mut Remap<'_>,
+) -> Result<NameSection> {
+    let mut section = NameSection::new();
+    for name in reader {
+        match name? {
+            Name::Module(name) => section.module(name.get_name()?),
+            Name::Function(map) => section.functions(&name_map(remap, Item::Function, map)?),
+            Name::Local(map) => section.locals(&indirect_name_map(remap, map)?),
+            Name::Label(map) => section.labels(&indirect_name_map(remap, map)?),
+            Name::Type(map) => section.types(&name_map(remap, Item::Type, map)?),
+            Name::Table(map) => section.tables(&name_map(remap, Item::Table, map)?),
+            Name::Memory(map) => section.memories(&name_map(remap, Item::Memory, map)?),
+            Name::Global(map) => section.globals(&name_map(remap, Item::Global, map)?),
+            Name::Element(map) => section.elements(&name_map(remap, Item::Element, map)?),
+            Name::Data(map) => section.data(&name_map(remap, Item::Data, map)?),
+            Name::Unknown { ty, data, .. } => section.raw(ty, data),
+        }
+    }
+    Ok(section)
+}
+
+fn name_map(remap: &mut Remap<'_>, item: Item, map: wasmparser::NameMap<'_>) -> Result<NameMap> {
+    let mut reader = map.get_map()?;
+    let mut names = Vec::new();
+    for _ in 0..reader.get_count() {
+        let naming = reader.read()?;
+        if let Some(idx) = remap(item, naming.index)? {
+            names.push((idx, naming.name));
+        }
+    }
+    // Remapping may reorder indices, or map several items to the same index,
+    // but name maps must be sorted by index and name each item at most once.
+    names.sort_by_key(|(idx, _)| *idx);
+    names.dedup_by_key(|(idx, _)| *idx);
+    let mut ret = NameMap::new();
+    for (idx, name) in names {
+        ret.append(idx, name);
+    }
+    Ok(ret)
+}
+
+fn indirect_name_map(
+    remap: &mut Remap<'_>,
+    map: wasmparser::IndirectNameMap<'_>,
+) -> Result<IndirectNameMap> {
+    let mut reader = map.get_indirect_map()?;
+    let mut funcs = Vec::new();
+    for _ in 0..reader.get_indirect_count() {
+        let naming = reader.read()?;
+        let func = match remap(Item::Function, naming.indirect_index)? {
+            Some(func) => func,
+            None => continue,
+        };
+        let mut names = NameMap::new();
+        let mut inner = naming.get_map()?;
+        for _ in 0..inner.get_count() {
+            let naming = inner.read()?;
+            names.append(naming.index, naming.name);
+        }
+        funcs.push((func, names));
+    }
+    funcs.sort_by_key(|(func, _)| *func);
+    funcs.dedup_by_key(|(func, _)| *func);
+    let mut ret = IndirectNameMap::new();
+    for (func, names) in funcs.iter() {
+        ret.append(*func, names);
+    }
+    Ok(ret)
+}
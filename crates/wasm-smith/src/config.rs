@@ -1,8 +1,9 @@
 //! Configuring the shape of generated Wasm modules.
 
-use crate::InstructionKinds;
+use crate::{InstructionKind, InstructionKinds};
 use arbitrary::{Arbitrary, Result, Unstructured};
 use std::borrow::Cow;
+use std::collections::HashMap;
 
 /// Configuration for a generated module.
 ///
@@ -135,6 +136,17 @@ pub trait Config: 'static + std::fmt::Debug {
         false
     }
 
+    /// Whether the names of imports and exports are derived from the kind
+    /// and index of the item they refer to, such as `func3` or `global1`,
+    /// rather than generated arbitrarily. Imports are all from the `env`
+    /// module. Defaults to false.
+    ///
+    /// Modules with the same structure then always have the same names,
+    /// which keeps generated modules stable and readable when debugging.
+    fn deterministic_names(&self) -> bool {
+        false
+    }
+
     /// The minimum number of element segments to generate. Defaults to 0.
     fn min_element_segments(&self) -> usize {
         0
@@ -431,6 +443,23 @@ pub trait Config: 'static + std::fmt::Debug {
         InstructionKinds::all()
     }
 
+    /// Returns how much to bias the choice of each kind of instruction in the
+    /// generated wasm programs.
+    ///
+    /// Each instruction which is valid at a given point is chosen with a
+    /// probability proportional to the bias of its kind, so a bias of 10.0
+    /// for [`InstructionKind::Vector`] makes vector instructions roughly ten
+    /// times as likely to be chosen as they otherwise would be. Kinds which
+    /// aren't in the map have a bias of 1.0, and biases are clamped to at
+    /// most 1000.0. A bias of 0.0 or less excludes the kind, as if it weren't
+    /// in [`Config::allowed_instructions`]. Biases only change which valid
+    /// instructions are chosen, so the generated programs are still valid.
+    ///
+    /// Defaults to an empty map.
+    fn instruction_bias(&self) -> HashMap<InstructionKind, f32> {
+        HashMap::new()
+    }
+
     /// Returns whether we should generate custom sections or not.
     ///
     /// This is false by default.
@@ -476,6 +505,7 @@ pub struct SwarmConfig {
     pub available_imports: Option<Vec<u8>>,
     pub bulk_memory_enabled: bool,
     pub canonicalize_nans: bool,
+    pub deterministic_names: bool,
     pub exceptions_enabled: bool,
     pub export_everything: bool,
     pub max_aliases: usize,
@@ -521,6 +551,7 @@ pub struct SwarmConfig {
     pub simd_enabled: bool,
     pub threads_enabled: bool,
     pub allowed_instructions: InstructionKinds,
+    pub instruction_bias: HashMap<InstructionKind, f32>,
     pub max_table_elements: u32,
     pub table_max_size_required: bool,
 }
@@ -594,9 +625,11 @@ impl<'a> Arbitrary<'a> for SwarmConfig {
             memory64_enabled: false,
             max_type_size: 1000,
             canonicalize_nans: false,
+            deterministic_names: false,
             available_imports: None,
             threads_enabled: false,
             export_everything: false,
+            instruction_bias: HashMap::new(),
         })
     }
 }
@@ -652,6 +685,10 @@ impl Config for SwarmConfig {
         self.export_everything
     }
 
+    fn deterministic_names(&self) -> bool {
+        self.deterministic_names
+    }
+
     fn min_element_segments(&self) -> usize {
         self.min_element_segments
     }
@@ -788,6 +825,10 @@ impl Config for SwarmConfig {
         self.allowed_instructions
     }
 
+    fn instruction_bias(&self) -> HashMap<InstructionKind, f32> {
+        self.instruction_bias.clone()
+    }
+
     fn max_table_elements(&self) -> u32 {
         self.max_table_elements
     }
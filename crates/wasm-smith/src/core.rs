@@ -497,8 +497,21 @@ impl Module {
             }
             self.type_size += entity_type.size() + 1;
 
-            // Generate an arbitrary module/name pair to name this import.
-            let mut import_pair = unique_import_strings(1_000, u)?;
+            // Generate an arbitrary module/name pair to name this import, or
+            // derive one from the index the item will have.
+            let mut import_pair = if self.config.deterministic_names() {
+                let (kind, idx) = match &entity_type {
+                    EntityType::Tag(_) => (ExportKind::Tag, self.tags.len()),
+                    EntityType::Func(..) => (ExportKind::Func, self.funcs.len()),
+                    EntityType::Global(_) => (ExportKind::Global, self.globals.len()),
+                    EntityType::Table(_) => (ExportKind::Table, self.tables.len()),
+                    EntityType::Memory(_) => (ExportKind::Memory, self.memories.len()),
+                };
+                let name = deterministic_name(kind, idx as u32, &mut HashSet::new());
+                ("env".to_string(), name)
+            } else {
+                unique_import_strings(1_000, u)?
+            };
             if self.duplicate_imports_behavior == DuplicateImportsBehavior::Disallowed {
                 while import_strings.contains(&import_pair) {
                     use std::fmt::Write;
@@ -676,6 +689,7 @@ impl Module {
                         maximum: memory_ty.maximum,
                         memory64: memory_ty.memory64,
                         shared: memory_ty.shared,
+                        page_size_log2: memory_ty.page_size_log2,
                     };
                     let entity = EntityType::Memory(memory_ty);
                     let type_size = entity.size();
@@ -945,7 +959,11 @@ impl Module {
         if self.config.export_everything() {
             for choices_by_kind in choices {
                 for (kind, idx) in choices_by_kind {
-                    let name = unique_string(1_000, &mut export_names, u)?;
+                    let name = if self.config.deterministic_names() {
+                        deterministic_name(kind, idx, &mut export_names)
+                    } else {
+                        unique_string(1_000, &mut export_names, u)?
+                    };
                     self.add_arbitrary_export(name, kind, idx)?;
                 }
             }
@@ -972,10 +990,15 @@ impl Module {
                 }
 
                 // Pick a name, then pick the export, and then we can record
-                // information about the chosen export.
-                let name = unique_string(1_000, &mut export_names, u)?;
+                // information about the chosen export. Deterministic names
+                // are instead derived from the chosen export.
+                let name = match self.config.deterministic_names() {
+                    true => None,
+                    false => Some(unique_string(1_000, &mut export_names, u)?),
+                };
                 let list = u.choose(&choices)?;
                 let (kind, idx) = *u.choose(list)?;
+                let name = name.unwrap_or_else(|| deterministic_name(kind, idx, &mut export_names));
                 self.add_arbitrary_export(name, kind, idx)?;
                 Ok(true)
             },
@@ -1409,6 +1432,7 @@ pub(crate) fn arbitrary_memtype(u: &mut Unstructured, config: &dyn Config) -> Re
         maximum,
         memory64,
         shared,
+        page_size_log2: None,
     })
 }
 
@@ -1556,6 +1580,28 @@ fn unique_import_strings(max_size: usize, u: &mut Unstructured) -> Result<(Strin
     Ok((module, field))
 }
 
+/// Returns a name for the item `idx` of kind `kind` which isn't in `names`,
+/// such as `func3`, and adds it to `names`.
+fn deterministic_name(kind: ExportKind, idx: u32, names: &mut HashSet<String>) -> String {
+    let kind = match kind {
+        ExportKind::Func => "func",
+        ExportKind::Table => "table",
+        ExportKind::Memory => "memory",
+        ExportKind::Global => "global",
+        ExportKind::Tag => "tag",
+    };
+    let mut name = format!("{}{}", kind, idx);
+    // The same item may be exported more than once, in which case the later
+    // exports get a suffix.
+    let mut suffix = 1;
+    while names.contains(&name) {
+        name = format!("{}{}_{}", kind, idx, suffix);
+        suffix += 1;
+    }
+    names.insert(name.clone());
+    name
+}
+
 fn arbitrary_vec_u8(u: &mut Unstructured) -> Result<Vec<u8>> {
     let size = u.arbitrary_len::<u8>()?;
     Ok(u.bytes(size)?.to_vec())
@@ -1637,6 +1683,7 @@ flags! {
     /// Enumerate the categories of instructions defined in the [WebAssembly
     /// specification](https://webassembly.github.io/spec/core/syntax/instructions.html).
     #[allow(missing_docs)]
+    #[derive(Hash)]
     #[cfg_attr(feature = "_internal_cli", derive(serde::Deserialize))]
     pub enum InstructionKind: u16 {
         Numeric,
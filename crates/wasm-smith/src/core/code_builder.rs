@@ -1,9 +1,9 @@
 use super::{
-    Elements, FuncType, GlobalInitExpr, Instruction, InstructionKind::*, InstructionKinds, Module,
-    ValType,
+    Elements, FuncType, GlobalInitExpr, Instruction, InstructionKind, InstructionKind::*,
+    InstructionKinds, Module, ValType,
 };
 use arbitrary::{Result, Unstructured};
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryFrom;
 use wasm_encoder::{BlockType, MemArg};
 
@@ -22,6 +22,7 @@ macro_rules! instructions {
             u: &mut Unstructured<'_>,
             module: &Module,
             allowed_instructions: InstructionKinds,
+            instruction_bias: &InstructionBias,
             builder: &mut CodeBuilder,
         ) -> Option<
             fn(&mut Unstructured<'_>, &Module, &mut CodeBuilder) -> Result<Instruction>
@@ -38,8 +39,11 @@ macro_rules! instructions {
                 let predicate: Option<fn(&Module, &mut CodeBuilder) -> bool> = $predicate;
                 if predicate.map_or(true, |f| f(module, builder))
                     && allowed_instructions.contains($instruction_kind) {
-                    builder.allocs.options.push(($generator_fn, cost));
-                    cost += 1000 $(- $cost)?;
+                    let weight = instruction_bias.weight($instruction_kind, 1000 $(- $cost)?);
+                    if weight > 0 {
+                        builder.allocs.options.push(($generator_fn, cost));
+                        cost += weight;
+                    }
                 }
             )*
 
@@ -68,6 +72,43 @@ macro_rules! instructions {
     };
 }
 
+/// The bias of each kind of instruction, from [`Config::instruction_bias`].
+///
+/// [`Config::instruction_bias`]: crate::Config::instruction_bias
+struct InstructionBias([f32; 8]);
+
+impl InstructionBias {
+    fn new(bias: &HashMap<InstructionKind, f32>) -> InstructionBias {
+        let mut ret = InstructionBias([1.0; 8]);
+        for (kind, bias) in bias {
+            // Note that a NaN bias stays NaN, which converts to a weight of 0
+            // below.
+            ret.0[Self::index(*kind)] = bias.clamp(0.0, 1000.0);
+        }
+        ret
+    }
+
+    fn index(kind: InstructionKind) -> usize {
+        match kind {
+            Numeric => 0,
+            Vector => 1,
+            Reference => 2,
+            Parametric => 3,
+            Variable => 4,
+            Table => 5,
+            Memory => 6,
+            Control => 7,
+        }
+    }
+
+    /// Returns the weight of an instruction of `kind` whose weight is `weight`
+    /// without any bias.
+    #[inline]
+    fn weight(&self, kind: InstructionKind, weight: u32) -> u32 {
+        (weight as f32 * self.0[Self::index(kind)]) as u32
+    }
+}
+
 // The static set of options of instruction to generate that could be valid at
 // some given time. One entry per Wasm instruction.
 //
@@ -837,6 +878,7 @@ impl CodeBuilder<'_> {
     ) -> Result<Vec<Instruction>> {
         let max_instructions = module.config.max_instructions();
         let allowed_instructions = module.config.allowed_instructions();
+        let instruction_bias = InstructionBias::new(&module.config.instruction_bias());
         let mut instructions = vec![];
 
         while !self.allocs.controls.is_empty() {
@@ -847,7 +889,13 @@ impl CodeBuilder<'_> {
                 break;
             }
 
-            match choose_instruction(u, module, allowed_instructions, &mut self) {
+            match choose_instruction(
+                u,
+                module,
+                allowed_instructions,
+                &instruction_bias,
+                &mut self,
+            ) {
                 Some(f) => {
                     let inst = f(u, module, &mut self)?;
                     instructions.push(inst);
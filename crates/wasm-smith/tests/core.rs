@@ -1,7 +1,10 @@
 use arbitrary::{Arbitrary, Unstructured};
+use flagset::Flags;
 use rand::{rngs::SmallRng, RngCore, SeedableRng};
 use std::collections::HashMap;
-use wasm_smith::{Config, ConfiguredModule, Module, SwarmConfig};
+use wasm_smith::{
+    Config, ConfiguredModule, InstructionKind, InstructionKinds, Module, SwarmConfig,
+};
 use wasmparser::{Parser, TypeRef, ValType, Validator, WasmFeatures};
 
 #[test]
@@ -37,6 +40,57 @@ fn smoke_test_ensure_termination() {
     }
 }
 
+#[test]
+fn ensure_termination_checks_fuel_in_every_loop() {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let mut buf = vec![0; 2048];
+    let mut loops = 0;
+    for _ in 0..1024 {
+        rng.fill_bytes(&mut buf);
+        let u = Unstructured::new(&buf);
+        let mut module = match Module::arbitrary_take_rest(u) {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        let fuel = module.ensure_termination(10);
+        let wasm_bytes = module.to_bytes();
+        let mut validator = Validator::new_with_features(wasm_features());
+        validate(&mut validator, &wasm_bytes);
+
+        for payload in Parser::new(0).parse_all(&wasm_bytes) {
+            let body = match payload.unwrap() {
+                wasmparser::Payload::CodeSectionEntry(body) => body,
+                _ => continue,
+            };
+            let mut reader = body.get_operators_reader().unwrap();
+            reader.allow_memarg64(true);
+            let ops = reader.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+
+            // Fuel is checked at the start of each function and at the head
+            // of each loop.
+            assert_fuel_check(&ops, fuel);
+            for (i, op) in ops.iter().enumerate() {
+                if let wasmparser::Operator::Loop { .. } = op {
+                    assert_fuel_check(&ops[i + 1..], fuel);
+                    loops += 1;
+                }
+            }
+        }
+    }
+    assert!(loops > 0);
+}
+
+/// Asserts that `ops` starts by trapping if the `fuel` global is zero and
+/// otherwise decrementing it.
+fn assert_fuel_check(ops: &[wasmparser::Operator<'_>], fuel: u32) {
+    use wasmparser::Operator::*;
+    match ops {
+        [GlobalGet { global_index: a }, I32Eqz, If { .. }, Unreachable, End, GlobalGet { global_index: b }, I32Const { value: 1 }, I32Sub, GlobalSet { global_index: c }, ..]
+            if [*a, *b, *c] == [fuel; 3] => {}
+        _ => panic!("missing fuel check: {:?}", &ops[..ops.len().min(9)]),
+    }
+}
+
 #[test]
 fn smoke_test_swarm_config() {
     let mut rng = SmallRng::seed_from_u64(0);
@@ -214,6 +268,265 @@ fn smoke_test_no_trapping_mode() {
     }
 }
 
+#[test]
+fn smoke_test_multi_memory_and_memory64() {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let mut buf = vec![0; 2048];
+    let mut saw_multiple_memories = false;
+    let mut saw_memory64 = false;
+    let mut saw_nonzero_memory_index = false;
+    let mut saw_offset64 = false;
+    for _ in 0..1024 {
+        rng.fill_bytes(&mut buf);
+        let mut u = Unstructured::new(&buf);
+        let mut cfg = SwarmConfig::arbitrary(&mut u).unwrap();
+        cfg.min_memories = 2;
+        cfg.max_memories = 4;
+        cfg.memory64_enabled = true;
+        let features = parser_features_from_config(&cfg);
+        let module = match Module::new(cfg, &mut u) {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        let wasm_bytes = module.to_bytes();
+        let mut validator = Validator::new_with_features(features);
+        validate(&mut validator, &wasm_bytes);
+
+        let mut memories64 = Vec::new();
+        for payload in Parser::new(0).parse_all(&wasm_bytes) {
+            let body = match payload.unwrap() {
+                wasmparser::Payload::ImportSection(reader) => {
+                    for import in reader {
+                        if let TypeRef::Memory(ty) = import.unwrap().ty {
+                            memories64.push(ty.memory64);
+                        }
+                    }
+                    continue;
+                }
+                wasmparser::Payload::MemorySection(reader) => {
+                    for ty in reader {
+                        memories64.push(ty.unwrap().memory64);
+                    }
+                    continue;
+                }
+                wasmparser::Payload::CodeSectionEntry(body) => body,
+                _ => continue,
+            };
+            let mut reader = body.get_operators_reader().unwrap();
+            reader.allow_memarg64(true);
+            for op in reader {
+                use wasmparser::Operator::*;
+                let memarg = match op.unwrap() {
+                    I32Load { memarg }
+                    | I64Load { memarg }
+                    | F32Load { memarg }
+                    | F64Load { memarg }
+                    | I32Load8S { memarg }
+                    | I32Load8U { memarg }
+                    | I32Load16S { memarg }
+                    | I32Load16U { memarg }
+                    | I64Load8S { memarg }
+                    | I64Load8U { memarg }
+                    | I64Load16S { memarg }
+                    | I64Load16U { memarg }
+                    | I64Load32S { memarg }
+                    | I64Load32U { memarg }
+                    | I32Store { memarg }
+                    | I64Store { memarg }
+                    | F32Store { memarg }
+                    | F64Store { memarg }
+                    | I32Store8 { memarg }
+                    | I32Store16 { memarg }
+                    | I64Store8 { memarg }
+                    | I64Store16 { memarg }
+                    | I64Store32 { memarg } => memarg,
+                    _ => continue,
+                };
+                saw_nonzero_memory_index |= memarg.memory != 0;
+                saw_offset64 |= memarg.offset > u64::from(u32::MAX);
+                assert!(memories64[memarg.memory as usize] || memarg.offset <= u64::from(u32::MAX));
+            }
+        }
+        saw_multiple_memories |= memories64.len() > 1;
+        saw_memory64 |= memories64.iter().any(|m| *m);
+    }
+    assert!(saw_multiple_memories);
+    assert!(saw_memory64);
+    assert!(saw_nonzero_memory_index);
+    assert!(saw_offset64);
+}
+
+#[test]
+fn smoke_test_deterministic_names() {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let mut buf = vec![0; 2048];
+    let mut saw_imports = false;
+    let mut saw_exports = false;
+    for _ in 0..1024 {
+        rng.fill_bytes(&mut buf);
+        let mut u = Unstructured::new(&buf);
+        let mut cfg = SwarmConfig::arbitrary(&mut u).unwrap();
+        cfg.deterministic_names = true;
+        let features = parser_features_from_config(&cfg);
+        let module = match Module::new(cfg, &mut u) {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        let wasm_bytes = module.to_bytes();
+        let mut validator = Validator::new_with_features(features);
+        validate(&mut validator, &wasm_bytes);
+
+        // Every name is derived from the kind and index of its item, so
+        // modules with the same structure have the same names.
+        let mut imported = HashMap::new();
+        for payload in Parser::new(0).parse_all(&wasm_bytes) {
+            match payload.unwrap() {
+                wasmparser::Payload::ImportSection(reader) => {
+                    for import in reader {
+                        let import = import.unwrap();
+                        let kind = match import.ty {
+                            TypeRef::Func(_) => "func",
+                            TypeRef::Table(_) => "table",
+                            TypeRef::Memory(_) => "memory",
+                            TypeRef::Global(_) => "global",
+                            TypeRef::Tag(_) => "tag",
+                        };
+                        let idx = imported.entry(kind).or_insert(0);
+                        assert_eq!(import.module, "env");
+                        assert_eq!(import.name, format!("{}{}", kind, idx));
+                        *idx += 1;
+                        saw_imports = true;
+                    }
+                }
+                wasmparser::Payload::ExportSection(reader) => {
+                    for export in reader {
+                        let export = export.unwrap();
+                        let kind = match export.kind {
+                            wasmparser::ExternalKind::Func => "func",
+                            wasmparser::ExternalKind::Table => "table",
+                            wasmparser::ExternalKind::Memory => "memory",
+                            wasmparser::ExternalKind::Global => "global",
+                            wasmparser::ExternalKind::Tag => "tag",
+                        };
+                        let name = format!("{}{}", kind, export.index);
+                        assert!(
+                            export.name == name || export.name.starts_with(&format!("{}_", name)),
+                            "export `{}` of {} {}",
+                            export.name,
+                            kind,
+                            export.index
+                        );
+                        saw_exports = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    assert!(saw_imports);
+    assert!(saw_exports);
+}
+
+#[test]
+fn smoke_test_instruction_bias() {
+    #[derive(Debug, Clone)]
+    struct BiasConfig {
+        bias: HashMap<InstructionKind, f32>,
+        allowed: InstructionKinds,
+    }
+
+    impl Config for BiasConfig {
+        fn simd_enabled(&self) -> bool {
+            true
+        }
+
+        fn allowed_instructions(&self) -> InstructionKinds {
+            self.allowed
+        }
+
+        fn instruction_bias(&self) -> HashMap<InstructionKind, f32> {
+            self.bias.clone()
+        }
+    }
+
+    // Returns the modules generated with `config` along with how many of
+    // their operators are SIMD operators and how many there are in total.
+    let generate = |config: BiasConfig| {
+        let features = parser_features_from_config(&config);
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut buf = vec![0; 2048];
+        let mut modules = Vec::new();
+        let (mut simd, mut total) = (0, 0);
+        for _ in 0..256 {
+            rng.fill_bytes(&mut buf);
+            let mut u = Unstructured::new(&buf);
+            let module = match Module::new(config.clone(), &mut u) {
+                Ok(m) => m,
+                Err(_) => continue,
+            };
+            let wasm_bytes = module.to_bytes();
+            let mut validator = Validator::new_with_features(features);
+            validate(&mut validator, &wasm_bytes);
+
+            for payload in Parser::new(0).parse_all(&wasm_bytes) {
+                if let wasmparser::Payload::CodeSectionEntry(body) = payload.unwrap() {
+                    let mut reader = body.get_operators_reader().unwrap();
+                    while !reader.eof() {
+                        let op = format!("{:?}", reader.read().unwrap());
+                        if ["V128", "I8x16", "I16x8", "I32x4", "I64x2", "F32x4", "F64x2"]
+                            .iter()
+                            .any(|prefix| op.starts_with(prefix))
+                        {
+                            simd += 1;
+                        }
+                        total += 1;
+                    }
+                }
+            }
+            modules.push(wasm_bytes);
+        }
+        (modules, simd, total)
+    };
+    let config = |bias: &[(InstructionKind, f32)], allowed| BiasConfig {
+        bias: bias.iter().copied().collect(),
+        allowed,
+    };
+
+    let (modules, simd, total) = generate(config(&[], InstructionKinds::all()));
+    let (_, biased_simd, biased_total) = generate(config(
+        &[(InstructionKind::Vector, 100.0)],
+        InstructionKinds::all(),
+    ));
+    assert!(total > 0 && biased_total > 0);
+    let ratio = simd as f64 / total as f64;
+    let biased_ratio = biased_simd as f64 / biased_total as f64;
+    assert!(
+        biased_ratio > ratio * 2.0 && biased_ratio > 0.5,
+        "{} of {} operators were SIMD without bias, and {} of {} with bias",
+        simd,
+        total,
+        biased_simd,
+        biased_total
+    );
+
+    // A bias of 1.0 is the same as no bias at all.
+    let (unbiased, _, _) = generate(config(
+        &[(InstructionKind::Vector, 1.0)],
+        InstructionKinds::all(),
+    ));
+    assert_eq!(unbiased, modules);
+
+    // A bias of 0.0 is the same as not allowing the kind of instruction.
+    let mut kinds = InstructionKind::LIST.to_vec();
+    kinds.retain(|k| *k != InstructionKind::Vector);
+    let (excluded, _, _) = generate(config(&[], InstructionKinds::new(&kinds)));
+    let (zero, _, _) = generate(config(
+        &[(InstructionKind::Vector, 0.0)],
+        InstructionKinds::all(),
+    ));
+    assert_eq!(zero, excluded);
+}
+
 fn wasm_features() -> WasmFeatures {
     WasmFeatures {
         multi_memory: true,
@@ -298,6 +611,7 @@ fn parser_features_from_config(config: &impl Config) -> WasmFeatures {
         deterministic_only: false,
         extended_const: false,
         component_model: false,
+        custom_page_sizes: false,
     }
 }
 
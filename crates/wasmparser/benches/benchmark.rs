@@ -232,6 +232,7 @@ fn define_benchmarks(c: &mut Criterion) {
             relaxed_simd: true,
             exceptions: true,
             component_model: true,
+            custom_page_sizes: true,
             bulk_memory: true,
             threads: true,
             tail_call: true,
@@ -789,10 +789,11 @@ impl<'a> BinaryReader<'a> {
     pub(crate) fn read_memory_type(&mut self) -> Result<MemoryType> {
         let pos = self.original_position();
         let flags = self.read_u8()?;
-        if (flags & !0b111) != 0 {
+        if (flags & !0b1111) != 0 {
             return Err(BinaryReaderError::new("invalid memory limits flags", pos));
         }
 
+        let has_page_size = flags & 0b1000 != 0;
         let memory64 = flags & 0b100 != 0;
         let shared = flags & 0b010 != 0;
         let has_max = flags & 0b001 != 0;
@@ -818,6 +819,11 @@ impl<'a> BinaryReader<'a> {
             } else {
                 Some(self.read_var_u32()?.into())
             },
+            page_size_log2: if has_page_size {
+                Some(self.read_var_u32()?)
+            } else {
+                None
+            },
         })
     }
 
@@ -2138,19 +2144,6 @@ impl<'a> BinaryReader<'a> {
         }
     }
 
-    pub(crate) fn read_linking_type(&mut self) -> Result<LinkingType> {
-        let ty = self.read_var_u32()?;
-        Ok(match ty {
-            1 => LinkingType::StackPointer(self.read_var_u32()?),
-            _ => {
-                return Err(BinaryReaderError::new(
-                    "invalid linking type",
-                    self.original_position() - 1,
-                ));
-            }
-        })
-    }
-
     pub(crate) fn read_reloc_type(&mut self) -> Result<RelocType> {
         let code = self.read_u7()?;
         match code {
@@ -41,6 +41,7 @@ pub struct Parser {
     offset: u64,
     max_size: u64,
     encoding: Encoding,
+    reject_unknown_sections: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -320,9 +321,26 @@ impl Parser {
             max_size: u64::MAX,
             // Assume the encoding is a module until we know otherwise
             encoding: Encoding::Module,
+            reject_unknown_sections: false,
         }
     }
 
+    /// Configures whether sections with unknown ids are rejected.
+    ///
+    /// By default sections with ids that this parser doesn't recognize are
+    /// returned as [`Payload::UnknownSection`], leaving it to the caller to
+    /// decide what to do with them. When `reject` is `true` an error is
+    /// returned instead, for consumers which only want to accept a closed set
+    /// of sections. This setting is inherited by the parsers of nested modules
+    /// and components.
+    ///
+    /// Note that [`Validator`](crate::Validator) always rejects unknown
+    /// sections regardless of this setting.
+    pub fn reject_unknown_sections(&mut self, reject: bool) -> &mut Parser {
+        self.reject_unknown_sections = reject;
+        self
+    }
+
     /// Attempts to parse a chunk of data.
     ///
     /// This method will attempt to parse the next incremental portion of a
@@ -539,6 +557,7 @@ impl Parser {
                 }
 
                 let id_pos = reader.position;
+                let id_offset = reader.original_position();
                 let id = reader.read_u8()?;
                 if id & 0x80 != 0 {
                     return Err(BinaryReaderError::new("malformed section id", id_pos));
@@ -638,6 +657,7 @@ impl Parser {
                         self.offset += u64::from(len);
                         let mut parser = Parser::new(usize_to_u64(reader.original_position()));
                         parser.max_size = len.into();
+                        parser.reject_unknown_sections = self.reject_unknown_sections;
 
                         Ok(match id {
                             1 => ModuleSection { parser, range },
@@ -694,6 +714,9 @@ impl Parser {
                         ComponentExportSection,
                     ),
                     (_, id) => {
+                        if self.reject_unknown_sections {
+                            bail!(id_offset, "malformed section id: {}", id);
+                        }
                         let offset = reader.original_position();
                         let contents = reader.read_bytes(len as usize)?;
                         let range = offset..offset + len as usize;
@@ -1501,4 +1524,36 @@ mod tests {
             "section too large",
         );
     }
+
+    #[test]
+    fn reject_unknown_sections() {
+        // A section with the bogus id 100 containing a single byte.
+        let bogus = [100, 1, 0];
+        match parser_after_header().parse(&bogus, false) {
+            Ok(Chunk::Parsed {
+                consumed: 3,
+                payload: Payload::UnknownSection { id: 100, .. },
+            }) => {}
+            other => panic!("bad parse {:?}", other),
+        }
+
+        let mut p = parser_after_header();
+        p.reject_unknown_sections(true);
+        let err = p.parse(&bogus, false).unwrap_err();
+        assert_eq!(err.message(), "malformed section id: 100");
+        assert_eq!(err.offset(), 8);
+
+        // The setting is inherited by nested modules.
+        let mut p = parser_after_component_header();
+        p.reject_unknown_sections(true);
+        let mut sub = match p.parse(&[1, 11], false) {
+            Ok(Chunk::Parsed {
+                payload: Payload::ModuleSection { parser, .. },
+                ..
+            }) => parser,
+            other => panic!("bad parse {:?}", other),
+        };
+        assert_matches!(sub.parse(b"\0asm\x01\0\0\0", false), Ok(_));
+        assert!(sub.parse(&bogus, false).is_err());
+    }
 }
@@ -1,4 +1,6 @@
+mod build_id;
 mod code;
+mod coredumps;
 mod custom;
 mod data;
 mod elements;
@@ -17,7 +19,9 @@ mod tables;
 mod tags;
 mod types;
 
+pub use self::build_id::*;
 pub use self::code::*;
+pub use self::coredumps::*;
 pub use self::custom::*;
 pub use self::data::*;
 pub use self::elements::*;
@@ -0,0 +1,43 @@
+use crate::{BinaryReader, BinaryReaderError, Result};
+
+/// The data portion of a `build_id` custom section, which contains an
+/// identifier for the build that produced a module such as a hash of its
+/// inputs. Per the tool-conventions repo the section contains the id as a
+/// vector of bytes.
+///
+/// # Examples
+///
+/// ```
+/// use wasmparser::BuildIdSection;
+/// let data: &[u8] = &[0x04, 0xde, 0xad, 0xbe, 0xef];
+/// let section = BuildIdSection::new(data, 0).unwrap();
+/// assert_eq!(section.id, [0xde, 0xad, 0xbe, 0xef]);
+/// assert_eq!(section.to_hex(), "deadbeef");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct BuildIdSection<'a> {
+    /// The bytes of the build id.
+    pub id: &'a [u8],
+}
+
+impl<'a> BuildIdSection<'a> {
+    /// Parses this section from the provided `data`, which starts at the
+    /// original `offset` of the input.
+    pub fn new(data: &'a [u8], offset: usize) -> Result<BuildIdSection<'a>> {
+        let mut reader = BinaryReader::new_with_offset(data, offset);
+        let len = reader.read_var_u32()? as usize;
+        let id = reader.read_bytes(len)?;
+        if !reader.eof() {
+            return Err(BinaryReaderError::new(
+                "unexpected trailing data in the build_id section",
+                reader.original_position(),
+            ));
+        }
+        Ok(BuildIdSection { id })
+    }
+
+    /// Returns the build id formatted as a lowercase hexadecimal string.
+    pub fn to_hex(&self) -> String {
+        self.id.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
@@ -0,0 +1,255 @@
+use crate::limits::{MAX_WASM_FUNCTION_LOCALS, MAX_WASM_GLOBALS, MAX_WASM_MEMORIES};
+use crate::{BinaryReader, BinaryReaderError, Ieee32, Ieee64, Result};
+
+/// The data portion of a custom section representing a core dump. Per the
+/// tool-conventions repo, this section just specifies the executable name that
+/// the core dump came from while the rest of the core dump information is
+/// contained in a corestack custom section.
+///
+/// # Examples
+///
+/// ```
+/// use wasmparser::CoreDumpSection;
+/// let data: &[u8] = &[0x00, 0x09, 0x74, 0x65, 0x73, 0x74, 0x2e, 0x77, 0x61,
+///      0x73, 0x6d];
+/// let core = CoreDumpSection::new(data, 0).unwrap();
+/// assert!(core.name == "test.wasm")
+/// ```
+pub struct CoreDumpSection<'a> {
+    /// The name of the process that created the core dump.
+    pub name: &'a str,
+}
+
+impl<'a> CoreDumpSection<'a> {
+    /// Parses this section from the provided `data`, which starts at the
+    /// original `offset` of the input.
+    pub fn new(data: &'a [u8], offset: usize) -> Result<CoreDumpSection<'a>> {
+        let mut reader = BinaryReader::new_with_offset(data, offset);
+        expect_byte(&mut reader, 0x0, "process-info")?;
+        let name = reader.read_string()?;
+        expect_eof(&reader, "core")?;
+        Ok(CoreDumpSection { name })
+    }
+}
+
+/// The data portion of a "coremodules" custom section. This contains a vec of
+/// module names that will be referenced by index by other coredump sections.
+///
+/// # Examples
+///
+/// ```
+/// use wasmparser::CoreDumpModulesSection;
+/// let data: &[u8] = &[0x01, 0x00, 0x04, 0x74, 0x65, 0x73, 0x74];
+/// let modules_section = CoreDumpModulesSection::new(data, 0).unwrap();
+/// assert!(modules_section.modules[0] == "test")
+/// ```
+#[derive(Debug)]
+pub struct CoreDumpModulesSection<'a> {
+    /// A list of module names, which may be URLs, file paths, or other
+    /// identifiers for the module.
+    pub modules: Vec<&'a str>,
+}
+
+impl<'a> CoreDumpModulesSection<'a> {
+    /// Parses this section from the provided `data`, which starts at the
+    /// original `offset` of the input.
+    pub fn new(data: &'a [u8], offset: usize) -> Result<CoreDumpModulesSection<'a>> {
+        let mut reader = BinaryReader::new_with_offset(data, offset);
+        let mut modules = Vec::new();
+        for _ in 0..read_count(&mut reader, MAX_WASM_MODULES, "modules")? {
+            expect_byte(&mut reader, 0x0, "module")?;
+            modules.push(reader.read_string()?);
+        }
+        expect_eof(&reader, "coremodules")?;
+        Ok(CoreDumpModulesSection { modules })
+    }
+}
+
+/// A custom section representing the instances involved in a given coredump.
+#[derive(Debug)]
+pub struct CoreDumpInstancesSection {
+    /// The instances for the coredump.
+    pub instances: Vec<CoreDumpInstance>,
+}
+
+impl CoreDumpInstancesSection {
+    /// Parses this section from the provided `data`, which starts at the
+    /// original `offset` of the input.
+    pub fn new(data: &[u8], offset: usize) -> Result<CoreDumpInstancesSection> {
+        let mut reader = BinaryReader::new_with_offset(data, offset);
+        let mut instances = Vec::new();
+        for _ in 0..read_count(&mut reader, MAX_WASM_INSTANCES, "instances")? {
+            expect_byte(&mut reader, 0x0, "instance")?;
+            let module_index = reader.read_var_u32()?;
+            let mut memories = Vec::new();
+            for _ in 0..read_count(&mut reader, MAX_WASM_MEMORIES, "memories")? {
+                memories.push(reader.read_var_u32()?);
+            }
+            let mut globals = Vec::new();
+            for _ in 0..read_count(&mut reader, MAX_WASM_GLOBALS, "globals")? {
+                globals.push(reader.read_var_u32()?);
+            }
+            instances.push(CoreDumpInstance {
+                module_index,
+                memories,
+                globals,
+            });
+        }
+        expect_eof(&reader, "coreinstances")?;
+        Ok(CoreDumpInstancesSection { instances })
+    }
+}
+
+/// A single instance from a coredump instances section.
+#[derive(Debug)]
+pub struct CoreDumpInstance {
+    /// The module that this is an instance of, as an index into a
+    /// "coremodules" section.
+    pub module_index: u32,
+
+    /// Which of the coredump's memories are this instance's memories, via
+    /// indexing into the memory index space.
+    pub memories: Vec<u32>,
+
+    /// Which of the coredump's globals are this instance's globals, via
+    /// indexing into the global index space.
+    pub globals: Vec<u32>,
+}
+
+/// The data portion of a custom section representing a core dump stack. The
+/// structure of this follows the coredump spec in the tool-conventions repo.
+///
+/// # Examples
+///
+/// ```
+/// use wasmparser::CoreDumpStackSection;
+///
+/// let data: &[u8] = &[0x00, 0x04, 0x6d, 0x61, 0x69, 0x6e, 0x01, 0x00, 0x04,
+///     0x2a, 0x33, 0x01, 0x7f, 0x01, 0x01, 0x7f, 0x02];
+/// let corestack = CoreDumpStackSection::new(data, 0).unwrap();
+/// assert!(corestack.name == "main");
+/// assert!(corestack.frames.len() == 1);
+/// let frame = &corestack.frames[0];
+/// assert!(frame.instanceidx == 4);
+/// assert!(frame.funcidx == 42);
+/// assert!(frame.codeoffset == 51);
+/// assert!(frame.locals.len() == 1);
+/// assert!(frame.stack.len() == 1);
+/// ```
+pub struct CoreDumpStackSection<'a> {
+    /// The thread name.
+    pub name: &'a str,
+    /// The stack frames for the core dump, with the innermost frame first.
+    pub frames: Vec<CoreDumpStackFrame>,
+}
+
+impl<'a> CoreDumpStackSection<'a> {
+    /// Parses this section from the provided `data`, which starts at the
+    /// original `offset` of the input.
+    pub fn new(data: &'a [u8], offset: usize) -> Result<CoreDumpStackSection<'a>> {
+        let mut reader = BinaryReader::new_with_offset(data, offset);
+        expect_byte(&mut reader, 0x0, "thread-info")?;
+        let name = reader.read_string()?;
+        let mut frames = Vec::new();
+        for _ in 0..read_count(&mut reader, MAX_WASM_FRAMES, "frames")? {
+            expect_byte(&mut reader, 0x0, "frame")?;
+            let instanceidx = reader.read_var_u32()?;
+            let funcidx = reader.read_var_u32()?;
+            let codeoffset = reader.read_var_u32()?;
+            let locals = read_values(&mut reader, "locals")?;
+            let stack = read_values(&mut reader, "stack values")?;
+            frames.push(CoreDumpStackFrame {
+                instanceidx,
+                funcidx,
+                codeoffset,
+                locals,
+                stack,
+            });
+        }
+        expect_eof(&reader, "corestack")?;
+        Ok(CoreDumpStackSection { name, frames })
+    }
+}
+
+/// A single stack frame from a core dump.
+#[derive(Debug)]
+pub struct CoreDumpStackFrame {
+    /// The instance that this stack frame belongs to.
+    pub instanceidx: u32,
+    /// The function index in the module.
+    pub funcidx: u32,
+    /// The instruction's offset relative to the function's start.
+    pub codeoffset: u32,
+    /// The locals for this stack frame, including function parameters.
+    pub locals: Vec<CoreDumpValue>,
+    /// The values on the stack.
+    pub stack: Vec<CoreDumpValue>,
+}
+
+/// Local and stack values are encoded using one byte for the type, similar to
+/// the binary format of the WebAssembly module, followed by bytes encoding the
+/// value.
+#[derive(Copy, Clone, Debug)]
+pub enum CoreDumpValue {
+    /// A missing value, usually because it was optimized out.
+    Missing,
+    /// An `i32` value.
+    I32(i32),
+    /// An `i64` value.
+    I64(i64),
+    /// An `f32` value.
+    F32(Ieee32),
+    /// An `f64` value.
+    F64(Ieee64),
+}
+
+// There are no limits on these in the coredump format, so these are chosen to
+// be far beyond what any real coredump contains to avoid huge allocations for
+// malformed input.
+const MAX_WASM_MODULES: usize = 100_000;
+const MAX_WASM_INSTANCES: usize = 100_000;
+const MAX_WASM_FRAMES: usize = 100_000;
+
+fn read_values(reader: &mut BinaryReader<'_>, desc: &str) -> Result<Vec<CoreDumpValue>> {
+    let mut values = Vec::new();
+    for _ in 0..read_count(reader, MAX_WASM_FUNCTION_LOCALS, desc)? {
+        let pos = reader.original_position();
+        values.push(match reader.read_u8()? {
+            0x01 => CoreDumpValue::Missing,
+            0x7f => CoreDumpValue::I32(reader.read_var_i32()?),
+            0x7e => CoreDumpValue::I64(reader.read_var_i64()?),
+            0x7d => CoreDumpValue::F32(reader.read_f32()?),
+            0x7c => CoreDumpValue::F64(reader.read_f64()?),
+            byte => bail!(pos, "invalid coredump value type {byte:#x}"),
+        });
+    }
+    Ok(values)
+}
+
+fn read_count(reader: &mut BinaryReader<'_>, limit: usize, desc: &str) -> Result<u32> {
+    let pos = reader.original_position();
+    let count = reader.read_var_u32()?;
+    if count as usize > limit {
+        bail!(pos, "number of coredump {desc} is out of bounds");
+    }
+    Ok(count)
+}
+
+fn expect_byte(reader: &mut BinaryReader<'_>, expected: u8, desc: &str) -> Result<()> {
+    let pos = reader.original_position();
+    let byte = reader.read_u8()?;
+    if byte != expected {
+        bail!(pos, "invalid start byte {byte:#x} for coredump {desc}");
+    }
+    Ok(())
+}
+
+fn expect_eof(reader: &BinaryReader<'_>, section: &str) -> Result<()> {
+    if !reader.eof() {
+        return Err(BinaryReaderError::new(
+            format!("unexpected trailing data in the coredump {section} section"),
+            reader.original_position(),
+        ));
+    }
+    Ok(())
+}
@@ -19,7 +19,7 @@ use std::ops::Range;
 /// External types as defined [here].
 ///
 /// [here]: https://webassembly.github.io/spec/core/syntax/types.html#external-types
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum ExternalKind {
     /// The external kind is a function.
     Func,
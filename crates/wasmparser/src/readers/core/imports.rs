@@ -14,8 +14,8 @@
  */
 
 use crate::{
-    BinaryReader, GlobalType, MemoryType, Result, SectionIteratorLimited, SectionReader,
-    SectionWithLimitedItems, TableType, TagType,
+    BinaryReader, Encoding, ExternalKind, GlobalType, MemoryType, Parser, Payload, Result,
+    SectionIteratorLimited, SectionReader, SectionWithLimitedItems, TableType, TagType,
 };
 use std::ops::Range;
 
@@ -127,3 +127,171 @@ impl<'a> IntoIterator for ImportSectionReader<'a> {
         SectionIteratorLimited::new(self)
     }
 }
+
+/// The number of imported items of each kind in a WebAssembly module.
+///
+/// In each index space of a module the imported items come first, in the
+/// order they're imported, followed by the items the module defines. This can
+/// be used to translate between indices of the index space and indices of
+/// defined items, such as the position of a function body within the code
+/// section.
+///
+/// # Examples
+///
+/// ```
+/// use wasmparser::{ExternalKind, ImportCounts};
+/// let wasm = wat::parse_str(r#"
+///     (module
+///         (import "env" "f" (func))
+///         (func)
+///         (func))
+/// "#).unwrap();
+/// let counts = ImportCounts::from_module(&wasm).unwrap();
+/// assert_eq!(counts.funcs, 1);
+/// assert!(counts.is_imported(ExternalKind::Func, 0));
+/// assert_eq!(counts.defined_index(ExternalKind::Func, 2), Some(1));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ImportCounts {
+    /// The number of imported functions.
+    pub funcs: u32,
+    /// The number of imported tables.
+    pub tables: u32,
+    /// The number of imported memories.
+    pub memories: u32,
+    /// The number of imported globals.
+    pub globals: u32,
+    /// The number of imported tags.
+    pub tags: u32,
+}
+
+impl ImportCounts {
+    /// Counts the imports of each kind in the import section `reader`.
+    pub fn from_reader(reader: ImportSectionReader<'_>) -> Result<ImportCounts> {
+        let mut counts = ImportCounts::default();
+        for import in reader {
+            match import?.ty {
+                TypeRef::Func(_) => counts.funcs += 1,
+                TypeRef::Table(_) => counts.tables += 1,
+                TypeRef::Memory(_) => counts.memories += 1,
+                TypeRef::Global(_) => counts.globals += 1,
+                TypeRef::Tag(_) => counts.tags += 1,
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Counts the imports of each kind in the core wasm module `wasm`.
+    ///
+    /// Only the sections up to and including the import section are parsed.
+    pub fn from_module(wasm: &[u8]) -> Result<ImportCounts> {
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::Version {
+                    encoding: Encoding::Component,
+                    range,
+                    ..
+                } => bail!(range.start, "expected a core module, found a component"),
+                Payload::ImportSection(reader) => return ImportCounts::from_reader(reader),
+                // The import section can only be preceded by the type section
+                // and custom sections.
+                Payload::Version { .. } | Payload::TypeSection(_) | Payload::CustomSection(_) => {}
+                _ => break,
+            }
+        }
+        Ok(ImportCounts::default())
+    }
+
+    /// Returns the number of imported items of the `kind` provided.
+    pub fn count(&self, kind: ExternalKind) -> u32 {
+        match kind {
+            ExternalKind::Func => self.funcs,
+            ExternalKind::Table => self.tables,
+            ExternalKind::Memory => self.memories,
+            ExternalKind::Global => self.globals,
+            ExternalKind::Tag => self.tags,
+        }
+    }
+
+    /// Returns whether `index`, in the index space of `kind`, refers to an
+    /// imported item.
+    pub fn is_imported(&self, kind: ExternalKind, index: u32) -> bool {
+        index < self.count(kind)
+    }
+
+    /// Returns the position of `index`, in the index space of `kind`, among
+    /// the items defined by the module, or `None` if it refers to an import.
+    pub fn defined_index(&self, kind: ExternalKind, index: u32) -> Option<u32> {
+        index.checked_sub(self.count(kind))
+    }
+
+    /// Returns the index in the index space of `kind` of the item at
+    /// position `defined` among the items defined by the module.
+    pub fn index_of_defined(&self, kind: ExternalKind, defined: u32) -> Option<u32> {
+        defined.checked_add(self.count(kind))
+    }
+}
+
+/// Returns the number of functions imported by the core wasm module `wasm`.
+///
+/// This is a shorthand for [`ImportCounts::from_module`] for the common case
+/// of only needing to know where defined functions start.
+pub fn imported_function_count(wasm: &[u8]) -> Result<u32> {
+    Ok(ImportCounts::from_module(wasm)?.funcs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_counts() {
+        let wasm = wat::parse_str(
+            r#"
+                (module
+                    (import "a" "f1" (func))
+                    (import "a" "g1" (global i32))
+                    (import "a" "f2" (func))
+                    (import "a" "m" (memory 1))
+                    (func)
+                    (func)
+                    (func)
+                    (global i32 (i32.const 0))
+                    (table 1 funcref))
+            "#,
+        )
+        .unwrap();
+        let counts = ImportCounts::from_module(&wasm).unwrap();
+        assert_eq!(
+            counts,
+            ImportCounts {
+                funcs: 2,
+                tables: 0,
+                memories: 1,
+                globals: 1,
+                tags: 0,
+            }
+        );
+        assert_eq!(imported_function_count(&wasm).unwrap(), 2);
+
+        assert!(counts.is_imported(ExternalKind::Func, 1));
+        assert!(!counts.is_imported(ExternalKind::Func, 2));
+        assert_eq!(counts.defined_index(ExternalKind::Func, 1), None);
+        assert_eq!(counts.defined_index(ExternalKind::Func, 4), Some(2));
+        assert_eq!(counts.index_of_defined(ExternalKind::Func, 0), Some(2));
+        assert!(!counts.is_imported(ExternalKind::Table, 0));
+        assert_eq!(counts.defined_index(ExternalKind::Global, 1), Some(0));
+    }
+
+    #[test]
+    fn import_counts_without_imports() {
+        let wasm = wat::parse_str("(module (func) (memory 1))").unwrap();
+        assert_eq!(
+            ImportCounts::from_module(&wasm).unwrap(),
+            ImportCounts::default()
+        );
+
+        let component = wat::parse_str("(component)").unwrap();
+        assert!(ImportCounts::from_module(&component).is_err());
+    }
+}
@@ -13,33 +13,70 @@
  * limitations under the License.
  */
 
-use crate::{BinaryReader, Result, SectionIteratorLimited, SectionReader, SectionWithLimitedItems};
+use crate::{
+    BinaryReader, BinaryReaderError, Result, SectionIterator, SectionIteratorLimited,
+    SectionReader, SectionWithLimitedItems,
+};
+use std::fmt;
 use std::ops::Range;
 
-/// Represents a linking type.
-#[derive(Debug, Copy, Clone)]
-pub enum LinkingType {
-    /// The linking uses a stack pointer.
-    StackPointer(u32),
+/// The version of the `linking` custom section supported by
+/// [`LinkingSectionReader`].
+pub const LINKING_SECTION_VERSION: u32 = 2;
+
+/// A subsection of the `linking` custom section.
+///
+/// The format of this section is described in the [tool conventions].
+///
+/// [tool conventions]: https://github.com/WebAssembly/tool-conventions/blob/main/Linking.md
+#[derive(Debug, Clone)]
+pub enum Linking<'a> {
+    /// The symbol table, a `WASM_SYMBOL_TABLE` subsection.
+    SymbolTable(SymbolTableReader<'a>),
+    /// A subsection which isn't otherwise parsed by this reader.
+    Unknown {
+        /// The identifier for this subsection.
+        ty: u8,
+        /// The contents of this subsection.
+        data: &'a [u8],
+        /// The range of bytes, relative to the start of the original data
+        /// stream, that the contents of this subsection reside in.
+        range: Range<usize>,
+    },
 }
 
-/// A reader for the linking custom section of a WebAssembly module.
+/// A reader for the `linking` custom section of a relocatable WebAssembly
+/// object file.
 pub struct LinkingSectionReader<'a> {
     reader: BinaryReader<'a>,
-    count: u32,
+    version: u32,
 }
 
 impl<'a> LinkingSectionReader<'a> {
     /// Constructs a new `LinkingSectionReader` for the given data and offset.
+    ///
+    /// Returns an error if the section's version isn't
+    /// [`LINKING_SECTION_VERSION`].
     pub fn new(data: &'a [u8], offset: usize) -> Result<LinkingSectionReader<'a>> {
         let mut reader = BinaryReader::new_with_offset(data, offset);
-        let count = reader.read_var_u32()?;
-        Ok(LinkingSectionReader { reader, count })
+        let version = reader.read_var_u32()?;
+        if version != LINKING_SECTION_VERSION {
+            return Err(BinaryReaderError::new(
+                format!("unsupported linking section version: {}", version),
+                offset,
+            ));
+        }
+        Ok(LinkingSectionReader { reader, version })
     }
 
-    /// Gets the count of items in the section.
-    pub fn get_count(&self) -> u32 {
-        self.count
+    /// Returns the version of this section.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Determines if the reader is at the end of the section.
+    pub fn eof(&self) -> bool {
+        self.reader.eof()
     }
 
     /// Gets the original position of the reader.
@@ -47,22 +84,46 @@ impl<'a> LinkingSectionReader<'a> {
         self.reader.original_position()
     }
 
-    /// Reads an item from the section.
-    pub fn read<'b>(&mut self) -> Result<LinkingType>
+    /// Reads a subsection from the section.
+    pub fn read<'b>(&mut self) -> Result<Linking<'b>>
     where
         'a: 'b,
     {
-        self.reader.read_linking_type()
+        let ty = self.reader.read_u8()?;
+        let payload_len = self.reader.read_var_u32()? as usize;
+        let payload_start = self.reader.position;
+        let payload_end = payload_start + payload_len;
+        if self.reader.buffer.len() < payload_end {
+            return Err(BinaryReaderError::new(
+                "linking subsection extends past end of the linking section",
+                self.reader.original_offset + self.reader.buffer.len(),
+            ));
+        }
+        let offset = self.reader.original_offset + payload_start;
+        let data = &self.reader.buffer[payload_start..payload_end];
+        self.reader.skip_to(payload_end);
+        Ok(match ty {
+            8 => {
+                let mut reader = BinaryReader::new_with_offset(data, offset);
+                let count = reader.read_var_u32()?;
+                Linking::SymbolTable(SymbolTableReader { reader, count })
+            }
+            _ => Linking::Unknown {
+                ty,
+                data,
+                range: offset..offset + payload_len,
+            },
+        })
     }
 }
 
 impl<'a> SectionReader for LinkingSectionReader<'a> {
-    type Item = LinkingType;
+    type Item = Linking<'a>;
     fn read(&mut self) -> Result<Self::Item> {
         LinkingSectionReader::read(self)
     }
     fn eof(&self) -> bool {
-        self.reader.eof()
+        LinkingSectionReader::eof(self)
     }
     fn original_position(&self) -> usize {
         LinkingSectionReader::original_position(self)
@@ -72,17 +133,273 @@ impl<'a> SectionReader for LinkingSectionReader<'a> {
     }
 }
 
-impl<'a> SectionWithLimitedItems for LinkingSectionReader<'a> {
+impl<'a> IntoIterator for LinkingSectionReader<'a> {
+    type Item = Result<Linking<'a>>;
+    type IntoIter = SectionIterator<LinkingSectionReader<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SectionIterator::new(self)
+    }
+}
+
+/// A reader for the symbol table subsection of the `linking` custom section.
+#[derive(Debug, Clone)]
+pub struct SymbolTableReader<'a> {
+    reader: BinaryReader<'a>,
+    count: u32,
+}
+
+impl<'a> SymbolTableReader<'a> {
+    /// Gets the count of symbols in the table.
+    pub fn get_count(&self) -> u32 {
+        self.count
+    }
+
+    /// Gets the original position of the reader.
+    pub fn original_position(&self) -> usize {
+        self.reader.original_position()
+    }
+
+    /// Reads a symbol from the table.
+    pub fn read<'b>(&mut self) -> Result<SymbolInfo<'b>>
+    where
+        'a: 'b,
+    {
+        let pos = self.reader.original_position();
+        let kind = self.reader.read_u8()?;
+        let flags = SymbolFlags::from_bits(self.reader.read_var_u32()?);
+        let has_name =
+            !flags.contains(SymbolFlags::UNDEFINED) || flags.contains(SymbolFlags::EXPLICIT_NAME);
+        Ok(match kind {
+            0 | 2 | 4 | 5 => {
+                let index = self.reader.read_var_u32()?;
+                let name = if has_name {
+                    Some(self.reader.read_string()?)
+                } else {
+                    None
+                };
+                match kind {
+                    0 => SymbolInfo::Func { flags, index, name },
+                    2 => SymbolInfo::Global { flags, index, name },
+                    4 => SymbolInfo::Tag { flags, index, name },
+                    _ => SymbolInfo::Table { flags, index, name },
+                }
+            }
+            1 => {
+                let name = self.reader.read_string()?;
+                let symbol = if flags.contains(SymbolFlags::UNDEFINED) {
+                    None
+                } else {
+                    Some(DefinedDataSymbol {
+                        index: self.reader.read_var_u32()?,
+                        offset: self.reader.read_var_u32()?,
+                        size: self.reader.read_var_u32()?,
+                    })
+                };
+                SymbolInfo::Data {
+                    flags,
+                    name,
+                    symbol,
+                }
+            }
+            3 => SymbolInfo::Section {
+                flags,
+                section: self.reader.read_var_u32()?,
+            },
+            _ => return Err(BinaryReaderError::new("invalid symbol kind", pos)),
+        })
+    }
+}
+
+impl<'a> SectionReader for SymbolTableReader<'a> {
+    type Item = SymbolInfo<'a>;
+    fn read(&mut self) -> Result<Self::Item> {
+        SymbolTableReader::read(self)
+    }
+    fn eof(&self) -> bool {
+        self.reader.eof()
+    }
+    fn original_position(&self) -> usize {
+        SymbolTableReader::original_position(self)
+    }
+    fn range(&self) -> Range<usize> {
+        self.reader.range()
+    }
+}
+
+impl<'a> SectionWithLimitedItems for SymbolTableReader<'a> {
     fn get_count(&self) -> u32 {
-        LinkingSectionReader::get_count(self)
+        SymbolTableReader::get_count(self)
     }
 }
 
-impl<'a> IntoIterator for LinkingSectionReader<'a> {
-    type Item = Result<LinkingType>;
-    type IntoIter = SectionIteratorLimited<LinkingSectionReader<'a>>;
+impl<'a> IntoIterator for SymbolTableReader<'a> {
+    type Item = Result<SymbolInfo<'a>>;
+    type IntoIter = SectionIteratorLimited<SymbolTableReader<'a>>;
 
     fn into_iter(self) -> Self::IntoIter {
         SectionIteratorLimited::new(self)
     }
 }
+
+/// A symbol in the symbol table of the `linking` custom section.
+#[derive(Debug, Copy, Clone)]
+pub enum SymbolInfo<'a> {
+    /// A function symbol.
+    Func {
+        /// The flags of this symbol.
+        flags: SymbolFlags,
+        /// The index of the function this symbol refers to.
+        index: u32,
+        /// The name of this symbol, which is only present for defined
+        /// symbols or symbols with an explicit name.
+        name: Option<&'a str>,
+    },
+    /// A data symbol.
+    Data {
+        /// The flags of this symbol.
+        flags: SymbolFlags,
+        /// The name of this symbol.
+        name: &'a str,
+        /// Where this symbol's data resides, which is only present for
+        /// defined symbols.
+        symbol: Option<DefinedDataSymbol>,
+    },
+    /// A global symbol.
+    Global {
+        /// The flags of this symbol.
+        flags: SymbolFlags,
+        /// The index of the global this symbol refers to.
+        index: u32,
+        /// The name of this symbol, which is only present for defined
+        /// symbols or symbols with an explicit name.
+        name: Option<&'a str>,
+    },
+    /// A section symbol, used for relocations against custom sections.
+    Section {
+        /// The flags of this symbol.
+        flags: SymbolFlags,
+        /// The index of the section this symbol refers to.
+        section: u32,
+    },
+    /// A tag symbol.
+    Tag {
+        /// The flags of this symbol.
+        flags: SymbolFlags,
+        /// The index of the tag this symbol refers to.
+        index: u32,
+        /// The name of this symbol, which is only present for defined
+        /// symbols or symbols with an explicit name.
+        name: Option<&'a str>,
+    },
+    /// A table symbol.
+    Table {
+        /// The flags of this symbol.
+        flags: SymbolFlags,
+        /// The index of the table this symbol refers to.
+        index: u32,
+        /// The name of this symbol, which is only present for defined
+        /// symbols or symbols with an explicit name.
+        name: Option<&'a str>,
+    },
+}
+
+impl SymbolInfo<'_> {
+    /// Returns the flags of this symbol.
+    pub fn flags(&self) -> SymbolFlags {
+        match self {
+            SymbolInfo::Func { flags, .. }
+            | SymbolInfo::Data { flags, .. }
+            | SymbolInfo::Global { flags, .. }
+            | SymbolInfo::Section { flags, .. }
+            | SymbolInfo::Tag { flags, .. }
+            | SymbolInfo::Table { flags, .. } => *flags,
+        }
+    }
+}
+
+/// The location of a defined data symbol.
+#[derive(Debug, Copy, Clone)]
+pub struct DefinedDataSymbol {
+    /// The index of the data segment containing the symbol.
+    pub index: u32,
+    /// The offset of the symbol within its data segment.
+    pub offset: u32,
+    /// The size of the symbol, in bytes.
+    pub size: u32,
+}
+
+/// The flags of a symbol in the symbol table of the `linking` custom section.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SymbolFlags {
+    bits: u32,
+}
+
+macro_rules! symbol_flags {
+    ($($(#[$doc:meta])* $name:ident = $bit:expr,)*) => {
+        impl SymbolFlags {
+            $(
+                $(#[$doc])*
+                pub const $name: SymbolFlags = SymbolFlags { bits: $bit };
+            )*
+        }
+
+        impl fmt::Debug for SymbolFlags {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let mut names = Vec::new();
+                $(
+                    if self.contains(SymbolFlags::$name) {
+                        names.push(stringify!($name).to_string());
+                    }
+                )*
+                let unknown = self.bits & !(0 $(| $bit)*);
+                if unknown != 0 {
+                    names.push(format!("{:#x}", unknown));
+                }
+                write!(f, "SymbolFlags({})", names.join(" | "))
+            }
+        }
+    };
+}
+
+symbol_flags! {
+    /// The symbol has weak binding.
+    BINDING_WEAK = 0x1,
+    /// The symbol has local binding and isn't visible outside of this object
+    /// file.
+    BINDING_LOCAL = 0x2,
+    /// The symbol has hidden visibility and isn't exported from the linked
+    /// module.
+    VISIBILITY_HIDDEN = 0x4,
+    /// The symbol isn't defined by this object file.
+    UNDEFINED = 0x10,
+    /// The symbol is intended to be exported from the linked module.
+    EXPORTED = 0x20,
+    /// The symbol uses an explicit name rather than the name of the import it
+    /// refers to.
+    EXPLICIT_NAME = 0x40,
+    /// The symbol must not be stripped by the linker.
+    NO_STRIP = 0x80,
+    /// The symbol resides in thread-local storage.
+    TLS = 0x100,
+    /// The symbol's data has an absolute address rather than residing within
+    /// a segment.
+    ABSOLUTE = 0x200,
+}
+
+impl SymbolFlags {
+    /// Creates flags from their raw encoded representation.
+    pub fn from_bits(bits: u32) -> SymbolFlags {
+        SymbolFlags { bits }
+    }
+
+    /// Returns the raw encoded representation of these flags.
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Returns whether all the flags in `other` are set in `self`.
+    pub fn contains(&self, other: SymbolFlags) -> bool {
+        self.bits & other.bits == other.bits
+    }
+}
@@ -152,9 +152,29 @@ pub struct MemoryType {
     /// be at most `u32::MAX` for valid types. This field is always present for
     /// valid wasm memories when `shared` is `true`.
     pub maximum: Option<u64>,
+
+    /// The log base 2 of the memory's custom page size, or `None` for the
+    /// default page size of 64KiB.
+    ///
+    /// This is part of the custom-page-sizes proposal in WebAssembly.
+    pub page_size_log2: Option<u32>,
 }
 
 impl MemoryType {
+    /// The default size of a wasm page, in bytes.
+    pub const DEFAULT_PAGE_SIZE: u64 = 1 << 16;
+
+    /// Gets the size of this memory's pages, in bytes.
+    ///
+    /// Note that this saturates to `u64::MAX` for page sizes which are too
+    /// large to represent, which are never valid.
+    pub fn page_size(&self) -> u64 {
+        match self.page_size_log2 {
+            Some(log2) => 1u64.checked_shl(log2).unwrap_or(u64::MAX),
+            None => Self::DEFAULT_PAGE_SIZE,
+        }
+    }
+
     /// Gets the index type for the memory.
     pub fn index_type(&self) -> ValType {
         if self.memory64 {
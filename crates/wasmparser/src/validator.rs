@@ -55,6 +55,7 @@ use self::component::*;
 pub use self::core::ValidatorResources;
 use self::core::*;
 use self::types::{TypeList, Types, TypesRef};
+use func::ForbiddenOperators;
 pub use func::{FuncToValidate, FuncValidator, FuncValidatorAllocations};
 pub use operators::{Frame, FrameKind};
 
@@ -127,6 +128,12 @@ pub struct Validator {
     /// Enabled WebAssembly feature flags, dictating what's valid and what
     /// isn't.
     features: WasmFeatures,
+
+    /// Configured limits on the number of items in each module.
+    limits: ValidatorLimits,
+
+    /// Operators which may not appear in function bodies.
+    forbidden_operators: Option<Arc<ForbiddenOperators>>,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -231,6 +238,8 @@ pub struct WasmFeatures {
     pub extended_const: bool,
     /// The WebAssembly component model proposal.
     pub component_model: bool,
+    /// The WebAssembly custom-page-sizes proposal
+    pub custom_page_sizes: bool,
 }
 
 impl WasmFeatures {
@@ -267,6 +276,7 @@ impl Default for WasmFeatures {
             memory64: false,
             extended_const: false,
             component_model: false,
+            custom_page_sizes: false,
             deterministic_only: cfg!(feature = "deterministic"),
 
             // on-by-default features
@@ -281,6 +291,84 @@ impl Default for WasmFeatures {
     }
 }
 
+/// Configurable limits on the number of items in a module, checked during
+/// validation.
+///
+/// These are in addition to the implementation limits which `wasmparser`
+/// always enforces, and are intended for hosts which accept untrusted modules
+/// and want to reject ones that are too large before validating them entirely.
+/// Each limit is checked as soon as the section declaring the items is
+/// reached, and imported items count towards the limit of their kind.
+///
+/// Limits only apply to core modules, including those nested in components.
+/// A limit of `None`, the default, means only the implementation limit
+/// applies.
+///
+/// # Examples
+///
+/// ```
+/// use wasmparser::{Validator, ValidatorLimits};
+/// let wasm = wat::parse_str("(module (func) (func))").unwrap();
+/// let mut validator = Validator::new();
+/// validator.set_limits(ValidatorLimits {
+///     functions: Some(1),
+///     ..ValidatorLimits::default()
+/// });
+/// assert!(validator.validate_all(&wasm).is_err());
+/// ```
+#[derive(Hash, Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ValidatorLimits {
+    /// The maximum number of functions, including imported functions.
+    pub functions: Option<u32>,
+    /// The maximum number of globals, including imported globals.
+    pub globals: Option<u32>,
+    /// The maximum number of tables, including imported tables.
+    pub tables: Option<u32>,
+    /// The maximum number of memories, including imported memories.
+    pub memories: Option<u32>,
+    /// The maximum number of types.
+    pub types: Option<u32>,
+    /// The maximum number of element segments.
+    pub element_segments: Option<u32>,
+    /// The maximum number of data segments.
+    pub data_segments: Option<u32>,
+}
+
+impl ValidatorLimits {
+    fn check(
+        limit: Option<u32>,
+        cur_len: usize,
+        amt_added: u32,
+        desc: &str,
+        offset: usize,
+    ) -> Result<()> {
+        match limit {
+            Some(limit) if cur_len as u64 + u64::from(amt_added) > u64::from(limit) => {
+                bail!(
+                    offset,
+                    "{desc} count exceeds the configured limit of {limit}"
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the limits of the kinds of items which can be imported.
+    fn check_imports(&self, module: &Module, offset: usize) -> Result<()> {
+        Self::check(
+            self.functions,
+            module.functions.len(),
+            0,
+            "functions",
+            offset,
+        )?;
+        Self::check(self.globals, module.globals.len(), 0, "globals", offset)?;
+        Self::check(self.tables, module.tables.len(), 0, "tables", offset)?;
+        Self::check(self.memories, module.memories.len(), 0, "memories", offset)?;
+        Ok(())
+    }
+}
+
 /// Possible return values from [`Validator::payload`].
 #[allow(clippy::large_enum_variant)]
 pub enum ValidPayload<'a> {
@@ -327,6 +415,57 @@ impl Validator {
         &self.features
     }
 
+    /// Configures limits on the number of items in modules validated by this
+    /// validator.
+    ///
+    /// See [`ValidatorLimits`] for more information.
+    pub fn set_limits(&mut self, limits: ValidatorLimits) -> &mut Validator {
+        self.limits = limits;
+        self
+    }
+
+    /// Returns the limits on the number of items in modules validated by
+    /// this validator.
+    pub fn limits(&self) -> &ValidatorLimits {
+        &self.limits
+    }
+
+    /// Forbids the operator `name` from appearing in the function bodies of
+    /// modules validated by this validator.
+    ///
+    /// This is finer-grained than disabling a proposal with [`WasmFeatures`],
+    /// for hosts which don't support particular instructions such as
+    /// `memory.grow` or floating point arithmetic. The operator is named
+    /// either by its text format name, such as `memory.grow`, or by the name
+    /// of its [`Operator`](crate::Operator) variant, such as `MemoryGrow`.
+    /// Using a forbidden operator in a function is a validation error at the
+    /// operator's offset. Constant expressions aren't affected.
+    ///
+    /// Returns `false`, leaving this validator unchanged, if `name` isn't the
+    /// name of an operator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wasmparser::Validator;
+    /// let wasm = wat::parse_str("(module (memory 1) (func (drop (memory.grow (i32.const 1)))))").unwrap();
+    /// let mut validator = Validator::new();
+    /// assert!(validator.forbid_operator("memory.grow"));
+    /// assert!(validator.validate_all(&wasm).is_err());
+    /// ```
+    pub fn forbid_operator(&mut self, name: &str) -> bool {
+        let op = match func::operator_name(name) {
+            Some(op) => op,
+            None => return false,
+        };
+        Arc::make_mut(
+            self.forbidden_operators
+                .get_or_insert_with(Default::default),
+        )
+        .insert(op, name.to_string());
+        true
+    }
+
     /// Validates an entire in-memory module or component with this validator.
     ///
     /// This function will internally create a [`Parser`] to parse the `bytes`
@@ -512,6 +651,7 @@ impl Validator {
 
     /// Validates [`Payload::TypeSection`](crate::Payload).
     pub fn type_section(&mut self, section: &crate::TypeSectionReader<'_>) -> Result<()> {
+        let limits = self.limits;
         self.process_module_section(
             Order::Type,
             section,
@@ -524,6 +664,13 @@ impl Validator {
                     "types",
                     offset,
                 )?;
+                ValidatorLimits::check(
+                    limits.types,
+                    state.module.types.len(),
+                    count,
+                    "types",
+                    offset,
+                )?;
                 types.reserve(count as usize);
                 state.module.assert_mut().types.reserve(count as usize);
                 Ok(())
@@ -541,16 +688,16 @@ impl Validator {
     ///
     /// This method should only be called when parsing a module.
     pub fn import_section(&mut self, section: &crate::ImportSectionReader<'_>) -> Result<()> {
+        let limits = self.limits;
         self.process_module_section(
             Order::Import,
             section,
             "import",
             |_, _, _, _, _| Ok(()), // add_import will check limits
             |state, features, types, import, offset| {
-                state
-                    .module
-                    .assert_mut()
-                    .add_import(import, features, types, offset)
+                let module = state.module.assert_mut();
+                module.add_import(import, features, types, offset)?;
+                limits.check_imports(module, offset)
             },
         )
     }
@@ -559,6 +706,7 @@ impl Validator {
     ///
     /// This method should only be called when parsing a module.
     pub fn function_section(&mut self, section: &crate::FunctionSectionReader<'_>) -> Result<()> {
+        let limits = self.limits;
         self.process_module_section(
             Order::Function,
             section,
@@ -571,6 +719,13 @@ impl Validator {
                     "functions",
                     offset,
                 )?;
+                ValidatorLimits::check(
+                    limits.functions,
+                    state.module.functions.len(),
+                    count,
+                    "functions",
+                    offset,
+                )?;
                 state.module.assert_mut().functions.reserve(count as usize);
                 debug_assert!(state.expected_code_bodies.is_none());
                 state.expected_code_bodies = Some(count);
@@ -585,6 +740,7 @@ impl Validator {
     /// This method should only be called when parsing a module.
     pub fn table_section(&mut self, section: &crate::TableSectionReader<'_>) -> Result<()> {
         let features = self.features;
+        let limits = self.limits;
         self.process_module_section(
             Order::Table,
             section,
@@ -597,6 +753,13 @@ impl Validator {
                     "tables",
                     offset,
                 )?;
+                ValidatorLimits::check(
+                    limits.tables,
+                    state.module.tables.len(),
+                    count,
+                    "tables",
+                    offset,
+                )?;
                 state.module.assert_mut().tables.reserve(count as usize);
                 Ok(())
             },
@@ -610,6 +773,7 @@ impl Validator {
     ///
     /// This method should only be called when parsing a module.
     pub fn memory_section(&mut self, section: &crate::MemorySectionReader<'_>) -> Result<()> {
+        let limits = self.limits;
         self.process_module_section(
             Order::Memory,
             section,
@@ -622,6 +786,13 @@ impl Validator {
                     "memories",
                     offset,
                 )?;
+                ValidatorLimits::check(
+                    limits.memories,
+                    state.module.memories.len(),
+                    count,
+                    "memories",
+                    offset,
+                )?;
                 state.module.assert_mut().memories.reserve(count as usize);
                 Ok(())
             },
@@ -670,6 +841,7 @@ impl Validator {
     ///
     /// This method should only be called when parsing a module.
     pub fn global_section(&mut self, section: &crate::GlobalSectionReader<'_>) -> Result<()> {
+        let limits = self.limits;
         self.process_module_section(
             Order::Global,
             section,
@@ -682,6 +854,13 @@ impl Validator {
                     "globals",
                     offset,
                 )?;
+                ValidatorLimits::check(
+                    limits.globals,
+                    state.module.globals.len(),
+                    count,
+                    "globals",
+                    offset,
+                )?;
                 state.module.assert_mut().globals.reserve(count as usize);
                 Ok(())
             },
@@ -742,6 +921,7 @@ impl Validator {
     ///
     /// This method should only be called when parsing a module.
     pub fn element_section(&mut self, section: &crate::ElementSectionReader<'_>) -> Result<()> {
+        let limits = self.limits;
         self.process_module_section(
             Order::Element,
             section,
@@ -754,6 +934,13 @@ impl Validator {
                     "element segments",
                     offset,
                 )?;
+                ValidatorLimits::check(
+                    limits.element_segments,
+                    state.module.element_types.len(),
+                    count,
+                    "element segments",
+                    offset,
+                )?;
                 state
                     .module
                     .assert_mut()
@@ -783,6 +970,7 @@ impl Validator {
                 offset,
             ));
         }
+        ValidatorLimits::check(self.limits.data_segments, 0, count, "data segments", offset)?;
 
         state.module.assert_mut().data_count = Some(count);
         Ok(())
@@ -846,25 +1034,29 @@ impl Validator {
         let state = self.module.as_mut().unwrap();
 
         let (index, ty) = state.next_code_index_and_type(offset)?;
-        Ok(FuncToValidate::new(
+        let mut func = FuncToValidate::new(
             index,
             ty,
             ValidatorResources(state.module.arc().clone()),
             &self.features,
-        ))
+        );
+        func.forbidden = self.forbidden_operators.clone();
+        Ok(func)
     }
 
     /// Validates [`Payload::DataSection`](crate::Payload).
     ///
     /// This method should only be called when parsing a module.
     pub fn data_section(&mut self, section: &crate::DataSectionReader<'_>) -> Result<()> {
+        let limits = self.limits;
         self.process_module_section(
             Order::Data,
             section,
             "data",
             |state, _, _, count, offset| {
                 state.data_segment_count = count;
-                check_max(0, count, MAX_WASM_DATA_SEGMENTS, "data segments", offset)
+                check_max(0, count, MAX_WASM_DATA_SEGMENTS, "data segments", offset)?;
+                ValidatorLimits::check(limits.data_segments, 0, count, "data segments", offset)
             },
             |state, features, types, d, offset| state.add_data_segment(d, features, types, offset),
         )
@@ -1325,7 +1517,9 @@ impl Validator {
 
 #[cfg(test)]
 mod tests {
-    use crate::{GlobalType, MemoryType, TableType, ValType, Validator, WasmFeatures};
+    use crate::{
+        GlobalType, MemoryType, TableType, ValType, Validator, ValidatorLimits, WasmFeatures,
+    };
     use anyhow::Result;
 
     #[test]
@@ -1385,7 +1579,8 @@ mod tests {
                 memory64: false,
                 shared: false,
                 initial: 1,
-                maximum: Some(5)
+                maximum: Some(5),
+                page_size_log2: None,
             })
         );
 
@@ -1467,4 +1662,177 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_limits() -> Result<()> {
+        let limit = Some(1);
+        let cases = [
+            (
+                ValidatorLimits {
+                    functions: limit,
+                    ..Default::default()
+                },
+                "(func) (func)",
+                "functions",
+            ),
+            (
+                ValidatorLimits {
+                    functions: limit,
+                    ..Default::default()
+                },
+                r#"(import "" "" (func)) (func)"#,
+                "functions",
+            ),
+            (
+                ValidatorLimits {
+                    functions: limit,
+                    ..Default::default()
+                },
+                r#"(import "" "a" (func)) (import "" "b" (func))"#,
+                "functions",
+            ),
+            (
+                ValidatorLimits {
+                    globals: limit,
+                    ..Default::default()
+                },
+                "(global i32 (i32.const 0)) (global i32 (i32.const 0))",
+                "globals",
+            ),
+            (
+                ValidatorLimits {
+                    tables: limit,
+                    ..Default::default()
+                },
+                "(table 0 funcref) (table 0 funcref)",
+                "tables",
+            ),
+            (
+                ValidatorLimits {
+                    memories: limit,
+                    ..Default::default()
+                },
+                r#"(import "" "" (memory 0)) (memory 0)"#,
+                "memories",
+            ),
+            (
+                ValidatorLimits {
+                    types: limit,
+                    ..Default::default()
+                },
+                "(type (func)) (type (func (param i32)))",
+                "types",
+            ),
+            (
+                ValidatorLimits {
+                    element_segments: limit,
+                    ..Default::default()
+                },
+                "(elem func) (elem func)",
+                "element segments",
+            ),
+            (
+                ValidatorLimits {
+                    data_segments: limit,
+                    ..Default::default()
+                },
+                r#"(data "") (data "")"#,
+                "data segments",
+            ),
+            (
+                ValidatorLimits {
+                    data_segments: limit,
+                    ..Default::default()
+                },
+                r#"(memory 1) (func (data.drop 1)) (data "") (data "")"#,
+                "data segments",
+            ),
+        ];
+
+        for (limits, module, desc) in cases {
+            let bytes = wat::parse_str(format!("(module {module})"))?;
+            let mut validator = Validator::new_with_features(WasmFeatures {
+                multi_memory: true,
+                ..Default::default()
+            });
+            let err = match validator.set_limits(limits).validate_all(&bytes) {
+                Ok(_) => panic!("`{module}` validated"),
+                Err(e) => e,
+            };
+            assert_eq!(
+                err.message(),
+                format!("{desc} count exceeds the configured limit of 1"),
+                "{module}",
+            );
+
+            // The module is valid when the limit isn't exceeded.
+            let mut validator = Validator::new_with_features(WasmFeatures {
+                multi_memory: true,
+                ..Default::default()
+            });
+            validator.set_limits(ValidatorLimits {
+                functions: limits.functions.map(|n| n + 1),
+                globals: limits.globals.map(|n| n + 1),
+                tables: limits.tables.map(|n| n + 1),
+                memories: limits.memories.map(|n| n + 1),
+                types: limits.types.map(|n| n + 1),
+                element_segments: limits.element_segments.map(|n| n + 1),
+                data_segments: limits.data_segments.map(|n| n + 1),
+            });
+            validator.validate_all(&bytes)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn forbidden_operators() -> Result<()> {
+        let bytes = wat::parse_str(
+            r#"
+            (module
+                (memory 1)
+                (func (result f32)
+                    f32.const 1
+                    f32.const 2
+                    f32.sub)
+                (func (param f32) (result f32)
+                    local.get 0
+                    local.get 0
+                    f32.add)
+            )
+            "#,
+        )?;
+
+        let mut validator = Validator::new();
+        assert!(!validator.forbid_operator("f32.frobnicate"));
+        assert!(validator.forbid_operator("memory.grow"));
+        validator.validate_all(&bytes)?;
+
+        let mut validator = Validator::new();
+        assert!(validator.forbid_operator("F32Add"));
+        let err = match validator.validate_all(&bytes) {
+            Ok(_) => panic!("module validated"),
+            Err(e) => e,
+        };
+        assert_eq!(
+            err.message(),
+            "forbidden operator `F32Add` found in function 1"
+        );
+
+        let mut validator = Validator::new();
+        assert!(validator.forbid_operator("f32.add"));
+        let err = match validator.validate_all(&bytes) {
+            Ok(_) => panic!("module validated"),
+            Err(e) => e,
+        };
+        assert_eq!(
+            err.message(),
+            "forbidden operator `f32.add` found in function 1"
+        );
+        // The offset is that of the `f32.add` itself, the second to last byte
+        // of the module before the function's `end`.
+        assert_eq!(err.offset(), bytes.len() - 2);
+
+        Ok(())
+    }
 }
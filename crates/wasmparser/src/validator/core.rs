@@ -698,22 +698,43 @@ impl Module {
         offset: usize,
     ) -> Result<()> {
         self.check_limits(ty.initial, ty.maximum, offset)?;
-        let (true_maximum, err) = if ty.memory64 {
-            if !features.memory64 {
-                return Err(BinaryReaderError::new(
-                    "memory64 must be enabled for 64-bit memories",
-                    offset,
-                ));
-            }
-            (
+        if ty.memory64 && !features.memory64 {
+            return Err(BinaryReaderError::new(
+                "memory64 must be enabled for 64-bit memories",
+                offset,
+            ));
+        }
+        let (true_maximum, err) = match ty.page_size_log2 {
+            None if ty.memory64 => (
                 MAX_WASM_MEMORY64_PAGES,
-                "memory size must be at most 2**48 pages",
-            )
-        } else {
-            (
+                "memory size must be at most 2**48 pages".to_string(),
+            ),
+            None => (
                 MAX_WASM_MEMORY32_PAGES,
-                "memory size must be at most 65536 pages (4GiB)",
-            )
+                "memory size must be at most 65536 pages (4GiB)".to_string(),
+            ),
+            Some(log2) => {
+                if !features.custom_page_sizes {
+                    return Err(BinaryReaderError::new(
+                        "the custom page sizes proposal must be enabled to \
+                         customize a memory's page size",
+                        offset,
+                    ));
+                }
+                // The proposal only allows pages of a single byte, in addition
+                // to the default page size.
+                if log2 != 0 && log2 != 16 {
+                    return Err(BinaryReaderError::new("invalid custom page size", offset));
+                }
+                // Memories may span the whole index space, so a 32-bit memory
+                // of 1-byte pages may have up to 2**32 pages.
+                let index_bits = if ty.memory64 { 64 } else { 32 };
+                let maximum = 1u64.checked_shl(index_bits - log2).unwrap_or(u64::MAX);
+                (
+                    maximum,
+                    format!("memory size must be at most 2**{} pages", index_bits - log2),
+                )
+            }
         };
         if ty.initial > true_maximum {
             return Err(BinaryReaderError::new(err, offset));
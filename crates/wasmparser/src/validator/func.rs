@@ -1,6 +1,36 @@
 use super::operators::{Frame, OperatorValidator, OperatorValidatorAllocations};
 use crate::{BinaryReader, Result, ValType, VisitOperator};
 use crate::{FunctionBody, Operator, WasmFeatures, WasmModuleResources};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Operators which may not appear in a function, mapping the name of each
+/// [`Operator`] variant to the name it was forbidden with.
+pub(crate) type ForbiddenOperators = HashMap<&'static str, String>;
+
+/// Returns the name of the [`Operator`] variant named `name`, either by its
+/// text format name such as `memory.grow` or by its variant name such as
+/// `MemoryGrow`.
+pub(crate) fn operator_name(name: &str) -> Option<&'static str> {
+    macro_rules! operator_names {
+        ($(@$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident)*) => {
+            &[$(stringify!($op)),*]
+        }
+    }
+    const NAMES: &[&str] = for_each_operator!(operator_names);
+
+    // Text format names only differ from variant names in their case and
+    // separators, for example `i16x8.extadd_pairwise_i8x16_s` and
+    // `I16x8ExtAddPairwiseI8x16S`.
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(|c| *c != '.' && *c != '_')
+            .map(|c| c.to_ascii_lowercase())
+            .collect::<String>()
+    };
+    let name = normalize(name);
+    NAMES.iter().copied().find(|op| normalize(op) == name)
+}
 
 /// Resources necessary to perform validation of a function.
 ///
@@ -14,6 +44,7 @@ pub struct FuncToValidate<T> {
     index: u32,
     ty: u32,
     features: WasmFeatures,
+    pub(crate) forbidden: Option<Arc<ForbiddenOperators>>,
 }
 
 impl<T: WasmModuleResources> FuncToValidate<T> {
@@ -32,6 +63,7 @@ impl<T: WasmModuleResources> FuncToValidate<T> {
             index,
             ty,
             features: *features,
+            forbidden: None,
         }
     }
 
@@ -54,6 +86,7 @@ impl<T: WasmModuleResources> FuncToValidate<T> {
             index,
             ty,
             features,
+            forbidden,
         } = self;
         let validator =
             OperatorValidator::new_func(ty, 0, &features, &resources, allocs.0).unwrap();
@@ -61,6 +94,7 @@ impl<T: WasmModuleResources> FuncToValidate<T> {
             validator,
             resources,
             index,
+            forbidden,
         }
     }
 }
@@ -73,6 +107,7 @@ pub struct FuncValidator<T> {
     validator: OperatorValidator,
     resources: T,
     index: u32,
+    forbidden: Option<Arc<ForbiddenOperators>>,
 }
 
 /// External handle to the internal allocations used during function validation.
@@ -129,9 +164,22 @@ impl<T: WasmModuleResources> FuncValidator<T> {
     /// the operator itself are passed to this function to provide more useful
     /// error messages.
     pub fn op(&mut self, offset: usize, operator: &Operator<'_>) -> Result<()> {
-        self.validator
-            .with_resources(&self.resources)
-            .visit_operator(offset, operator)
+        self.visit_operator(offset, operator)
+    }
+
+    /// Returns an error if the [`Operator`] variant named `op` has been
+    /// forbidden with
+    /// [`Validator::forbid_operator`](crate::Validator::forbid_operator).
+    fn check_forbidden(&self, offset: usize, op: &str) -> Result<()> {
+        if let Some(name) = self.forbidden.as_ref().and_then(|f| f.get(op)) {
+            bail!(
+                offset,
+                "forbidden operator `{}` found in function {}",
+                name,
+                self.index
+            );
+        }
+        Ok(())
     }
 
     /// Function that must be called after the last opcode has been processed.
@@ -312,6 +360,7 @@ macro_rules! define_visit_operator {
     ($(@$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident)*) => {
         $(
             fn $visit(&mut self, offset: usize $($(,$arg: $argty)*)?) -> Result<()> {
+                self.check_forbidden(offset, stringify!($op))?;
                 self.validator.with_resources(&self.resources)
                     .$visit(offset $($(,$arg)*)?)
             }
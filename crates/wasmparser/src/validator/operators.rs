@@ -1128,14 +1128,20 @@ where
         self.pop_operand(offset, Some(ValType::I32))?;
         let default = self.jump(offset, table.default())?;
         let default_types = self.label_types(offset, default.0, default.1)?;
-        for element in table.targets() {
+        for (i, element) in table.targets().enumerate() {
             let relative_depth = element?;
             let block = self.jump(offset, relative_depth)?;
             let tys = self.label_types(offset, block.0, block.1)?;
             if tys.len() != default_types.len() {
                 bail!(
                     offset,
-                    "type mismatch: br_table target labels have different number of types"
+                    "type mismatch: br_table target labels have different number of types: \
+                     target {} (depth {}) has {} but the default (depth {}) has {}",
+                    i,
+                    relative_depth,
+                    tys.len(),
+                    table.default(),
+                    default_types.len(),
                 );
             }
             debug_assert!(self.br_table_tmp.is_empty());
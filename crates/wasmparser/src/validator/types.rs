@@ -380,7 +380,10 @@ impl EntityType {
                 a.element_type == b.element_type && limits_match!(a, b)
             }
             (EntityType::Memory(a), EntityType::Memory(b)) => {
-                a.shared == b.shared && a.memory64 == b.memory64 && limits_match!(a, b)
+                a.shared == b.shared
+                    && a.memory64 == b.memory64
+                    && a.page_size_log2 == b.page_size_log2
+                    && limits_match!(a, b)
             }
             (EntityType::Global(a), EntityType::Global(b)) => a == b,
             (EntityType::Tag(a), EntityType::Tag(b)) => {
@@ -35,6 +35,8 @@ pub fn print_bytes(wasm: impl AsRef<[u8]>) -> Result<String> {
     Printer::new().print(wasm.as_ref())
 }
 
+type SectionFilter = dyn FnMut(&Payload<'_>) -> bool;
+
 /// Context used for printing a WebAssembly binary.
 ///
 /// This is largely only required if you'd like to register custom printers for
@@ -42,7 +44,12 @@ pub fn print_bytes(wasm: impl AsRef<[u8]>) -> Result<String> {
 #[derive(Default)]
 pub struct Printer {
     print_offsets: bool,
+    abbreviate_types: bool,
+    inline_exports: bool,
+    max_line_width: Option<usize>,
+    max_depth: Option<u32>,
     printers: HashMap<String, Box<dyn FnMut(&mut Printer, usize, &[u8]) -> Result<()>>>,
+    section_filter: Option<Box<SectionFilter>>,
     result: String,
     nesting: u32,
     line: usize,
@@ -52,6 +59,12 @@ pub struct Printer {
 #[derive(Default)]
 struct CoreState {
     types: Vec<Option<FuncType>>,
+    inline_types: HashSet<u32>,
+    /// The names of the exports of each item defined in the module which are
+    /// printed inline with the item.
+    inline_exports: HashMap<(ExternalKind, u32), Vec<String>>,
+    /// The items whose exports have been printed inline.
+    inlined: Vec<(ExternalKind, u32)>,
     funcs: u32,
     memories: u32,
     tags: u32,
@@ -105,6 +118,16 @@ impl State {
     }
 }
 
+impl CoreState {
+    /// Records that the exports of the item `index` of `kind`, if any, have
+    /// been printed inline.
+    fn mark_inlined(&mut self, kind: ExternalKind, index: u32) {
+        if self.inline_exports.contains_key(&(kind, index)) {
+            self.inlined.push((kind, index));
+        }
+    }
+}
+
 struct Naming {
     identifier: Option<String>,
     name: String,
@@ -123,6 +146,52 @@ impl Printer {
         self.print_offsets = print;
     }
 
+    /// Whether or not to abbreviate the use of function types in core modules.
+    ///
+    /// When enabled, a function type which is used exactly once is inlined
+    /// as a signature such as `(param i32) (result i32)` at its use site and
+    /// its definition in the type section is omitted. Types which are used
+    /// more than once are given a name, if they don't have one already, and
+    /// are referenced by that name. By default types are referenced by
+    /// index and every definition is printed.
+    pub fn abbreviate_types(&mut self, abbreviate: bool) {
+        self.abbreviate_types = abbreviate;
+    }
+
+    /// Whether or not to print the exports of core wasm items inline.
+    ///
+    /// When enabled, the exports of functions, tables, memories, globals, and
+    /// tags defined within a module are printed on the item itself, as in
+    /// `(func (export "foo") ...)`, instead of as separate `export` fields.
+    /// Exports of imported items are always printed as separate fields. By
+    /// default all exports are printed as separate fields.
+    pub fn inline_exports(&mut self, inline: bool) {
+        self.inline_exports = inline;
+    }
+
+    /// Configures the maximum width, in bytes, of printed lines.
+    ///
+    /// When set, long lists of `br_table` targets and long data segment
+    /// strings are folded onto continuation lines to stay within `width`
+    /// where possible. By default lines are never wrapped.
+    pub fn max_line_width(&mut self, width: Option<usize>) {
+        self.max_line_width = width;
+    }
+
+    /// Configures the maximum depth of nested blocks within function bodies
+    /// whose instructions are printed.
+    ///
+    /// When set, each run of instructions nested within more than `depth`
+    /// blocks, such as `block`, `loop`, and `if`, is replaced with a single
+    /// `(; ... ;)` comment. The instructions which begin and end the blocks
+    /// at `depth` are still printed. A depth of 0 only prints the
+    /// instructions at the top level of each function. The output is
+    /// therefore not equivalent to the input. By default all instructions
+    /// are printed.
+    pub fn max_depth(&mut self, depth: Option<u32>) {
+        self.max_depth = depth;
+    }
+
     /// Registers a custom `printer` function to get invoked whenever a custom
     /// section of name `section` is seen.
     ///
@@ -146,6 +215,20 @@ impl Printer {
         self.printers.insert(section.to_string(), Box::new(printer));
     }
 
+    /// Configures a `filter` which determines which sections are printed.
+    ///
+    /// The `filter` is invoked with each section of the binary and sections
+    /// for which it returns `false` are omitted from the output. Omitted
+    /// sections are still read so that the indices and names of items in
+    /// other sections are printed correctly. Function bodies are printed with
+    /// the function section, so the result for `Payload::FunctionSection`
+    /// determines whether they're printed.
+    ///
+    /// By default all sections are printed.
+    pub fn section_filter(&mut self, filter: impl FnMut(&Payload<'_>) -> bool + 'static) {
+        self.section_filter = Some(Box::new(filter));
+    }
+
     /// Gets the output result of this `Printer`, or where all output is going.
     pub fn result_mut(&mut self) -> &mut String {
         &mut self.result
@@ -160,6 +243,25 @@ impl Printer {
         Ok(mem::take(&mut self.result))
     }
 
+    /// Returns the current length of the output and line number if `payload`
+    /// is excluded by the section filter, so its output can be discarded once
+    /// it's been processed.
+    fn omit_section(&mut self, payload: &Payload<'_>) -> Option<(usize, usize)> {
+        match payload {
+            Payload::Version { .. }
+            | Payload::ModuleSection { .. }
+            | Payload::ComponentSection { .. }
+            | Payload::End(_) => return None,
+            _ => {}
+        }
+        let filter = self.section_filter.as_mut()?;
+        if filter(payload) {
+            None
+        } else {
+            Some((self.result.len(), self.line))
+        }
+    }
+
     fn read_names_and_code<'a>(
         &mut self,
         mut bytes: &'a [u8],
@@ -167,6 +269,10 @@ impl Printer {
         state: &mut State,
         code: &mut Vec<FunctionBody<'a>>,
     ) -> Result<()> {
+        let abbreviate = self.abbreviate_types && state.encoding == Encoding::Module;
+        let inline_exports = self.inline_exports && state.encoding == Encoding::Module;
+        let mut type_uses = HashMap::new();
+        let mut imports = HashMap::new();
         loop {
             let payload = match parser.parse(bytes, true)? {
                 Chunk::NeedMoreData(_) => unreachable!(),
@@ -176,6 +282,9 @@ impl Printer {
                 }
             };
 
+            if abbreviate {
+                count_type_uses(&payload, &mut type_uses)?;
+            }
             match payload {
                 Payload::FunctionSection(s) => {
                     if s.get_count() > MAX_WASM_FUNCTIONS {
@@ -190,6 +299,32 @@ impl Printer {
                 Payload::CodeSectionEntry(f) => {
                     code.push(f);
                 }
+                Payload::ImportSection(s) if inline_exports => {
+                    for import in s {
+                        let kind = match import?.ty {
+                            TypeRef::Func(_) => ExternalKind::Func,
+                            TypeRef::Table(_) => ExternalKind::Table,
+                            TypeRef::Memory(_) => ExternalKind::Memory,
+                            TypeRef::Global(_) => ExternalKind::Global,
+                            TypeRef::Tag(_) => ExternalKind::Tag,
+                        };
+                        *imports.entry(kind).or_insert(0) += 1;
+                    }
+                }
+                Payload::ExportSection(s) if inline_exports => {
+                    for export in s {
+                        let export = export?;
+                        if export.index < imports.get(&export.kind).copied().unwrap_or(0) {
+                            continue;
+                        }
+                        state
+                            .core
+                            .inline_exports
+                            .entry((export.kind, export.index))
+                            .or_default()
+                            .push(export.name.to_string());
+                    }
+                }
                 Payload::ModuleSection { range, .. } | Payload::ComponentSection { range, .. } => {
                     let offset = range.end - range.start;
                     if offset > bytes.len() {
@@ -208,9 +343,45 @@ impl Printer {
             }
         }
 
+        if abbreviate {
+            Self::abbreviate_types_of(state, &type_uses);
+        }
         Ok(())
     }
 
+    /// Determines which types of `state` are inlined at their use sites and
+    /// names those which are referenced more than once, given the number of
+    /// times each type is used.
+    fn abbreviate_types_of(state: &mut State, type_uses: &HashMap<u32, u32>) {
+        let mut unnamed = Vec::new();
+        for (idx, uses) in type_uses {
+            if *uses == 1 {
+                state.core.inline_types.insert(*idx);
+            } else if !state.core.type_names.contains_key(idx) {
+                unnamed.push(*idx);
+            }
+        }
+        unnamed.sort_unstable();
+
+        let names = unnamed
+            .iter()
+            .map(|idx| format!("type{}", idx))
+            .collect::<Vec<_>>();
+        let existing = state
+            .core
+            .type_names
+            .values()
+            .map(|n| n.identifier.clone().unwrap_or_else(|| n.name.clone()))
+            .collect::<Vec<_>>();
+        let mut used = existing.iter().map(|s| s.as_str()).collect::<HashSet<_>>();
+        let namings = unnamed
+            .iter()
+            .zip(&names)
+            .map(|(idx, name)| (*idx, Naming::new(name, *idx, "type", &mut used)))
+            .collect::<Vec<_>>();
+        state.core.type_names.extend(namings);
+    }
+
     fn ensure_module(states: &[State]) -> Result<()> {
         if !matches!(states.last().unwrap().encoding, Encoding::Module) {
             bail!("a module section was encountered when parsing a component");
@@ -243,6 +414,8 @@ impl Printer {
                     payload
                 }
             };
+            let omitted = self.omit_section(&payload);
+            let inlined = states.last().map_or(0, |s| s.core.inlined.len());
             match payload {
                 Payload::Version { encoding, .. } => {
                     if let Some(e) = expected {
@@ -254,9 +427,13 @@ impl Printer {
 
                     assert!(states.last().map(|s| s.encoding) != Some(Encoding::Module));
 
+                    states.push(State::new(encoding));
+                    if states.len() > 1 {
+                        self.print_nesting_label(&states)?;
+                    }
+
                     match encoding {
                         Encoding::Module => {
-                            states.push(State::new(Encoding::Module));
                             if states.len() > 1 {
                                 self.start_group("core module");
                             } else {
@@ -270,7 +447,6 @@ impl Printer {
                             }
                         }
                         Encoding::Component => {
-                            states.push(State::new(Encoding::Component));
                             self.start_group("component");
 
                             if states.len() > 1 {
@@ -443,11 +619,44 @@ impl Printer {
 
                 Payload::UnknownSection { id, .. } => bail!("found unknown section `{}`", id),
             }
+            if let Some((len, line)) = omitted {
+                self.result.truncate(len);
+                self.line = line;
+                // Exports which were printed inline in the discarded output
+                // are printed as separate fields instead.
+                if let Some(state) = states.last_mut() {
+                    state.core.inlined.truncate(inlined);
+                }
+            }
         }
 
         Ok(())
     }
 
+    /// Prints a comment describing where the innermost module or component in
+    /// `states` is located within its enclosing components, for example
+    /// `;; component 0 / core module 1`.
+    ///
+    /// The indices printed are those of each module or component within the
+    /// index space of its immediately enclosing component.
+    fn print_nesting_label(&mut self, states: &[State]) -> Result<()> {
+        self.result.push_str(";;");
+        for (i, pair) in states.windows(2).enumerate() {
+            let (parent, state) = (&pair[0], &pair[1]);
+            if i > 0 {
+                self.result.push_str(" /");
+            }
+            match state.encoding {
+                Encoding::Module => write!(self.result, " core module {}", parent.core.modules)?,
+                Encoding::Component => {
+                    write!(self.result, " component {}", parent.component.components)?
+                }
+            }
+        }
+        self.newline_unknown_pos();
+        Ok(())
+    }
+
     fn start_group(&mut self, name: &str) {
         self.result.push('(');
         self.result.push_str(name);
@@ -547,6 +756,15 @@ impl Printer {
     }
 
     fn print_type(&mut self, state: &mut State, ty: wasmparser::Type) -> Result<()> {
+        if state
+            .core
+            .inline_types
+            .contains(&(state.core.types.len() as u32))
+        {
+            let wasmparser::Type::Func(ty) = ty;
+            state.core.types.push(Some(ty));
+            return Ok(());
+        }
         self.start_group("type ");
         self.print_name(&state.core.type_names, state.core.types.len() as u32)?;
         self.result.push(' ');
@@ -580,7 +798,13 @@ impl Printer {
     fn print_types(&mut self, state: &mut State, parser: TypeSectionReader<'_>) -> Result<()> {
         for ty in parser.into_iter_with_offsets() {
             let (offset, ty) = ty?;
-            self.newline(offset);
+            if !state
+                .core
+                .inline_types
+                .contains(&(state.core.types.len() as u32))
+            {
+                self.newline(offset);
+            }
             self.print_type(state, ty)?;
         }
 
@@ -594,7 +818,7 @@ impl Printer {
         always_print_type: bool,
         names_for: Option<u32>,
     ) -> Result<Option<u32>> {
-        if always_print_type {
+        if always_print_type && !state.core.inline_types.contains(&idx) {
             self.print_type_ref(state, idx, true, None)?;
         }
 
@@ -700,7 +924,11 @@ impl Printer {
                     self.result.push(' ');
                     self.print_name(&state.core.func_names, state.core.funcs)?;
                 }
-                self.print_type_ref(state, *f, true, None)?;
+                if state.core.inline_types.contains(f) {
+                    self.print_core_functype_idx(state, *f, false, None)?;
+                } else {
+                    self.print_type_ref(state, *f, true, None)?;
+                }
             }
             TypeRef::Table(f) => self.print_table_type(state, f, index)?,
             TypeRef::Memory(f) => self.print_memory_type(state, f, index)?,
@@ -715,6 +943,7 @@ impl Printer {
         self.start_group("table ");
         if index {
             self.print_name(&state.core.table_names, state.core.tables)?;
+            self.print_inline_exports(state, ExternalKind::Table, state.core.tables)?;
             self.result.push(' ');
         }
         self.print_limits(ty.initial, ty.maximum)?;
@@ -727,6 +956,7 @@ impl Printer {
         self.start_group("memory ");
         if index {
             self.print_name(&state.core.memory_names, state.core.memories)?;
+            self.print_inline_exports(state, ExternalKind::Memory, state.core.memories)?;
             self.result.push(' ');
         }
         if ty.memory64 {
@@ -736,6 +966,13 @@ impl Printer {
         if ty.shared {
             self.result.push_str(" shared");
         }
+        if let Some(log2) = ty.page_size_log2 {
+            let size = match 1u64.checked_shl(log2) {
+                Some(size) => size,
+                None => bail!("custom page size of 2**{} bytes is too large", log2),
+            };
+            write!(self.result, " (pagesize {})", size)?;
+        }
         Ok(())
     }
 
@@ -743,6 +980,7 @@ impl Printer {
         self.start_group("tag ");
         if index {
             write!(self.result, "(;{};)", state.core.tags)?;
+            self.print_inline_exports(state, ExternalKind::Tag, state.core.tags)?;
         }
         self.print_core_functype_idx(state, ty.func_type_idx, true, None)?;
         Ok(())
@@ -763,6 +1001,7 @@ impl Printer {
         self.start_group("global ");
         if index {
             self.print_name(&state.core.global_names, state.core.globals)?;
+            self.print_inline_exports(state, ExternalKind::Global, state.core.globals)?;
             self.result.push(' ');
         }
         if ty.mutable {
@@ -781,6 +1020,9 @@ impl Printer {
             self.newline(offset);
             self.print_table_type(state, &table, true)?;
             self.end_group();
+            state
+                .core
+                .mark_inlined(ExternalKind::Table, state.core.tables);
             state.core.tables += 1;
         }
         Ok(())
@@ -792,6 +1034,9 @@ impl Printer {
             self.newline(offset);
             self.print_memory_type(state, &memory, true)?;
             self.end_group();
+            state
+                .core
+                .mark_inlined(ExternalKind::Memory, state.core.memories);
             state.core.memories += 1;
         }
         Ok(())
@@ -803,6 +1048,7 @@ impl Printer {
             self.newline(offset);
             self.print_tag_type(state, &tag, true)?;
             self.end_group();
+            state.core.mark_inlined(ExternalKind::Tag, state.core.tags);
             state.core.tags += 1;
         }
         Ok(())
@@ -816,6 +1062,9 @@ impl Printer {
             self.result.push(' ');
             self.print_const_expr(state, &global.init_expr)?;
             self.end_group();
+            state
+                .core
+                .mark_inlined(ExternalKind::Global, state.core.globals);
             state.core.globals += 1;
         }
         Ok(())
@@ -838,6 +1087,8 @@ impl Printer {
             self.start_group("func ");
             let func_idx = state.core.funcs;
             self.print_name(&state.core.func_names, func_idx)?;
+            self.print_inline_exports(state, ExternalKind::Func, func_idx)?;
+            state.core.mark_inlined(ExternalKind::Func, func_idx);
             let params = self
                 .print_core_functype_idx(state, ty, true, Some(func_idx))?
                 .unwrap_or(0);
@@ -875,6 +1126,9 @@ impl Printer {
             body.allow_memarg64(true);
 
             let mut buf = String::new();
+            // Whether the instructions since the last one printed have been
+            // elided because they're nested too deeply.
+            let mut elided = false;
             let mut op_printer = operator::PrintOperator::new(self, state);
             while !body.eof() {
                 // TODO
@@ -883,6 +1137,37 @@ impl Printer {
                 let op_kind = body.visit_operator(&mut op_printer)??;
                 mem::swap(&mut buf, &mut op_printer.printer.result);
 
+                // Instructions which end the current block, or are in the
+                // middle of it, are at the depth of the block itself.
+                let depth = op_printer.printer.nesting - nesting_start;
+                let op_depth = match op_kind {
+                    operator::OpKind::BlockMid
+                    | operator::OpKind::End
+                    | operator::OpKind::Delegate => depth.saturating_sub(1),
+                    _ => depth,
+                };
+                let too_deep = match op_printer.printer.max_depth {
+                    Some(max) => op_depth > max,
+                    None => false,
+                };
+                if too_deep && !body.eof() {
+                    if !elided {
+                        op_printer.printer.newline(offset);
+                        op_printer.printer.result.push_str("(; ... ;)");
+                        elided = true;
+                    }
+                    match op_kind {
+                        operator::OpKind::BlockStart => op_printer.printer.nesting += 1,
+                        operator::OpKind::End | operator::OpKind::Delegate => {
+                            op_printer.printer.nesting -= 1
+                        }
+                        _ => {}
+                    }
+                    buf.truncate(0);
+                    continue;
+                }
+                elided = false;
+
                 match op_kind {
                     // The final `end` in a reader is not printed, it's implied
                     // in the text format.
@@ -965,6 +1250,43 @@ impl Printer {
         }
     }
 
+    /// Starts a continuation line if appending `len` more bytes to the current
+    /// line would exceed the configured maximum line width.
+    ///
+    /// Returns whether a new line was started.
+    fn wrap_line(&mut self, len: usize) -> bool {
+        if !self.exceeds_line_width(len) {
+            return false;
+        }
+        self.continuation_line();
+        true
+    }
+
+    fn continuation_line(&mut self) {
+        self.nesting += 1;
+        self.newline_unknown_pos();
+        self.nesting -= 1;
+    }
+
+    fn exceeds_line_width(&self, len: usize) -> bool {
+        let max = match self.max_line_width {
+            Some(max) => max,
+            None => return false,
+        };
+        let (line, column) = match self.result.rfind('\n') {
+            Some(i) => (&self.result[i + 1..], self.result.len() - i - 1),
+            // Instructions are printed into a separate buffer before the
+            // newline and indentation preceding them, so account for that
+            // indentation here.
+            None => {
+                let indent = 2 * self.nesting.min(MAX_NESTING_TO_PRINT) as usize;
+                let offsets = if self.print_offsets { 11 } else { 0 };
+                (&self.result[..], indent + offsets + self.result.len())
+            }
+        };
+        column + len > max && !line.trim().is_empty()
+    }
+
     fn mem_instr(
         &mut self,
         state: &State,
@@ -1016,14 +1338,38 @@ impl Printer {
     }
 
     fn print_exports(&mut self, state: &State, data: ExportSectionReader) -> Result<()> {
+        let inlined = state.core.inlined.iter().collect::<HashSet<_>>();
         for export in data.into_iter_with_offsets() {
             let (offset, export) = export?;
+            if inlined.contains(&(export.kind, export.index)) {
+                continue;
+            }
             self.newline(offset);
             self.print_export(state, &export)?;
         }
         Ok(())
     }
 
+    /// Prints the exports of the item `index` of `kind` which are printed
+    /// inline, each preceded by a space.
+    fn print_inline_exports(
+        &mut self,
+        state: &State,
+        kind: ExternalKind,
+        index: u32,
+    ) -> Result<()> {
+        let names = match state.core.inline_exports.get(&(kind, index)) {
+            Some(names) => names,
+            None => return Ok(()),
+        };
+        for name in names {
+            self.result.push_str(" (export ");
+            self.print_str(name)?;
+            self.result.push(')');
+        }
+        Ok(())
+    }
+
     fn print_export(&mut self, state: &State, export: &Export) -> Result<()> {
         self.start_group("export ");
         self.print_str(export.name)?;
@@ -2224,8 +2570,17 @@ impl Printer {
 
     fn print_bytes(&mut self, bytes: &[u8]) -> Result<()> {
         self.result.push('"');
-        for byte in bytes {
-            if *byte >= 0x20 && *byte < 0x7f && *byte != b'"' && *byte != b'\\' {
+        for (i, byte) in bytes.iter().enumerate() {
+            let printable = *byte >= 0x20 && *byte < 0x7f && *byte != b'"' && *byte != b'\\';
+            // Account for this byte and a closing quote when deciding whether
+            // to split the string onto another line.
+            let len = if printable { 1 } else { 3 };
+            if i > 0 && self.exceeds_line_width(len + 1) {
+                self.result.push('"');
+                self.continuation_line();
+                self.result.push('"');
+            }
+            if printable {
                 self.result.push(*byte as char);
             } else {
                 self.hex_byte(*byte);
@@ -2381,7 +2736,11 @@ macro_rules! print_float {
                         exponent -= leading as $sint;
                     }
 
-                    self.result.push('.');
+                    // Subnormal powers of two have no fraction left once
+                    // they're normalized.
+                    if fraction > 0 {
+                        self.result.push('.');
+                    }
                     while fraction > 0 {
                         write!(self.result, "{:x}", fraction >> (int_width - 4))?;
                         fraction <<= 4;
@@ -2400,6 +2759,54 @@ impl Printer {
     print_float!(print_f64 f64 u64 i64 11);
 }
 
+/// Counts the uses of each core function type by items and instructions in
+/// `payload`.
+fn count_type_uses(payload: &Payload<'_>, uses: &mut HashMap<u32, u32>) -> Result<()> {
+    let mut add = |idx: u32| *uses.entry(idx).or_insert(0) += 1;
+    match payload {
+        Payload::ImportSection(s) => {
+            for import in s.clone() {
+                match import?.ty {
+                    TypeRef::Func(idx) => add(idx),
+                    TypeRef::Tag(ty) => add(ty.func_type_idx),
+                    _ => {}
+                }
+            }
+        }
+        Payload::FunctionSection(s) => {
+            for idx in s.clone() {
+                add(idx?);
+            }
+        }
+        Payload::TagSection(s) => {
+            for ty in s.clone() {
+                add(ty?.func_type_idx);
+            }
+        }
+        Payload::CodeSectionEntry(f) => {
+            let mut reader = f.get_operators_reader()?;
+            reader.allow_memarg64(true);
+            for op in reader {
+                match op? {
+                    Operator::Block { blockty }
+                    | Operator::Loop { blockty }
+                    | Operator::If { blockty }
+                    | Operator::Try { blockty } => {
+                        if let BlockType::FuncType(idx) = blockty {
+                            add(idx);
+                        }
+                    }
+                    Operator::CallIndirect { type_index, .. }
+                    | Operator::ReturnCallIndirect { type_index, .. } => add(type_index),
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
 impl Naming {
     fn new<'a>(name: &'a str, index: u32, group: &str, used: &mut HashSet<&'a str>) -> Naming {
         let mut identifier = None;
@@ -75,6 +75,11 @@ impl<'a, 'b> PrintOperator<'a, 'b> {
     }
 
     fn print_type_ref(&mut self, idx: u32) -> Result<()> {
+        if self.state.core.inline_types.contains(&idx) {
+            self.printer
+                .print_core_functype_idx(self.state, idx, false, None)?;
+            return Ok(());
+        }
         self.printer.print_type_ref(self.state, idx, true, None)
     }
 
@@ -175,7 +180,11 @@ impl<'a> VisitOperator<'a> for PrintOperator<'_, '_> {
         for item in table.targets().chain(Some(Ok(table.default()))) {
             let item = item?;
             let label = self.label(item);
-            write!(self.result(), " {item} (;{label};)")?;
+            let target = format!("{item} (;{label};)");
+            if !self.printer.wrap_line(target.len() + 1) {
+                self.push_str(" ");
+            }
+            self.push_str(&target);
         }
         Ok(OpKind::Normal)
     }
@@ -1090,6 +1099,9 @@ impl<'a> VisitOperator<'a> for PrintOperator<'_, '_> {
         self.mem_instr("v128.store", &memarg, 16)
     }
     fn visit_v128_const(&mut self, _pos: usize, value: V128) -> Self::Output {
+        // The binary format doesn't record the lane shape a constant was
+        // written with, so always print the `i32x4` shape in hexadecimal
+        // which shows every bit of the value.
         write!(self.result(), "v128.const i32x4")?;
         for chunk in value.bytes().chunks(4) {
             write!(
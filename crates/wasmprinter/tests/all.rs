@@ -169,3 +169,766 @@ fn dont_reserve_the_world() {
         err
     );
 }
+
+#[test]
+fn label_nested_modules_and_components() {
+    let bytes = wat::parse_str(
+        r#"
+            (component
+                (core module $a)
+                (component
+                    (core module $b
+                        (func (export "f"))
+                    )
+                )
+            )
+        "#,
+    )
+    .unwrap();
+    let wat = wasmprinter::print_bytes(&bytes).unwrap();
+    let labels = wat
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| l.starts_with(";;"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        labels,
+        [
+            ";; core module 0",
+            ";; component 0",
+            ";; component 0 / core module 0",
+        ],
+        "{}",
+        wat
+    );
+    assert!(
+        wat.contains("\n    ;; component 0 / core module 0\n    (core module (;0;)"),
+        "{}",
+        wat
+    );
+    wat::parse_str(&wat).unwrap();
+}
+
+#[test]
+fn max_line_width_wraps_br_table() {
+    let targets = (0..50).map(|i| (i % 3).to_string()).collect::<Vec<_>>();
+    let wat = format!(
+        r#"
+            (module
+                (func (param i32)
+                    {blocks}
+                    local.get 0
+                    br_table {targets}
+                    {ends}
+                )
+            )
+        "#,
+        blocks = "block ".repeat(3),
+        targets = targets.join(" "),
+        ends = "end ".repeat(3),
+    );
+    let bytes = wat::parse_str(&wat).unwrap();
+
+    // By default the entire `br_table` is printed on a single line.
+    let unwrapped = wasmprinter::print_bytes(&bytes).unwrap();
+    assert_eq!(
+        unwrapped.lines().filter(|l| l.contains("(;@")).count(),
+        1,
+        "{}",
+        unwrapped
+    );
+
+    let mut printer = wasmprinter::Printer::new();
+    printer.max_line_width(Some(80));
+    let wrapped = printer.print(&bytes).unwrap();
+    assert!(wrapped.lines().all(|l| l.len() <= 80), "{}", wrapped);
+    let lines = wrapped
+        .lines()
+        .filter(|l| l.contains("(;@"))
+        .collect::<Vec<_>>();
+    assert!(lines.len() > 1, "{}", wrapped);
+    assert!(lines[0].trim_start().starts_with("br_table 0 (;@"));
+    assert_eq!(
+        lines
+            .iter()
+            .map(|l| l.matches("(;@").count())
+            .sum::<usize>(),
+        50
+    );
+
+    // Wrapping doesn't change the meaning of the text.
+    assert_eq!(wat::parse_str(&wrapped).unwrap(), bytes);
+    assert_eq!(wat::parse_str(&unwrapped).unwrap(), bytes);
+}
+
+#[test]
+fn max_line_width_wraps_data() {
+    let data = "abcdefghij\\00".repeat(20);
+    let bytes = wat::parse_str(format!(
+        r#"(module (memory 1) (data (i32.const 0) "{data}"))"#
+    ))
+    .unwrap();
+
+    let mut printer = wasmprinter::Printer::new();
+    printer.max_line_width(Some(60));
+    let wrapped = printer.print(&bytes).unwrap();
+    assert!(wrapped.lines().all(|l| l.len() <= 60), "{}", wrapped);
+    assert!(wrapped.lines().count() > 5, "{}", wrapped);
+    assert_eq!(wat::parse_str(&wrapped).unwrap(), bytes);
+}
+
+#[test]
+fn abbreviate_types() {
+    let bytes = wat::parse_str(
+        r#"
+            (module
+                (type (func (param i32) (result i32)))
+                (type (func (param i64)))
+                (type (func (param f32)))
+                (type (func))
+                (import "" "f" (func (type 1)))
+                (table 1 funcref)
+                (func (type 0) local.get 0)
+                (func (type 0)
+                    f32.const 0
+                    i32.const 0
+                    call_indirect (type 2)
+                    local.get 0)
+            )
+        "#,
+    )
+    .unwrap();
+
+    let indexed = wasmprinter::print_bytes(&bytes).unwrap();
+    let mut printer = wasmprinter::Printer::new();
+    printer.abbreviate_types(true);
+    let abbreviated = printer.print(&bytes).unwrap();
+
+    let expected_indexed = r#"(module
+  (type (;0;) (func (param i32) (result i32)))
+  (type (;1;) (func (param i64)))
+  (type (;2;) (func (param f32)))
+  (type (;3;) (func))
+  (import "" "f" (func (;0;) (type 1)))
+  (func (;1;) (type 0) (param i32) (result i32)
+    local.get 0
+  )
+  (func (;2;) (type 0) (param i32) (result i32)
+    f32.const 0x0p+0 (;=0;)
+    i32.const 0
+    call_indirect (type 2)
+    local.get 0
+  )
+  (table (;0;) 1 funcref)
+)"#;
+    let expected_abbreviated = r#"(module
+  (type $type0 (;0;) (func (param i32) (result i32)))
+  (type (;3;) (func))
+  (import "" "f" (func (;0;) (param i64)))
+  (func (;1;) (type $type0) (param i32) (result i32)
+    local.get 0
+  )
+  (func (;2;) (type $type0) (param i32) (result i32)
+    f32.const 0x0p+0 (;=0;)
+    i32.const 0
+    call_indirect (param f32)
+    local.get 0
+  )
+  (table (;0;) 1 funcref)
+)"#;
+    assert_eq!(indexed.trim(), expected_indexed, "{}", indexed);
+    assert_eq!(abbreviated.trim(), expected_abbreviated, "{}", abbreviated);
+
+    // The abbreviated text still describes a module with the same functions,
+    // even though its types are defined in a different order.
+    let reparsed = wat::parse_str(&abbreviated).unwrap();
+    wasmparser::validate(&reparsed).unwrap();
+    let reprinted = printer.print(&reparsed).unwrap();
+    assert!(reprinted.contains("(import \"\" \"f\" (func (;0;) (param i64)))"));
+    assert!(reprinted.contains("call_indirect (param f32)"));
+
+    // Types are still counted in functions with 64-bit memory offsets.
+    let bytes = wat::parse_str(
+        r#"
+            (module
+                (memory i64 1)
+                (func (result i32)
+                    i64.const 0
+                    i32.load offset=0x100000000)
+            )
+        "#,
+    )
+    .unwrap();
+    let abbreviated = printer.print(&bytes).unwrap();
+    assert!(
+        abbreviated.contains("i32.load offset=4294967296"),
+        "{}",
+        abbreviated
+    );
+}
+
+#[test]
+fn relaxed_simd_roundtrip() {
+    use wasm_encoder::{
+        CodeSection, Function, FunctionSection, Instruction, Module, TypeSection, ValType,
+    };
+
+    let mut types = TypeSection::new();
+    types.function([ValType::V128; 3], [ValType::V128]);
+    let mut functions = FunctionSection::new();
+    functions.function(0);
+    let mut func = Function::new([]);
+    func.instruction(&Instruction::LocalGet(0))
+        .instruction(&Instruction::LocalGet(1))
+        .instruction(&Instruction::I8x16RelaxedSwizzle)
+        .instruction(&Instruction::LocalGet(1))
+        .instruction(&Instruction::LocalGet(2))
+        .instruction(&Instruction::F32x4RelaxedFma)
+        .instruction(&Instruction::LocalGet(0))
+        .instruction(&Instruction::LocalGet(2))
+        .instruction(&Instruction::I32x4RelaxedLaneselect)
+        .instruction(&Instruction::End);
+    let mut code = CodeSection::new();
+    code.function(&func);
+    let mut module = Module::new();
+    module.section(&types).section(&functions).section(&code);
+    let bytes = module.finish();
+
+    // The module is only valid with the relaxed-simd proposal enabled.
+    assert!(wasmparser::Validator::new().validate_all(&bytes).is_err());
+    wasmparser::Validator::new_with_features(wasmparser::WasmFeatures {
+        relaxed_simd: true,
+        ..Default::default()
+    })
+    .validate_all(&bytes)
+    .unwrap();
+
+    let text = wasmprinter::print_bytes(&bytes).unwrap();
+    for instr in [
+        "i8x16.relaxed_swizzle",
+        "f32x4.relaxed_fma",
+        "i32x4.relaxed_laneselect",
+    ] {
+        assert!(text.contains(instr), "{}", text);
+    }
+    assert_eq!(wat::parse_str(&text).unwrap(), bytes);
+}
+
+#[test]
+fn tail_call_roundtrip() {
+    use wasm_encoder::{
+        CodeSection, ElementSection, Elements, Function, FunctionSection, Instruction, Module,
+        TableSection, TableType, TypeSection, ValType,
+    };
+
+    // A recursive function counting its argument down to zero with
+    // `return_call`, and another doing the same through a table with
+    // `return_call_indirect`.
+    let text = r#"
+        (module
+            (type (func (param i32) (result i32)))
+            (table 1 1 funcref)
+            (elem (i32.const 0) func 1)
+            (func (type 0)
+                local.get 0
+                i32.eqz
+                if
+                    i32.const 0
+                    return
+                end
+                local.get 0
+                i32.const 1
+                i32.sub
+                return_call 0)
+            (func (type 0)
+                local.get 0
+                i32.const 0
+                return_call_indirect (type 0))
+        )
+    "#;
+    let assembled = wat::parse_str(text).unwrap();
+
+    let mut types = TypeSection::new();
+    types.function([ValType::I32], [ValType::I32]);
+    let mut functions = FunctionSection::new();
+    functions.function(0).function(0);
+    let mut tables = TableSection::new();
+    tables.table(TableType {
+        element_type: ValType::FuncRef,
+        minimum: 1,
+        maximum: Some(1),
+    });
+    let mut elements = ElementSection::new();
+    elements.active(
+        None,
+        &wasm_encoder::ConstExpr::i32_const(0),
+        ValType::FuncRef,
+        Elements::Functions(&[1]),
+    );
+    let mut countdown = Function::new([]);
+    countdown
+        .instruction(&Instruction::LocalGet(0))
+        .instruction(&Instruction::I32Eqz)
+        .instruction(&Instruction::If(wasm_encoder::BlockType::Empty))
+        .instruction(&Instruction::I32Const(0))
+        .instruction(&Instruction::Return)
+        .instruction(&Instruction::End)
+        .instruction(&Instruction::LocalGet(0))
+        .instruction(&Instruction::I32Const(1))
+        .instruction(&Instruction::I32Sub)
+        .instruction(&Instruction::ReturnCall(0))
+        .instruction(&Instruction::End);
+    let mut indirect = Function::new([]);
+    indirect
+        .instruction(&Instruction::LocalGet(0))
+        .instruction(&Instruction::I32Const(0))
+        .instruction(&Instruction::ReturnCallIndirect { ty: 0, table: 0 })
+        .instruction(&Instruction::End);
+    let mut code = CodeSection::new();
+    code.function(&countdown).function(&indirect);
+    let mut module = Module::new();
+    module
+        .section(&types)
+        .section(&functions)
+        .section(&tables)
+        .section(&elements)
+        .section(&code);
+    let encoded = module.finish();
+    assert_eq!(encoded, assembled);
+
+    // The module is only valid with the tail-call proposal enabled.
+    assert!(wasmparser::Validator::new().validate_all(&encoded).is_err());
+    wasmparser::Validator::new_with_features(wasmparser::WasmFeatures {
+        tail_call: true,
+        ..Default::default()
+    })
+    .validate_all(&encoded)
+    .unwrap();
+
+    let printed = wasmprinter::print_bytes(&encoded).unwrap();
+    assert!(printed.contains("return_call 0"), "{}", printed);
+    assert!(
+        printed.contains("return_call_indirect (type 0)"),
+        "{}",
+        printed
+    );
+    assert_eq!(wat::parse_str(&printed).unwrap(), encoded);
+}
+
+#[test]
+fn v128_const_lane_shapes() {
+    use wasm_encoder::{
+        CodeSection, Function, FunctionSection, Instruction, Module, TypeSection, ValType,
+    };
+
+    // Each lane shape of the text format with the value it encodes and how
+    // it's printed. The binary format doesn't record the lane shape so
+    // constants are always printed as four hexadecimal `i32` lanes, which
+    // shows every bit of the value and parses back to the same bytes.
+    let cases: &[(&str, u128, &str)] = &[
+        (
+            "i8x16 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15",
+            0x0f0e0d0c_0b0a0908_07060504_03020100,
+            "0x03020100 0x07060504 0x0b0a0908 0x0f0e0d0c",
+        ),
+        (
+            "i8x16 -1 -128 127 255 0x80 0 0 0 0 0 0 0 0 0 0 0",
+            0x00000000_00000000_00000080_ff7f80ff,
+            "0xff7f80ff 0x00000080 0x00000000 0x00000000",
+        ),
+        (
+            "i16x8 -1 0 1 2 3 4 5 0x7fff",
+            0x7fff0005_00040003_00020001_0000ffff,
+            "0x0000ffff 0x00020001 0x00040003 0x7fff0005",
+        ),
+        (
+            "i32x4 -1 0 0x12345678 2147483647",
+            0x7fffffff_12345678_00000000_ffffffff,
+            "0xffffffff 0x00000000 0x12345678 0x7fffffff",
+        ),
+        (
+            "i64x2 -2 0x0123456789abcdef",
+            0x01234567_89abcdef_ffffffff_fffffffe,
+            "0xfffffffe 0xffffffff 0x89abcdef 0x01234567",
+        ),
+        (
+            "f32x4 1.5 -0 nan:0x200000 inf",
+            0x7f800000_7fa00000_80000000_3fc00000,
+            "0x3fc00000 0x80000000 0x7fa00000 0x7f800000",
+        ),
+        (
+            "f64x2 0x1p-1 -nan",
+            0xfff80000_00000000_3fe00000_00000000,
+            "0x00000000 0x3fe00000 0x00000000 0xfff80000",
+        ),
+    ];
+
+    for (shape, value, lanes) in cases {
+        let text = format!("(module (func (result v128) v128.const {}))", shape);
+        let assembled = wat::parse_str(&text).unwrap();
+
+        let mut types = TypeSection::new();
+        types.function([], [ValType::V128]);
+        let mut functions = FunctionSection::new();
+        functions.function(0);
+        let mut func = Function::new([]);
+        func.instruction(&Instruction::V128Const(*value as i128))
+            .instruction(&Instruction::End);
+        let mut code = CodeSection::new();
+        code.function(&func);
+        let mut module = Module::new();
+        module.section(&types).section(&functions).section(&code);
+        let encoded = module.finish();
+        assert_eq!(encoded, assembled, "{}", shape);
+
+        let printed = wasmprinter::print_bytes(&encoded).unwrap();
+        let expected = format!("v128.const i32x4 {}\n", lanes);
+        assert!(printed.contains(&expected), "{}: {}", shape, printed);
+        assert_eq!(wat::parse_str(&printed).unwrap(), encoded, "{}", shape);
+    }
+}
+
+#[test]
+fn custom_page_sizes_roundtrip() {
+    use wasm_encoder::{MemorySection, MemoryType, Module};
+
+    let text = "(module (memory 65536 131072 (pagesize 1)))";
+    let assembled = wat::parse_str(text).unwrap();
+
+    let mut memories = MemorySection::new();
+    memories.memory(MemoryType {
+        minimum: 65536,
+        maximum: Some(131072),
+        memory64: false,
+        shared: false,
+        page_size_log2: Some(0),
+    });
+    let mut module = Module::new();
+    module.section(&memories);
+    let encoded = module.finish();
+    assert_eq!(encoded, assembled);
+
+    // The module is only valid with the custom-page-sizes proposal enabled.
+    assert!(wasmparser::Validator::new().validate_all(&encoded).is_err());
+    wasmparser::Validator::new_with_features(wasmparser::WasmFeatures {
+        custom_page_sizes: true,
+        ..Default::default()
+    })
+    .validate_all(&encoded)
+    .unwrap();
+
+    let printed = wasmprinter::print_bytes(&encoded).unwrap();
+    assert!(
+        printed.contains("(memory (;0;) 65536 131072 (pagesize 1))"),
+        "{}",
+        printed
+    );
+    assert_eq!(wat::parse_str(&printed).unwrap(), encoded);
+}
+
+#[test]
+fn component_roundtrip() {
+    // A component using each kind of component section, which should print
+    // as text that assembles back to the same binary.
+    let wasm = wat::parse_str(
+        r#"
+        (component
+          (type $point (record (field "x" u32) (field "y" u32)))
+          (type $shape (variant (case "circle" u32) (case "none")))
+          (type $perms (flags "read" "write"))
+          (type $color (enum "red" "green"))
+          (type $maybe (option string))
+          (type $res (result $point (error string)))
+          (type $pair (tuple u8 s64))
+          (type $list (list $point))
+          (type $f (func (param "p" $point) (param "s" $shape) (result $res)))
+          (type $it (instance
+            (type $t (record (field "a" float32)))
+            (export "get" (func (result $t)))
+          ))
+          (type $ct (component
+            (import "in" (func))
+            (export "out" (func (result string)))
+          ))
+          (import "log" (func $log (param "msg" string)))
+          (import "draw" (func $draw (param "p" $point)))
+          (import "host" (instance $host (type $it)))
+          (import "plugin" (component $plugin (type $ct)))
+          (core module $libc
+            (memory (export "memory") 1)
+            (func (export "realloc") (param i32 i32 i32 i32) (result i32) unreachable)
+            (func (export "run") (param i32 i32))
+          )
+          (core instance $libc (instantiate $libc))
+          (alias core export $libc "memory" (core memory $mem))
+          (alias core export $libc "realloc" (core func $realloc))
+          (alias export $host "get" (func $get))
+          (core func $log_lower (canon lower (func $log) string-encoding=utf8 (memory $mem) (realloc $realloc)))
+          (core instance $env (export "log" (func $log_lower)))
+          (func $run (param "msg" string)
+            (canon lift (core func $libc "run") (memory $mem) (realloc $realloc)))
+          (component $inner
+            (alias outer 1 $point (type $p))
+            (import "f" (func $f (param "p" $p)))
+            (export "f2" (func $f))
+          )
+          (instance $i (instantiate $inner (with "f" (func $draw))))
+          (instance $bundle (export "run" (func $run)) (export "point" (type $point)))
+          (export "run" (func $run))
+          (export "bundle" (instance $bundle))
+          (export "inner" (component $inner))
+          (export "libc" (core module $libc))
+        )
+        "#,
+    )
+    .unwrap();
+    wasmparser::Validator::new_with_features(wasmparser::WasmFeatures {
+        component_model: true,
+        ..Default::default()
+    })
+    .validate_all(&wasm)
+    .unwrap();
+
+    let printed = wasmprinter::print_bytes(&wasm).unwrap();
+    for expected in [
+        "(type (;5;) (result 0 (error string)))",
+        "(import \"host\" (instance (;0;) (type 9)))",
+        "(alias core export 0 \"memory\" (core memory (;0;)))",
+        "(canon lower (func 0) string-encoding=utf8 (memory 0) (realloc 0))",
+        "(canon lift (core func 2) (memory 0) (realloc 0))",
+        "(alias outer 1 0 (type (;0;)))",
+        "(export \"libc\" (core module 0))",
+    ] {
+        assert!(printed.contains(expected), "{}", printed);
+    }
+    assert_eq!(wat::parse_str(&printed).unwrap(), wasm);
+}
+
+#[test]
+fn inline_exports() {
+    let bytes = wat::parse_str(
+        r#"
+            (module
+                (import "" "f" (func $imported))
+                (import "" "g" (global i32))
+                (func $f)
+                (table 1 funcref)
+                (memory 1)
+                (global (mut i32) (i32.const 0))
+                (tag)
+                (export "f" (func $f))
+                (export "also-f" (func $f))
+                (export "table" (table 0))
+                (export "memory" (memory 0))
+                (export "tag" (tag 0))
+                (export "global" (global 1))
+                (export "imported" (func $imported))
+                (export "imported-global" (global 0))
+            )
+        "#,
+    )
+    .unwrap();
+
+    let mut printer = wasmprinter::Printer::new();
+    printer.inline_exports(true);
+    let inline = printer.print(&bytes).unwrap();
+    assert_eq!(
+        inline,
+        r#"(module
+  (type (;0;) (func))
+  (import "" "f" (func $imported (;0;) (type 0)))
+  (import "" "g" (global (;0;) i32))
+  (func $f (;1;) (export "f") (export "also-f") (type 0))
+  (table (;0;) (export "table") 1 funcref)
+  (memory (;0;) (export "memory") 1)
+  (tag (;0;) (export "tag") (type 0))
+  (global (;1;) (export "global") (mut i32) i32.const 0)
+  (export "imported" (func $imported))
+  (export "imported-global" (global 0))
+)"#
+    );
+
+    // Both forms assemble to the original module, since its exports are in
+    // the order of their items and inline exports are assembled in that
+    // order.
+    let separate = wasmprinter::print_bytes(&bytes).unwrap();
+    assert!(
+        separate.contains("(export \"f\" (func $f))"),
+        "{}",
+        separate
+    );
+    assert_eq!(wat::parse_str(&separate).unwrap(), bytes);
+    assert_eq!(wat::parse_str(&inline).unwrap(), bytes);
+
+    // Exports of items which aren't printed are printed separately.
+    let mut printer = wasmprinter::Printer::new();
+    printer.inline_exports(true);
+    printer.section_filter(|payload| !matches!(payload, wasmparser::Payload::TableSection(_)));
+    let filtered = printer.print(&bytes).unwrap();
+    assert!(!filtered.contains("(table (;0;)"), "{}", filtered);
+    assert!(
+        filtered.contains("(export \"table\" (table 0))"),
+        "{}",
+        filtered
+    );
+    assert!(
+        filtered.contains("(export \"f\") (export \"also-f\")"),
+        "{}",
+        filtered
+    );
+}
+
+#[test]
+fn float_bit_patterns_roundtrip() {
+    use wasm_encoder::{CodeSection, Function, FunctionSection, Instruction, Module, TypeSection};
+
+    let f32s = [
+        0x0000_0000, // 0
+        0x8000_0000, // -0
+        0x0000_0001, // smallest subnormal
+        0x007f_ffff, // largest subnormal
+        0x0080_0000, // smallest normal
+        0x7f7f_ffff, // largest normal
+        0x3f80_0001, // 1 + ulp
+        0x3eaa_aaab, // 1/3
+        0x7f80_0000, // inf
+        0xff80_0000, // -inf
+        0x7fc0_0000, // canonical nan
+        0xffc0_0000, // negative canonical nan
+        0x7f80_0001, // signaling nan with the smallest payload
+        0x7fff_ffff, // nan with the largest payload
+        0xff81_2345, // negative nan with a payload
+    ];
+    let f64s = [
+        0x0000_0000_0000_0000,
+        0x8000_0000_0000_0000,
+        0x0000_0000_0000_0001,
+        0x000f_ffff_ffff_ffff,
+        0x0010_0000_0000_0000,
+        0x7fef_ffff_ffff_ffff,
+        0x3ff0_0000_0000_0001,
+        0x3fd5_5555_5555_5555,
+        0x7ff0_0000_0000_0000,
+        0xfff0_0000_0000_0000,
+        0x7ff8_0000_0000_0000,
+        0xfff8_0000_0000_0000,
+        0x7ff0_0000_0000_0001,
+        0x7fff_ffff_ffff_ffff,
+        0xfff1_2345_6789_abcd,
+    ];
+
+    let mut types = TypeSection::new();
+    types.function([], []);
+    let mut functions = FunctionSection::new();
+    functions.function(0);
+    let mut func = Function::new([]);
+    for bits in f32s {
+        func.instruction(&Instruction::F32Const(f32::from_bits(bits)))
+            .instruction(&Instruction::Drop);
+    }
+    for bits in f64s {
+        func.instruction(&Instruction::F64Const(f64::from_bits(bits)))
+            .instruction(&Instruction::Drop);
+    }
+    func.instruction(&Instruction::End);
+    let mut code = CodeSection::new();
+    code.function(&func);
+    let mut module = Module::new();
+    module.section(&types).section(&functions).section(&code);
+    let bytes = module.finish();
+    wasmparser::validate(&bytes).unwrap();
+
+    // Every constant is printed exactly, with NaN payloads printed in hex.
+    let printed = wasmprinter::print_bytes(&bytes).unwrap();
+    for expected in [
+        "f32.const 0x1p-149 ",
+        "f32.const -0x0p+0 ",
+        "f32.const -nan (;",
+        "f32.const nan:0x1 (;",
+        "f32.const -nan:0x12345 (;",
+        "f64.const 0x1.5555555555555p-2 ",
+        "f64.const nan:0xfffffffffffff (;",
+    ] {
+        assert!(printed.contains(expected), "{}", printed);
+    }
+    assert_eq!(wat::parse_str(&printed).unwrap(), bytes);
+}
+
+#[test]
+fn max_depth() {
+    let bytes = wat::parse_str(
+        r#"
+            (module
+                (func (param i32) (result i32)
+                    block
+                        i32.const 1
+                        drop
+                        loop
+                            local.get 0
+                            if
+                                nop
+                                block
+                                    nop
+                                end
+                            else
+                                br 2
+                            end
+                            nop
+                        end
+                        nop
+                    end
+                    local.get 0)
+            )
+        "#,
+    )
+    .unwrap();
+
+    let print = |depth| {
+        let mut printer = wasmprinter::Printer::new();
+        printer.max_depth(depth);
+        printer.print(&bytes).unwrap()
+    };
+    assert_eq!(
+        print(Some(1)),
+        r#"(module
+  (type (;0;) (func (param i32) (result i32)))
+  (func (;0;) (type 0) (param i32) (result i32)
+    block  ;; label = @1
+      i32.const 1
+      drop
+      loop  ;; label = @2
+        (; ... ;)
+      end
+      nop
+    end
+    local.get 0
+  )
+)"#
+    );
+    assert_eq!(
+        print(Some(2)),
+        r#"(module
+  (type (;0;) (func (param i32) (result i32)))
+  (func (;0;) (type 0) (param i32) (result i32)
+    block  ;; label = @1
+      i32.const 1
+      drop
+      loop  ;; label = @2
+        local.get 0
+        if  ;; label = @3
+          (; ... ;)
+        else
+          (; ... ;)
+        end
+        nop
+      end
+      nop
+    end
+    local.get 0
+  )
+)"#
+    );
+    assert_ne!(print(Some(3)), print(None));
+    assert_eq!(print(Some(4)), print(None));
+    assert!(print(None).contains("br 2 (;@1;)"));
+}
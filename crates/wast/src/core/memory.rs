@@ -36,6 +36,8 @@ pub enum MemoryKind<'a> {
     Inline {
         /// Whether or not this will be creating a 32-bit memory
         is_32: bool,
+        /// The log base 2 of a custom page size for this memory
+        page_size_log2: Option<u32>,
         /// The inline data specified for this memory
         data: Vec<DataVal<'a>>,
     },
@@ -51,7 +53,7 @@ impl<'a> Parse<'a> for Memory<'a> {
         // Afterwards figure out which style this is, either:
         //
         //  *   `(import "a" "b") limits`
-        //  *   `(data ...)`
+        //  *   `(pagesize N)? (data ...)`
         //  *   `limits`
         let mut l = parser.lookahead1();
         let kind = if let Some(import) = parser.parse()? {
@@ -59,12 +61,15 @@ impl<'a> Parse<'a> for Memory<'a> {
                 import,
                 ty: parser.parse()?,
             }
-        } else if l.peek::<LParen>() || parser.peek2::<LParen>() {
+        } else if l.peek::<LParen>()
+            || ((parser.peek::<kw::i32>() || parser.peek::<kw::i64>()) && parser.peek2::<LParen>())
+        {
             let is_32 = if parser.parse::<Option<kw::i32>>()?.is_some() {
                 true
             } else {
                 parser.parse::<Option<kw::i64>>()?.is_none()
             };
+            let page_size_log2 = page_size(parser)?;
             let data = parser.parens(|parser| {
                 parser.parse::<kw::data>()?;
                 let mut data = Vec::new();
@@ -73,7 +78,11 @@ impl<'a> Parse<'a> for Memory<'a> {
                 }
                 Ok(data)
             })?;
-            MemoryKind::Inline { data, is_32 }
+            MemoryKind::Inline {
+                data,
+                is_32,
+                page_size_log2,
+            }
         } else if l.peek::<u32>() || l.peek::<kw::i32>() || l.peek::<kw::i64>() {
             MemoryKind::Normal(parser.parse()?)
         } else {
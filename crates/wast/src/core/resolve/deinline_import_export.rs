@@ -48,24 +48,33 @@ pub fn run(fields: &mut Vec<ModuleField>) {
                     }
                     // If data is defined inline insert an explicit `data` module
                     // field here instead, switching this to a `Normal` memory.
-                    MemoryKind::Inline { is_32, ref data } => {
-                        let len = data.iter().map(|l| l.len()).sum::<usize>() as u32;
-                        let pages = (len + page_size() - 1) / page_size();
+                    MemoryKind::Inline {
+                        is_32,
+                        page_size_log2,
+                        ref data,
+                    } => {
+                        let len = data.iter().map(|l| l.len()).sum::<usize>() as u64;
+                        let page_size = 1u64
+                            .checked_shl(page_size_log2.unwrap_or(16))
+                            .unwrap_or(u64::MAX);
+                        let pages = len.div_ceil(page_size);
                         let kind = MemoryKind::Normal(if is_32 {
                             MemoryType::B32 {
                                 limits: Limits {
-                                    min: pages,
-                                    max: Some(pages),
+                                    min: pages as u32,
+                                    max: Some(pages as u32),
                                 },
                                 shared: false,
+                                page_size_log2,
                             }
                         } else {
                             MemoryType::B64 {
                                 limits: Limits64 {
-                                    min: u64::from(pages),
-                                    max: Some(u64::from(pages)),
+                                    min: pages,
+                                    max: Some(pages),
                                 },
                                 shared: false,
+                                page_size_log2,
                             }
                         });
                         let data = match mem::replace(&mut m.kind, kind) {
@@ -206,10 +215,6 @@ pub fn run(fields: &mut Vec<ModuleField>) {
 
         fields.push(item);
     }
-
-    fn page_size() -> u32 {
-        1 << 16
-    }
 }
 
 fn export<'a>(
@@ -1,5 +1,6 @@
 use crate::core::*;
 use crate::kw;
+use crate::Error;
 use crate::parser::{Cursor, Parse, Parser, Peek, Result};
 use crate::token::{Id, Index, LParen, NameAnnotation, Span};
 use std::mem;
@@ -391,6 +392,8 @@ pub enum MemoryType {
         limits: Limits,
         /// Whether or not this is a shared (atomic) memory type
         shared: bool,
+        /// The log base 2 of a custom page size for this memory
+        page_size_log2: Option<u32>,
     },
     /// A 64-bit memory
     B64 {
@@ -398,6 +401,8 @@ pub enum MemoryType {
         limits: Limits64,
         /// Whether or not this is a shared (atomic) memory type
         shared: bool,
+        /// The log base 2 of a custom page size for this memory
+        page_size_log2: Option<u32>,
     },
 }
 
@@ -407,16 +412,43 @@ impl<'a> Parse<'a> for MemoryType {
             parser.parse::<kw::i64>()?;
             let limits = parser.parse()?;
             let shared = parser.parse::<Option<kw::shared>>()?.is_some();
-            Ok(MemoryType::B64 { limits, shared })
+            let page_size_log2 = page_size(parser)?;
+            Ok(MemoryType::B64 {
+                limits,
+                shared,
+                page_size_log2,
+            })
         } else {
             parser.parse::<Option<kw::i32>>()?;
             let limits = parser.parse()?;
             let shared = parser.parse::<Option<kw::shared>>()?.is_some();
-            Ok(MemoryType::B32 { limits, shared })
+            let page_size_log2 = page_size(parser)?;
+            Ok(MemoryType::B32 {
+                limits,
+                shared,
+                page_size_log2,
+            })
         }
     }
 }
 
+/// Parses an optional `(pagesize N)` annotation of a memory's custom page
+/// size, returning the log base 2 of the page size.
+pub(crate) fn page_size(parser: Parser<'_>) -> Result<Option<u32>> {
+    if !parser.peek2::<kw::pagesize>() {
+        return Ok(None);
+    }
+    parser.parens(|parser| {
+        parser.parse::<kw::pagesize>()?;
+        let span = parser.cur_span();
+        let size = parser.parse::<u64>()?;
+        if !size.is_power_of_two() {
+            return Err(Error::new(span, "invalid custom page size".to_string()));
+        }
+        Ok(Some(size.trailing_zeros()))
+    })
+}
+
 /// A function type with parameters and results.
 #[derive(Clone, Debug, Default)]
 pub struct FunctionType<'a> {
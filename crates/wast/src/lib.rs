@@ -444,6 +444,7 @@ pub mod kw {
     custom_keyword!(nullref);
     custom_keyword!(offset);
     custom_keyword!(outer);
+    custom_keyword!(pagesize);
     custom_keyword!(param);
     custom_keyword!(parent);
     custom_keyword!(passive);
@@ -36,6 +36,7 @@ fuzz_target!(|data: &[u8]| {
         mutable_global: (byte2 & 0b0010_0000) != 0,
         saturating_float_to_int: (byte2 & 0b0100_0000) != 0,
         sign_extension: (byte2 & 0b1000_0000) != 0,
+        custom_page_sizes: (byte3 & 0b0000_0010) != 0,
     });
     let use_maybe_invalid = byte3 & 0b0000_0001 != 0;
 
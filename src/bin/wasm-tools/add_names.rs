@@ -0,0 +1,301 @@
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use wasm_encoder::{IndirectNameMap, NameMap, NameSection, RawSection};
+use wasmparser::{ImportCounts, Name, NameSectionReader, Parser, Payload, Validator};
+
+/// Add a `name` section to a WebAssembly module from a JSON symbol map.
+///
+/// This is the inverse of stripping names, allowing build tools to reattach
+/// symbols to a module after it has been optimized or stripped. The JSON file
+/// is an object with any of the keys `module`, `functions`, `locals`,
+/// `labels`, `types`, `tables`, `memories`, `globals`, `elements`, and `data`.
+/// `module` is a string, `locals` and `labels` map function indices to maps of
+/// indices to names, and the others map indices to names:
+///
+/// ```json
+/// {
+///     "module": "foo",
+///     "functions": { "0": "main", "1": "helper" },
+///     "locals": { "0": { "0": "argc", "1": "argv" } }
+/// }
+/// ```
+///
+/// Names already in the module are kept unless `--replace` is passed, with
+/// names in the JSON file taking precedence.
+///
+/// ## Example
+///
+/// $ wasm-tools add-names foo.wasm --names names.json -o out.wasm
+#[derive(clap::Parser)]
+pub struct Opts {
+    #[clap(flatten)]
+    io: wasm_tools::InputOutput,
+
+    /// The JSON file containing the names to add.
+    #[clap(long, value_name = "PATH")]
+    names: PathBuf,
+
+    /// Discard any names already in the module instead of merging them.
+    #[clap(long)]
+    replace: bool,
+
+    /// Output the text format of WebAssembly instead of the binary format.
+    #[clap(short = 't', long)]
+    wat: bool,
+}
+
+type Map = BTreeMap<u32, String>;
+type IndirectMap = BTreeMap<u32, Map>;
+
+/// The names of a module, sorted by index as the `name` section requires.
+#[derive(Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Names {
+    module: Option<String>,
+    #[serde(default)]
+    functions: Map,
+    #[serde(default)]
+    locals: IndirectMap,
+    #[serde(default)]
+    labels: IndirectMap,
+    #[serde(default)]
+    types: Map,
+    #[serde(default)]
+    tables: Map,
+    #[serde(default)]
+    memories: Map,
+    #[serde(default)]
+    globals: Map,
+    #[serde(default)]
+    elements: Map,
+    #[serde(default)]
+    data: Map,
+}
+
+/// The number of items in each index space of a module.
+#[derive(Default)]
+struct Counts {
+    imports: ImportCounts,
+    types: u32,
+    functions: u32,
+    tables: u32,
+    memories: u32,
+    globals: u32,
+    elements: u32,
+    data: u32,
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let input = self.io.parse_input_wasm()?;
+        let json = std::fs::read_to_string(&self.names)
+            .with_context(|| format!("failed to read `{}`", self.names.display()))?;
+        let new: Names = serde_json::from_str(&json)
+            .with_context(|| format!("failed to parse `{}`", self.names.display()))?;
+
+        let mut names = Names::default();
+        let mut counts = Counts::default();
+        let mut module = wasm_encoder::Module::new();
+        for payload in Parser::new(0).parse_all(&input) {
+            let payload = payload?;
+            match &payload {
+                Payload::Version { encoding, .. } if *encoding != wasmparser::Encoding::Module => {
+                    bail!("names can only be added to core wasm modules")
+                }
+                Payload::CustomSection(c) if c.name() == "name" => {
+                    if !self.replace {
+                        names.read(NameSectionReader::new(c.data(), c.data_offset())?)?;
+                    }
+                    continue;
+                }
+                Payload::ImportSection(s) => {
+                    counts.imports = ImportCounts::from_reader(s.clone())?;
+                }
+                Payload::TypeSection(s) => counts.types = s.get_count(),
+                Payload::FunctionSection(s) => counts.functions = s.get_count(),
+                Payload::TableSection(s) => counts.tables = s.get_count(),
+                Payload::MemorySection(s) => counts.memories = s.get_count(),
+                Payload::GlobalSection(s) => counts.globals = s.get_count(),
+                Payload::ElementSection(s) => counts.elements = s.get_count(),
+                Payload::DataSection(s) => counts.data = s.get_count(),
+                _ => {}
+            }
+            if let Some((id, range)) = payload.as_section() {
+                module.section(&RawSection {
+                    id,
+                    data: &input[range],
+                });
+            }
+        }
+
+        new.check(&counts)?;
+        names.merge(new);
+        module.section(&names.encode());
+        let output = module.finish();
+
+        // Validate with all features enabled since this command doesn't
+        // change the module's code, only its names.
+        Validator::new_with_features(wasm_tools::all_core_features())
+            .validate_all(&output)
+            .context("output failed to validate")?;
+
+        self.io.output(wasm_tools::Output::Wasm {
+            bytes: &output,
+            wat: self.wat,
+        })?;
+        Ok(())
+    }
+}
+
+impl Names {
+    /// Reads the names from an existing `name` section.
+    fn read(&mut self, reader: NameSectionReader<'_>) -> Result<()> {
+        for name in reader {
+            match name? {
+                Name::Module(name) => self.module = Some(name.get_name()?.to_string()),
+                Name::Function(map) => read_map(&mut self.functions, map.get_map()?)?,
+                Name::Local(map) => read_indirect_map(&mut self.locals, map)?,
+                Name::Label(map) => read_indirect_map(&mut self.labels, map)?,
+                Name::Type(map) => read_map(&mut self.types, map.get_map()?)?,
+                Name::Table(map) => read_map(&mut self.tables, map.get_map()?)?,
+                Name::Memory(map) => read_map(&mut self.memories, map.get_map()?)?,
+                Name::Global(map) => read_map(&mut self.globals, map.get_map()?)?,
+                Name::Element(map) => read_map(&mut self.elements, map.get_map()?)?,
+                Name::Data(map) => read_map(&mut self.data, map.get_map()?)?,
+                Name::Unknown { ty, .. } => {
+                    log::warn!("discarding unknown name subsection {}", ty);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds `other` to these names, replacing any names for the same items.
+    fn merge(&mut self, other: Names) {
+        if other.module.is_some() {
+            self.module = other.module;
+        }
+        self.functions.extend(other.functions);
+        for (func, locals) in other.locals {
+            self.locals.entry(func).or_default().extend(locals);
+        }
+        for (func, labels) in other.labels {
+            self.labels.entry(func).or_default().extend(labels);
+        }
+        self.types.extend(other.types);
+        self.tables.extend(other.tables);
+        self.memories.extend(other.memories);
+        self.globals.extend(other.globals);
+        self.elements.extend(other.elements);
+        self.data.extend(other.data);
+    }
+
+    /// Ensures that every name refers to an item which exists.
+    fn check(&self, counts: &Counts) -> Result<()> {
+        let functions = counts.imports.funcs + counts.functions;
+        check_map("function", &self.functions, functions)?;
+        check_map("function", &self.locals, functions)?;
+        check_map("function", &self.labels, functions)?;
+        check_map("type", &self.types, counts.types)?;
+        check_map("table", &self.tables, counts.imports.tables + counts.tables)?;
+        check_map(
+            "memory",
+            &self.memories,
+            counts.imports.memories + counts.memories,
+        )?;
+        check_map(
+            "global",
+            &self.globals,
+            counts.imports.globals + counts.globals,
+        )?;
+        check_map("element segment", &self.elements, counts.elements)?;
+        check_map("data segment", &self.data, counts.data)?;
+        Ok(())
+    }
+
+    /// Encodes these names, with subsections in the order required by the
+    /// specification.
+    fn encode(&self) -> NameSection {
+        let mut section = NameSection::new();
+        if let Some(name) = &self.module {
+            section.module(name);
+        }
+        if !self.functions.is_empty() {
+            section.functions(&name_map(&self.functions));
+        }
+        if !self.locals.is_empty() {
+            section.locals(&indirect_name_map(&self.locals));
+        }
+        if !self.labels.is_empty() {
+            section.labels(&indirect_name_map(&self.labels));
+        }
+        if !self.types.is_empty() {
+            section.types(&name_map(&self.types));
+        }
+        if !self.tables.is_empty() {
+            section.tables(&name_map(&self.tables));
+        }
+        if !self.memories.is_empty() {
+            section.memories(&name_map(&self.memories));
+        }
+        if !self.globals.is_empty() {
+            section.globals(&name_map(&self.globals));
+        }
+        if !self.elements.is_empty() {
+            section.elements(&name_map(&self.elements));
+        }
+        if !self.data.is_empty() {
+            section.data(&name_map(&self.data));
+        }
+        section
+    }
+}
+
+fn read_map(dst: &mut Map, mut reader: wasmparser::NamingReader<'_>) -> Result<()> {
+    for _ in 0..reader.get_count() {
+        let naming = reader.read()?;
+        dst.insert(naming.index, naming.name.to_string());
+    }
+    Ok(())
+}
+
+fn read_indirect_map(dst: &mut IndirectMap, map: wasmparser::IndirectNameMap<'_>) -> Result<()> {
+    let mut reader = map.get_indirect_map()?;
+    for _ in 0..reader.get_indirect_count() {
+        let naming = reader.read()?;
+        read_map(
+            dst.entry(naming.indirect_index).or_default(),
+            naming.get_map()?,
+        )?;
+    }
+    Ok(())
+}
+
+fn check_map<T>(desc: &str, map: &BTreeMap<u32, T>, count: u32) -> Result<()> {
+    if let Some((&index, _)) = map.range(count..).next() {
+        bail!(
+            "cannot name {} {}, the index is out of bounds as the module has {}",
+            desc,
+            index,
+            count,
+        );
+    }
+    Ok(())
+}
+
+fn name_map(map: &Map) -> NameMap {
+    let mut ret = NameMap::new();
+    for (index, name) in map {
+        ret.append(*index, name);
+    }
+    ret
+}
+
+fn indirect_name_map(map: &IndirectMap) -> IndirectNameMap {
+    let mut ret = IndirectNameMap::new();
+    for (index, names) in map {
+        ret.append(*index, &name_map(names));
+    }
+    ret
+}
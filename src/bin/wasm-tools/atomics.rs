@@ -0,0 +1,380 @@
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::io::Write;
+use wasmparser::{
+    Encoding, FunctionBody, ImportCounts, Name, NameSectionReader, Operator, Parser, Payload,
+};
+
+/// Report the atomic operations in a WebAssembly module and flag non-atomic
+/// accesses to shared memories.
+///
+/// With the threads proposal a memory may be marked `shared` and accessed
+/// concurrently by multiple threads. Plain loads and stores of a shared memory
+/// are not synchronized with other threads, and while they're valid they're
+/// often a bug. This lists every atomic instruction by function, along with
+/// the memory it accesses, and warns about every non-atomic load or store of
+/// a shared memory.
+///
+/// Offsets are relative to the start of the module. The process exits
+/// successfully even if warnings are reported.
+#[derive(clap::Parser)]
+pub struct Opts {
+    #[clap(flatten)]
+    io: wasm_tools::InputOutput,
+
+    /// Print the report as JSON instead of as text.
+    #[clap(long)]
+    json: bool,
+}
+
+#[derive(serde::Serialize)]
+struct Report {
+    /// The indices of all shared memories, imported or defined.
+    shared_memories: Vec<u32>,
+    /// The functions with atomic instructions or non-atomic accesses of
+    /// shared memories.
+    functions: Vec<Function>,
+}
+
+#[derive(serde::Serialize)]
+struct Function {
+    index: u32,
+    name: Option<String>,
+    atomics: Vec<Instruction>,
+    non_atomic_shared_accesses: Vec<Instruction>,
+}
+
+#[derive(serde::Serialize)]
+struct Instruction {
+    offset: usize,
+    instruction: &'static str,
+    /// The memory accessed, which is `None` for `atomic.fence`.
+    memory: Option<u32>,
+}
+
+/// An instruction which accesses memory.
+struct Access {
+    name: &'static str,
+    atomic: bool,
+    memory: Option<u32>,
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let wasm = self.io.parse_input_wasm()?;
+        let report = analyze(&wasm)?;
+
+        let mut output = self.io.output_writer()?;
+        if self.json {
+            serde_json::to_writer_pretty(&mut output, &report)?;
+            writeln!(output)?;
+            return Ok(());
+        }
+
+        if report.shared_memories.is_empty() {
+            writeln!(output, "shared memories: (none)")?;
+        } else {
+            let memories = report
+                .shared_memories
+                .iter()
+                .map(|m| m.to_string())
+                .collect::<Vec<_>>();
+            writeln!(output, "shared memories: {}", memories.join(", "))?;
+        }
+
+        let mut atomics = 0;
+        let mut warnings = 0;
+        for func in report.functions.iter() {
+            write!(output, "func {}", func.index)?;
+            if let Some(name) = &func.name {
+                write!(output, " `{}`", name)?;
+            }
+            writeln!(output, ":")?;
+
+            // Interleave atomic instructions and warnings in the order they
+            // appear in the function.
+            let mut instrs = func
+                .atomics
+                .iter()
+                .map(|i| (i, false))
+                .chain(func.non_atomic_shared_accesses.iter().map(|i| (i, true)))
+                .collect::<Vec<_>>();
+            instrs.sort_by_key(|(i, _)| i.offset);
+            for (instr, warning) in instrs {
+                write!(output, "  {:#x}: ", instr.offset)?;
+                if warning {
+                    write!(output, "warning: non-atomic ")?;
+                }
+                write!(output, "{}", instr.instruction)?;
+                match (instr.memory, warning) {
+                    (Some(memory), true) => writeln!(output, " of shared memory {}", memory)?,
+                    (Some(memory), false) => writeln!(output, " (memory {})", memory)?,
+                    (None, _) => writeln!(output)?,
+                }
+            }
+            atomics += func.atomics.len();
+            warnings += func.non_atomic_shared_accesses.len();
+        }
+        writeln!(
+            output,
+            "{} atomic instructions, {} non-atomic accesses of shared memories",
+            atomics, warnings
+        )?;
+        Ok(())
+    }
+}
+
+/// Finds all the atomic instructions and non-atomic accesses of shared
+/// memories in the core module `wasm`.
+fn analyze(wasm: &[u8]) -> Result<Report> {
+    let mut shared = Vec::new();
+    let mut imported_funcs = 0;
+    let mut names = HashMap::new();
+    let mut bodies = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::Version {
+                encoding: Encoding::Component,
+                ..
+            } => bail!("components are not supported"),
+            Payload::ImportSection(reader) => {
+                imported_funcs = ImportCounts::from_reader(reader.clone())?.funcs;
+                for import in reader {
+                    if let wasmparser::TypeRef::Memory(ty) = import?.ty {
+                        shared.push(ty.shared);
+                    }
+                }
+            }
+            Payload::MemorySection(reader) => {
+                for ty in reader {
+                    shared.push(ty?.shared);
+                }
+            }
+            Payload::CodeSectionEntry(body) => bodies.push(body),
+            Payload::CustomSection(c) if c.name() == "name" => {
+                let reader = NameSectionReader::new(c.data(), c.data_offset())?;
+                // Names are only used to make the output friendlier, so an
+                // invalid name section is ignored.
+                let _ = read_function_names(reader, &mut names);
+            }
+            _ => {}
+        }
+    }
+
+    let mut functions = Vec::new();
+    for (i, body) in bodies.iter().enumerate() {
+        let index = imported_funcs + i as u32;
+        let mut func = analyze_body(body, &shared)?;
+        if func.atomics.is_empty() && func.non_atomic_shared_accesses.is_empty() {
+            continue;
+        }
+        func.index = index;
+        func.name = names.get(&index).cloned();
+        functions.push(func);
+    }
+
+    Ok(Report {
+        shared_memories: shared
+            .iter()
+            .enumerate()
+            .filter(|(_, shared)| **shared)
+            .map(|(i, _)| i as u32)
+            .collect(),
+        functions,
+    })
+}
+
+fn read_function_names(
+    reader: NameSectionReader<'_>,
+    names: &mut HashMap<u32, String>,
+) -> Result<()> {
+    for name in reader {
+        if let Name::Function(map) = name? {
+            let mut map = map.get_map()?;
+            for _ in 0..map.get_count() {
+                let naming = map.read()?;
+                names.insert(naming.index, naming.name.to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Finds the memory accesses of interest in a single function body, where
+/// `shared` records whether each memory is shared.
+fn analyze_body(body: &FunctionBody<'_>, shared: &[bool]) -> Result<Function> {
+    let mut func = Function {
+        index: 0,
+        name: None,
+        atomics: Vec::new(),
+        non_atomic_shared_accesses: Vec::new(),
+    };
+    let mut reader = body.get_operators_reader()?;
+    reader.allow_memarg64(true);
+    while !reader.eof() {
+        let (op, offset) = reader.read_with_offset()?;
+        let access = match memory_access(&op) {
+            Some(access) => access,
+            None => continue,
+        };
+        let instr = Instruction {
+            offset,
+            instruction: access.name,
+            memory: access.memory,
+        };
+        if access.atomic {
+            func.atomics.push(instr);
+        } else if let Some(true) = access.memory.and_then(|m| shared.get(m as usize).copied()) {
+            func.non_atomic_shared_accesses.push(instr);
+        }
+    }
+    Ok(func)
+}
+
+/// Generates `memory_access` from the names of instructions with a `memarg`,
+/// split into those which are atomic and those which aren't.
+macro_rules! memory_accesses {
+    (
+        plain { $($plain:ident => $plain_name:literal,)* }
+        atomic { $($atomic:ident => $atomic_name:literal,)* }
+    ) => {
+        /// Returns how `op` accesses memory, if it does so with a `memarg`.
+        fn memory_access(op: &Operator<'_>) -> Option<Access> {
+            let (name, atomic, memarg) = match op {
+                $(Operator::$plain { memarg, .. } => ($plain_name, false, memarg),)*
+                $(Operator::$atomic { memarg } => ($atomic_name, true, memarg),)*
+                Operator::AtomicFence => {
+                    return Some(Access {
+                        name: "atomic.fence",
+                        atomic: true,
+                        memory: None,
+                    })
+                }
+                _ => return None,
+            };
+            Some(Access {
+                name,
+                atomic,
+                memory: Some(memarg.memory),
+            })
+        }
+    };
+}
+
+memory_accesses! {
+    plain {
+        I32Load => "i32.load",
+        I64Load => "i64.load",
+        F32Load => "f32.load",
+        F64Load => "f64.load",
+        I32Load8S => "i32.load8_s",
+        I32Load8U => "i32.load8_u",
+        I32Load16S => "i32.load16_s",
+        I32Load16U => "i32.load16_u",
+        I64Load8S => "i64.load8_s",
+        I64Load8U => "i64.load8_u",
+        I64Load16S => "i64.load16_s",
+        I64Load16U => "i64.load16_u",
+        I64Load32S => "i64.load32_s",
+        I64Load32U => "i64.load32_u",
+        I32Store => "i32.store",
+        I64Store => "i64.store",
+        F32Store => "f32.store",
+        F64Store => "f64.store",
+        I32Store8 => "i32.store8",
+        I32Store16 => "i32.store16",
+        I64Store8 => "i64.store8",
+        I64Store16 => "i64.store16",
+        I64Store32 => "i64.store32",
+        V128Load => "v128.load",
+        V128Load8x8S => "v128.load8x8_s",
+        V128Load8x8U => "v128.load8x8_u",
+        V128Load16x4S => "v128.load16x4_s",
+        V128Load16x4U => "v128.load16x4_u",
+        V128Load32x2S => "v128.load32x2_s",
+        V128Load32x2U => "v128.load32x2_u",
+        V128Load8Splat => "v128.load8_splat",
+        V128Load16Splat => "v128.load16_splat",
+        V128Load32Splat => "v128.load32_splat",
+        V128Load64Splat => "v128.load64_splat",
+        V128Load32Zero => "v128.load32_zero",
+        V128Load64Zero => "v128.load64_zero",
+        V128Store => "v128.store",
+        V128Load8Lane => "v128.load8_lane",
+        V128Load16Lane => "v128.load16_lane",
+        V128Load32Lane => "v128.load32_lane",
+        V128Load64Lane => "v128.load64_lane",
+        V128Store8Lane => "v128.store8_lane",
+        V128Store16Lane => "v128.store16_lane",
+        V128Store32Lane => "v128.store32_lane",
+        V128Store64Lane => "v128.store64_lane",
+    }
+    atomic {
+        MemoryAtomicNotify => "memory.atomic.notify",
+        MemoryAtomicWait32 => "memory.atomic.wait32",
+        MemoryAtomicWait64 => "memory.atomic.wait64",
+        I32AtomicLoad => "i32.atomic.load",
+        I64AtomicLoad => "i64.atomic.load",
+        I32AtomicLoad8U => "i32.atomic.load8_u",
+        I32AtomicLoad16U => "i32.atomic.load16_u",
+        I64AtomicLoad8U => "i64.atomic.load8_u",
+        I64AtomicLoad16U => "i64.atomic.load16_u",
+        I64AtomicLoad32U => "i64.atomic.load32_u",
+        I32AtomicStore => "i32.atomic.store",
+        I64AtomicStore => "i64.atomic.store",
+        I32AtomicStore8 => "i32.atomic.store8",
+        I32AtomicStore16 => "i32.atomic.store16",
+        I64AtomicStore8 => "i64.atomic.store8",
+        I64AtomicStore16 => "i64.atomic.store16",
+        I64AtomicStore32 => "i64.atomic.store32",
+        I32AtomicRmwAdd => "i32.atomic.rmw.add",
+        I64AtomicRmwAdd => "i64.atomic.rmw.add",
+        I32AtomicRmw8AddU => "i32.atomic.rmw8.add_u",
+        I32AtomicRmw16AddU => "i32.atomic.rmw16.add_u",
+        I64AtomicRmw8AddU => "i64.atomic.rmw8.add_u",
+        I64AtomicRmw16AddU => "i64.atomic.rmw16.add_u",
+        I64AtomicRmw32AddU => "i64.atomic.rmw32.add_u",
+        I32AtomicRmwSub => "i32.atomic.rmw.sub",
+        I64AtomicRmwSub => "i64.atomic.rmw.sub",
+        I32AtomicRmw8SubU => "i32.atomic.rmw8.sub_u",
+        I32AtomicRmw16SubU => "i32.atomic.rmw16.sub_u",
+        I64AtomicRmw8SubU => "i64.atomic.rmw8.sub_u",
+        I64AtomicRmw16SubU => "i64.atomic.rmw16.sub_u",
+        I64AtomicRmw32SubU => "i64.atomic.rmw32.sub_u",
+        I32AtomicRmwAnd => "i32.atomic.rmw.and",
+        I64AtomicRmwAnd => "i64.atomic.rmw.and",
+        I32AtomicRmw8AndU => "i32.atomic.rmw8.and_u",
+        I32AtomicRmw16AndU => "i32.atomic.rmw16.and_u",
+        I64AtomicRmw8AndU => "i64.atomic.rmw8.and_u",
+        I64AtomicRmw16AndU => "i64.atomic.rmw16.and_u",
+        I64AtomicRmw32AndU => "i64.atomic.rmw32.and_u",
+        I32AtomicRmwOr => "i32.atomic.rmw.or",
+        I64AtomicRmwOr => "i64.atomic.rmw.or",
+        I32AtomicRmw8OrU => "i32.atomic.rmw8.or_u",
+        I32AtomicRmw16OrU => "i32.atomic.rmw16.or_u",
+        I64AtomicRmw8OrU => "i64.atomic.rmw8.or_u",
+        I64AtomicRmw16OrU => "i64.atomic.rmw16.or_u",
+        I64AtomicRmw32OrU => "i64.atomic.rmw32.or_u",
+        I32AtomicRmwXor => "i32.atomic.rmw.xor",
+        I64AtomicRmwXor => "i64.atomic.rmw.xor",
+        I32AtomicRmw8XorU => "i32.atomic.rmw8.xor_u",
+        I32AtomicRmw16XorU => "i32.atomic.rmw16.xor_u",
+        I64AtomicRmw8XorU => "i64.atomic.rmw8.xor_u",
+        I64AtomicRmw16XorU => "i64.atomic.rmw16.xor_u",
+        I64AtomicRmw32XorU => "i64.atomic.rmw32.xor_u",
+        I32AtomicRmwXchg => "i32.atomic.rmw.xchg",
+        I64AtomicRmwXchg => "i64.atomic.rmw.xchg",
+        I32AtomicRmw8XchgU => "i32.atomic.rmw8.xchg_u",
+        I32AtomicRmw16XchgU => "i32.atomic.rmw16.xchg_u",
+        I64AtomicRmw8XchgU => "i64.atomic.rmw8.xchg_u",
+        I64AtomicRmw16XchgU => "i64.atomic.rmw16.xchg_u",
+        I64AtomicRmw32XchgU => "i64.atomic.rmw32.xchg_u",
+        I32AtomicRmwCmpxchg => "i32.atomic.rmw.cmpxchg",
+        I64AtomicRmwCmpxchg => "i64.atomic.rmw.cmpxchg",
+        I32AtomicRmw8CmpxchgU => "i32.atomic.rmw8.cmpxchg_u",
+        I32AtomicRmw16CmpxchgU => "i32.atomic.rmw16.cmpxchg_u",
+        I64AtomicRmw8CmpxchgU => "i64.atomic.rmw8.cmpxchg_u",
+        I64AtomicRmw16CmpxchgU => "i64.atomic.rmw16.cmpxchg_u",
+        I64AtomicRmw32CmpxchgU => "i64.atomic.rmw32.cmpxchg_u",
+    }
+}
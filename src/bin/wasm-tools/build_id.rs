@@ -0,0 +1,47 @@
+use anyhow::{bail, Result};
+use std::io::Write;
+use wasmparser::{BuildIdSection, Encoding, Parser, Payload};
+
+/// Print the build id of a WebAssembly module.
+///
+/// Linkers such as `wasm-ld` can emit a `build_id` custom section containing
+/// an identifier, such as a hash, of the build which produced a module. This
+/// prints that id as a hexadecimal string, which is useful to correlate a
+/// deployed binary with its debug artifacts.
+///
+/// The process exits with an error if the module has no `build_id` section.
+#[derive(clap::Parser)]
+pub struct Opts {
+    #[clap(flatten)]
+    io: wasm_tools::InputOutput,
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let wasm = self.io.parse_input_wasm()?;
+        let mut build_id = None;
+        for payload in Parser::new(0).parse_all(&wasm) {
+            match payload? {
+                Payload::CustomSection(c) if c.name() == "build_id" => {
+                    if build_id.is_some() {
+                        bail!("input has multiple build_id custom sections");
+                    }
+                    build_id = Some(BuildIdSection::new(c.data(), c.data_offset())?.to_hex());
+                }
+                Payload::Version {
+                    encoding: Encoding::Component,
+                    ..
+                } => bail!("components are not supported"),
+                _ => {}
+            }
+        }
+
+        let build_id = match build_id {
+            Some(id) => id,
+            None => bail!("no build_id custom section found"),
+        };
+        let mut output = self.io.output_writer()?;
+        writeln!(output, "{}", build_id)?;
+        Ok(())
+    }
+}
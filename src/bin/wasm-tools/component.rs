@@ -0,0 +1,879 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::io::Write;
+use std::ops::Range;
+use wasmparser::types::{
+    ComponentDefinedType, ComponentEntityType, ComponentValType, EntityType, Type, TypeId, Types,
+};
+use wasmparser::{
+    CanonicalFunction, ComponentAlias, ComponentExternalKind, ComponentInstance,
+    ComponentOuterAliasKind, ComponentTypeRef, Encoding, ExternalKind, FuncType, Instance, Parser,
+    Payload, PrimitiveValType, ValType, Validator, WasmFeatures,
+};
+
+/// WebAssembly component-related subcommands.
+#[derive(clap::Parser)]
+pub struct Opts {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    ExtractCore(ExtractCoreOpts),
+    Types(TypesOpts),
+    Graph(GraphOpts),
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        match &self.command {
+            Command::ExtractCore(opts) => opts.run(),
+            Command::Types(opts) => opts.run(),
+            Command::Graph(opts) => opts.run(),
+        }
+    }
+}
+
+/// Extract a core wasm module embedded within a component.
+///
+/// Core modules are numbered in the order they appear in the component's
+/// binary, including those within nested components. The selected module is
+/// written out as a standalone core wasm file.
+#[derive(clap::Parser)]
+struct ExtractCoreOpts {
+    #[clap(flatten)]
+    io: wasm_tools::InputOutput,
+
+    /// The index of the core module to extract.
+    #[clap(long, default_value_t = 0, conflicts_with = "list")]
+    index: usize,
+
+    /// List the core modules within the component instead of extracting one.
+    #[clap(long)]
+    list: bool,
+
+    /// Output the text format of WebAssembly instead of the binary format.
+    #[clap(short = 't', long)]
+    wat: bool,
+}
+
+/// A core module found within a component.
+struct CoreModule {
+    /// The byte range of the module within the component.
+    range: Range<usize>,
+    /// How many components the module is nested within.
+    depth: usize,
+}
+
+impl ExtractCoreOpts {
+    fn run(&self) -> Result<()> {
+        let input = self.io.parse_input_wasm()?;
+        let modules = core_modules(&input)?;
+
+        if self.list {
+            let mut output = self.io.output_writer()?;
+            for (i, module) in modules.iter().enumerate() {
+                writeln!(
+                    output,
+                    "{}: {} bytes at offset {:#x} (depth {})",
+                    i,
+                    module.range.len(),
+                    module.range.start,
+                    module.depth,
+                )?;
+            }
+            return Ok(());
+        }
+
+        let module = match modules.get(self.index) {
+            Some(module) => module,
+            None => bail!(
+                "core module index {} is out of bounds, the component contains {} core modules",
+                self.index,
+                modules.len()
+            ),
+        };
+        let bytes = &input[module.range.clone()];
+        validate_core_module(bytes).with_context(|| {
+            format!(
+                "core module {} at offset {:#x} failed to parse",
+                self.index, module.range.start
+            )
+        })?;
+        self.io.output(wasm_tools::Output::Wasm {
+            bytes,
+            wat: self.wat,
+        })?;
+        Ok(())
+    }
+}
+
+/// Print the resolved types of a component's imports and exports.
+///
+/// Each top-level import and export is printed with its fully-resolved type
+/// in the style of the text format, with type indices replaced by the types
+/// they refer to. Unlike WIT this shows the raw component model types,
+/// including core module types, instance types, and type bounds.
+///
+/// The component is validated first since types are only resolved for valid
+/// components.
+#[derive(clap::Parser)]
+struct TypesOpts {
+    #[clap(flatten)]
+    io: wasm_tools::InputOutput,
+}
+
+impl TypesOpts {
+    fn run(&self) -> Result<()> {
+        let input = self.io.parse_input_wasm()?;
+        let types = Validator::new_with_features(WasmFeatures {
+            component_model: true,
+            ..WasmFeatures::default()
+        })
+        .validate_all(&input)
+        .context("component failed to validate")?;
+
+        let mut printer = TypePrinter {
+            types: &types,
+            output: String::new(),
+        };
+        let mut depth = 0;
+        for payload in Parser::new(0).parse_all(&input) {
+            match payload? {
+                Payload::Version { encoding, .. } => {
+                    if depth == 0 && encoding != Encoding::Component {
+                        bail!("input is a core module, not a component");
+                    }
+                    depth += 1;
+                }
+                Payload::End(_) => depth -= 1,
+                Payload::ComponentImportSection(s) if depth == 1 => {
+                    for import in s {
+                        let import = import?;
+                        let ty = types.component_entity_type_from_import(&import).unwrap();
+                        printer.entity("import", import.name, &ty, 0);
+                        printer.output.push('\n');
+                    }
+                }
+                Payload::ComponentExportSection(s) if depth == 1 => {
+                    for export in s {
+                        let export = export?;
+                        let ty = types.component_entity_type_from_export(&export).unwrap();
+                        printer.entity("export", export.name, &ty, 0);
+                        printer.output.push('\n');
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.io
+            .output_writer()?
+            .write_all(printer.output.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Renders resolved component types in the style of the text format.
+struct TypePrinter<'a> {
+    types: &'a Types,
+    output: String,
+}
+
+impl TypePrinter<'_> {
+    fn ty(&self, id: TypeId) -> &Type {
+        self.types
+            .type_from_id(id)
+            .expect("types of a valid component are defined")
+    }
+
+    /// Prints an import or export of a component indented by `indent`.
+    fn entity(&mut self, kind: &str, name: &str, ty: &ComponentEntityType, indent: usize) {
+        self.indent(indent);
+        self.output.push_str(&format!("({} {:?} ", kind, name));
+        self.entity_type(ty, indent);
+        self.output.push(')');
+    }
+
+    fn entity_type(&mut self, ty: &ComponentEntityType, indent: usize) {
+        match ty {
+            ComponentEntityType::Module(id)
+            | ComponentEntityType::Func(id)
+            | ComponentEntityType::Instance(id)
+            | ComponentEntityType::Component(id) => self.type_def(*id, indent),
+            ComponentEntityType::Value(ty) => {
+                let ty = self.val_type(ty);
+                self.output.push_str(&format!("(value {})", ty));
+            }
+            ComponentEntityType::Type(id) => {
+                self.output.push_str("(type (eq ");
+                self.type_def(*id, indent);
+                self.output.push_str("))");
+            }
+        }
+    }
+
+    /// Prints the type `id`, where any lines after the first are indented by
+    /// `indent`.
+    fn type_def(&mut self, id: TypeId, indent: usize) {
+        let types = self.types;
+        match types
+            .type_from_id(id)
+            .expect("types of a valid component are defined")
+        {
+            Type::Func(ty) => self.output.push_str(&core_func_type(ty)),
+            Type::Module(ty) => {
+                self.output.push_str("(core module");
+                for ((module, name), ty) in ty.imports.iter() {
+                    let ty = self.core_entity_type(ty);
+                    self.line(
+                        indent + 1,
+                        &format!("(import {:?} {:?} {})", module, name, ty),
+                    );
+                }
+                for (name, ty) in ty.exports.iter() {
+                    let ty = self.core_entity_type(ty);
+                    self.line(indent + 1, &format!("(export {:?} {})", name, ty));
+                }
+                self.close(indent, !ty.imports.is_empty() || !ty.exports.is_empty());
+            }
+            Type::Instance(ty) => {
+                self.output.push_str("(core instance");
+                let exports = ty.exports(types.as_ref());
+                for (name, ty) in exports.iter() {
+                    let ty = self.core_entity_type(ty);
+                    self.line(indent + 1, &format!("(export {:?} {})", name, ty));
+                }
+                self.close(indent, !exports.is_empty());
+            }
+            Type::Component(ty) => {
+                self.output.push_str("(component");
+                for (name, ty) in ty.imports.iter() {
+                    self.output.push('\n');
+                    self.entity("import", name, ty, indent + 1);
+                }
+                for (name, ty) in ty.exports.iter() {
+                    self.output.push('\n');
+                    self.entity("export", name, ty, indent + 1);
+                }
+                self.close(indent, !ty.imports.is_empty() || !ty.exports.is_empty());
+            }
+            Type::ComponentInstance(ty) => {
+                self.output.push_str("(instance");
+                let exports = ty.exports(types.as_ref());
+                for (name, ty) in exports.iter() {
+                    self.output.push('\n');
+                    self.entity("export", name, ty, indent + 1);
+                }
+                self.close(indent, !exports.is_empty());
+            }
+            Type::ComponentFunc(ty) => {
+                self.output.push_str("(func");
+                for (name, ty) in ty.params.iter() {
+                    let ty = self.val_type(ty);
+                    self.output.push_str(&format!(" (param {:?} {})", name, ty));
+                }
+                for (name, ty) in ty.results.iter() {
+                    let ty = self.val_type(ty);
+                    match name {
+                        Some(name) => self
+                            .output
+                            .push_str(&format!(" (result {:?} {})", name, ty)),
+                        None => self.output.push_str(&format!(" (result {})", ty)),
+                    }
+                }
+                self.output.push(')');
+            }
+            Type::Defined(ty) => {
+                let ty = self.defined_type(ty);
+                self.output.push_str(&ty);
+            }
+        }
+    }
+
+    fn val_type(&self, ty: &ComponentValType) -> String {
+        match ty {
+            ComponentValType::Primitive(ty) => primitive_val_type(*ty).to_string(),
+            ComponentValType::Type(id) => match self.ty(*id) {
+                Type::Defined(ty) => self.defined_type(ty),
+                _ => unreachable!("value types are always defined types"),
+            },
+        }
+    }
+
+    fn defined_type(&self, ty: &ComponentDefinedType) -> String {
+        let list = |types: &mut dyn Iterator<Item = String>| {
+            types.map(|ty| format!(" {}", ty)).collect::<String>()
+        };
+        match ty {
+            ComponentDefinedType::Primitive(ty) => primitive_val_type(*ty).to_string(),
+            ComponentDefinedType::Record(r) => format!(
+                "(record{})",
+                list(
+                    &mut r
+                        .fields
+                        .iter()
+                        .map(|(name, ty)| { format!("(field {:?} {})", name, self.val_type(ty)) })
+                )
+            ),
+            ComponentDefinedType::Variant(v) => format!(
+                "(variant{})",
+                list(&mut v.cases.iter().map(|(name, case)| {
+                    let mut ret = format!("(case {:?}", name);
+                    if let Some(ty) = &case.ty {
+                        ret.push_str(&format!(" {}", self.val_type(ty)));
+                    }
+                    if let Some(refines) = &case.refines {
+                        ret.push_str(&format!(" (refines {:?})", refines));
+                    }
+                    ret.push(')');
+                    ret
+                }))
+            ),
+            ComponentDefinedType::List(ty) => format!("(list {})", self.val_type(ty)),
+            ComponentDefinedType::Tuple(t) => format!(
+                "(tuple{})",
+                list(&mut t.types.iter().map(|ty| self.val_type(ty)))
+            ),
+            ComponentDefinedType::Flags(names) => format!(
+                "(flags{})",
+                list(&mut names.iter().map(|name| format!("{:?}", name)))
+            ),
+            ComponentDefinedType::Enum(names) => format!(
+                "(enum{})",
+                list(&mut names.iter().map(|name| format!("{:?}", name)))
+            ),
+            ComponentDefinedType::Union(u) => format!(
+                "(union{})",
+                list(&mut u.types.iter().map(|ty| self.val_type(ty)))
+            ),
+            ComponentDefinedType::Option(ty) => format!("(option {})", self.val_type(ty)),
+            ComponentDefinedType::Result { ok, err } => {
+                let mut ret = "(result".to_string();
+                if let Some(ok) = ok {
+                    ret.push_str(&format!(" {}", self.val_type(ok)));
+                }
+                if let Some(err) = err {
+                    ret.push_str(&format!(" (error {})", self.val_type(err)));
+                }
+                ret.push(')');
+                ret
+            }
+        }
+    }
+
+    fn core_entity_type(&self, ty: &EntityType) -> String {
+        match ty {
+            EntityType::Func(id) => match self.ty(*id) {
+                Type::Func(ty) => core_func_type(ty),
+                _ => unreachable!("functions always have function types"),
+            },
+            EntityType::Table(ty) => format!(
+                "(table {}{} {})",
+                ty.initial,
+                ty.maximum.map(|m| format!(" {}", m)).unwrap_or_default(),
+                core_val_type(ty.element_type),
+            ),
+            EntityType::Memory(ty) => format!(
+                "(memory {}{}{}{})",
+                if ty.memory64 { "i64 " } else { "" },
+                ty.initial,
+                ty.maximum.map(|m| format!(" {}", m)).unwrap_or_default(),
+                if ty.shared { " shared" } else { "" },
+            ),
+            EntityType::Global(ty) => {
+                let content = core_val_type(ty.content_type);
+                if ty.mutable {
+                    format!("(global (mut {}))", content)
+                } else {
+                    format!("(global {})", content)
+                }
+            }
+            EntityType::Tag(id) => match self.ty(*id) {
+                Type::Func(ty) => format!("(tag {})", core_func_type(ty)),
+                _ => unreachable!("tags always have function types"),
+            },
+        }
+    }
+
+    fn indent(&mut self, indent: usize) {
+        for _ in 0..indent {
+            self.output.push_str("  ");
+        }
+    }
+
+    /// Starts a new line, indented by `indent`, containing `text`.
+    fn line(&mut self, indent: usize, text: &str) {
+        self.output.push('\n');
+        self.indent(indent);
+        self.output.push_str(text);
+    }
+
+    /// Closes a type started on a previous line, putting the closing
+    /// parenthesis on its own line if any items were printed.
+    fn close(&mut self, indent: usize, multiline: bool) {
+        if multiline {
+            self.output.push('\n');
+            self.indent(indent);
+        }
+        self.output.push(')');
+    }
+}
+
+fn core_func_type(ty: &FuncType) -> String {
+    let mut ret = "(func".to_string();
+    if !ty.params().is_empty() {
+        ret.push_str(" (param");
+        for ty in ty.params() {
+            ret.push_str(&format!(" {}", core_val_type(*ty)));
+        }
+        ret.push(')');
+    }
+    if !ty.results().is_empty() {
+        ret.push_str(" (result");
+        for ty in ty.results() {
+            ret.push_str(&format!(" {}", core_val_type(*ty)));
+        }
+        ret.push(')');
+    }
+    ret.push(')');
+    ret
+}
+
+fn core_val_type(ty: ValType) -> &'static str {
+    match ty {
+        ValType::I32 => "i32",
+        ValType::I64 => "i64",
+        ValType::F32 => "f32",
+        ValType::F64 => "f64",
+        ValType::V128 => "v128",
+        ValType::FuncRef => "funcref",
+        ValType::ExternRef => "externref",
+    }
+}
+
+fn primitive_val_type(ty: PrimitiveValType) -> &'static str {
+    match ty {
+        PrimitiveValType::Bool => "bool",
+        PrimitiveValType::S8 => "s8",
+        PrimitiveValType::U8 => "u8",
+        PrimitiveValType::S16 => "s16",
+        PrimitiveValType::U16 => "u16",
+        PrimitiveValType::S32 => "s32",
+        PrimitiveValType::U32 => "u32",
+        PrimitiveValType::S64 => "s64",
+        PrimitiveValType::U64 => "u64",
+        PrimitiveValType::Float32 => "float32",
+        PrimitiveValType::Float64 => "float64",
+        PrimitiveValType::Char => "char",
+        PrimitiveValType::String => "string",
+    }
+}
+
+/// Render the instantiation graph of a component in the Graphviz DOT format.
+///
+/// Each core module, nested component, instance, import, and export of the
+/// top-level component is a node of the graph. A dashed edge leads from each
+/// module or component to the instances created from it, and a solid edge
+/// leads from wherever each argument of an instantiation comes from to the
+/// instance created with it, labeled with the name of the argument. Items
+/// aliased from the exports of an instance are traced back to that instance,
+/// including through `canon lift` and `canon lower`.
+///
+/// Nested components are shown as a single node without their contents, and
+/// the component is validated first.
+///
+/// ## Example
+///
+/// $ wasm-tools component graph foo.wasm -o graph.dot
+///
+/// $ dot -Tsvg graph.dot -o graph.svg
+#[derive(clap::Parser)]
+struct GraphOpts {
+    #[clap(flatten)]
+    io: wasm_tools::InputOutput,
+}
+
+impl GraphOpts {
+    fn run(&self) -> Result<()> {
+        let input = self.io.parse_input_wasm()?;
+        Validator::new_with_features(WasmFeatures {
+            component_model: true,
+            ..WasmFeatures::default()
+        })
+        .validate_all(&input)
+        .context("component failed to validate")?;
+
+        let mut graph = Graph::default();
+        let mut depth = 0;
+        for payload in Parser::new(0).parse_all(&input) {
+            let payload = payload?;
+            match &payload {
+                Payload::Version { encoding, .. } => {
+                    if depth == 0 && *encoding != Encoding::Component {
+                        bail!("input is a core module, not a component");
+                    }
+                    depth += 1;
+                    continue;
+                }
+                Payload::End(_) => {
+                    depth -= 1;
+                    continue;
+                }
+                _ if depth > 1 => continue,
+                _ => {}
+            }
+            graph.payload(payload)?;
+        }
+
+        let mut output = self.io.output_writer()?;
+        graph.write(&mut output)?;
+        Ok(())
+    }
+}
+
+/// The index spaces of a component.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Space {
+    CoreFunc,
+    CoreTable,
+    CoreMemory,
+    CoreGlobal,
+    CoreTag,
+    CoreModule,
+    CoreInstance,
+    Func,
+    Value,
+    Type,
+    Instance,
+    Component,
+}
+
+impl Space {
+    fn core(kind: ExternalKind) -> Space {
+        match kind {
+            ExternalKind::Func => Space::CoreFunc,
+            ExternalKind::Table => Space::CoreTable,
+            ExternalKind::Memory => Space::CoreMemory,
+            ExternalKind::Global => Space::CoreGlobal,
+            ExternalKind::Tag => Space::CoreTag,
+        }
+    }
+
+    fn component(kind: ComponentExternalKind) -> Space {
+        match kind {
+            ComponentExternalKind::Module => Space::CoreModule,
+            ComponentExternalKind::Func => Space::Func,
+            ComponentExternalKind::Value => Space::Value,
+            ComponentExternalKind::Type => Space::Type,
+            ComponentExternalKind::Instance => Space::Instance,
+            ComponentExternalKind::Component => Space::Component,
+        }
+    }
+}
+
+/// The node of the graph which an item comes from.
+#[derive(Clone)]
+struct Origin {
+    node: usize,
+    /// The name of the export of `node` that the item is, if it isn't the
+    /// node itself.
+    export: Option<String>,
+}
+
+#[derive(Default)]
+struct Graph {
+    /// The label and shape of each node.
+    nodes: Vec<(String, &'static str)>,
+    /// Each edge as its source, destination, label, and whether it's dashed.
+    edges: Vec<(usize, usize, Option<String>, bool)>,
+    /// Where each item of each index space comes from, if it comes from
+    /// a node of the graph.
+    spaces: HashMap<Space, Vec<Option<Origin>>>,
+}
+
+impl Graph {
+    fn payload(&mut self, payload: Payload<'_>) -> Result<()> {
+        match payload {
+            Payload::ModuleSection { .. } => {
+                let label = format!("module {}", self.len(Space::CoreModule));
+                self.push_node(Space::CoreModule, label, "box");
+            }
+            Payload::ComponentSection { .. } => {
+                let label = format!("component {}", self.len(Space::Component));
+                self.push_node(Space::Component, label, "box");
+            }
+            Payload::ComponentImportSection(s) => {
+                for import in s {
+                    let import = import?;
+                    let space = match import.ty {
+                        ComponentTypeRef::Module(_) => Space::CoreModule,
+                        ComponentTypeRef::Func(_) => Space::Func,
+                        ComponentTypeRef::Value(_) => Space::Value,
+                        ComponentTypeRef::Type(..) => Space::Type,
+                        ComponentTypeRef::Instance(_) => Space::Instance,
+                        ComponentTypeRef::Component(_) => Space::Component,
+                    };
+                    self.push_node(space, format!("import {:?}", import.name), "plaintext");
+                }
+            }
+            Payload::ComponentAliasSection(s) => {
+                for alias in s {
+                    match alias? {
+                        ComponentAlias::InstanceExport {
+                            kind,
+                            instance_index,
+                            name,
+                        } => {
+                            let origin = self.export(Space::Instance, instance_index, name);
+                            self.push(Space::component(kind), origin);
+                        }
+                        ComponentAlias::CoreInstanceExport {
+                            kind,
+                            instance_index,
+                            name,
+                        } => {
+                            let origin = self.export(Space::CoreInstance, instance_index, name);
+                            self.push(Space::core(kind), origin);
+                        }
+                        ComponentAlias::Outer { kind, count, index } => {
+                            let space = match kind {
+                                ComponentOuterAliasKind::CoreModule => Space::CoreModule,
+                                ComponentOuterAliasKind::Type => Space::Type,
+                                ComponentOuterAliasKind::Component => Space::Component,
+                                // Core types aren't tracked.
+                                ComponentOuterAliasKind::CoreType => continue,
+                            };
+                            // Only aliases of the component itself refer to
+                            // items of the graph.
+                            let origin = match count {
+                                0 => self.origin(space, index),
+                                _ => None,
+                            };
+                            self.push(space, origin);
+                        }
+                    }
+                }
+            }
+            Payload::ComponentTypeSection(s) => {
+                for _ in 0..s.get_count() {
+                    self.push(Space::Type, None);
+                }
+            }
+            Payload::ComponentCanonicalSection(s) => {
+                for func in s {
+                    match func? {
+                        CanonicalFunction::Lift {
+                            core_func_index, ..
+                        } => {
+                            let origin = self.origin(Space::CoreFunc, core_func_index);
+                            self.push(Space::Func, origin);
+                        }
+                        CanonicalFunction::Lower { func_index, .. } => {
+                            let origin = self.origin(Space::Func, func_index);
+                            self.push(Space::CoreFunc, origin);
+                        }
+                    }
+                }
+            }
+            Payload::InstanceSection(s) => {
+                for instance in s {
+                    let label = format!("core instance {}", self.len(Space::CoreInstance));
+                    let node = self.push_node(Space::CoreInstance, label, "ellipse");
+                    match instance? {
+                        Instance::Instantiate { module_index, args } => {
+                            self.instantiate(Space::CoreModule, module_index, node);
+                            for arg in args.iter() {
+                                self.edge(Space::CoreInstance, arg.index, node, arg.name);
+                            }
+                        }
+                        Instance::FromExports(exports) => {
+                            for export in exports.iter() {
+                                self.edge(
+                                    Space::core(export.kind),
+                                    export.index,
+                                    node,
+                                    export.name,
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+            Payload::ComponentInstanceSection(s) => {
+                for instance in s {
+                    let label = format!("instance {}", self.len(Space::Instance));
+                    let node = self.push_node(Space::Instance, label, "ellipse");
+                    match instance? {
+                        ComponentInstance::Instantiate {
+                            component_index,
+                            args,
+                        } => {
+                            self.instantiate(Space::Component, component_index, node);
+                            for arg in args.iter() {
+                                self.edge(Space::component(arg.kind), arg.index, node, arg.name);
+                            }
+                        }
+                        ComponentInstance::FromExports(exports) => {
+                            for export in exports.iter() {
+                                let space = Space::component(export.kind);
+                                self.edge(space, export.index, node, export.name);
+                            }
+                        }
+                    }
+                }
+            }
+            Payload::ComponentStartSection(mut s) => {
+                for _ in 0..s.read()?.results {
+                    self.push(Space::Value, None);
+                }
+            }
+            Payload::ComponentExportSection(s) => {
+                for export in s {
+                    let export = export?;
+                    let node = self.nodes.len();
+                    self.nodes
+                        .push((format!("export {:?}", export.name), "plaintext"));
+                    self.edge(
+                        Space::component(export.kind),
+                        export.index,
+                        node,
+                        export.name,
+                    );
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn len(&self, space: Space) -> usize {
+        self.spaces.get(&space).map_or(0, |s| s.len())
+    }
+
+    fn push(&mut self, space: Space, origin: Option<Origin>) {
+        self.spaces.entry(space).or_default().push(origin);
+    }
+
+    /// Adds a node for a new item of `space`, returning the node.
+    fn push_node(&mut self, space: Space, label: String, shape: &'static str) -> usize {
+        let node = self.nodes.len();
+        self.nodes.push((label, shape));
+        self.push(space, Some(Origin { node, export: None }));
+        node
+    }
+
+    fn origin(&self, space: Space, index: u32) -> Option<Origin> {
+        self.spaces
+            .get(&space)?
+            .get(index as usize)
+            .cloned()
+            .flatten()
+    }
+
+    /// Returns where the export `name` of the instance at `index` of `space`
+    /// comes from.
+    fn export(&self, space: Space, index: u32, name: &str) -> Option<Origin> {
+        let origin = self.origin(space, index)?;
+        Some(Origin {
+            node: origin.node,
+            export: Some(match origin.export {
+                Some(export) => format!("{}.{}", export, name),
+                None => name.to_string(),
+            }),
+        })
+    }
+
+    /// Adds a dashed edge to `node` from the module or component at `index`
+    /// of `space` which it's an instance of.
+    fn instantiate(&mut self, space: Space, index: u32, node: usize) {
+        if let Some(origin) = self.origin(space, index) {
+            self.edges.push((origin.node, node, origin.export, true));
+        }
+    }
+
+    /// Adds an edge to `node` from wherever the item at `index` of `space`
+    /// comes from, where `name` is the name the item is given for `node`.
+    fn edge(&mut self, space: Space, index: u32, node: usize, name: &str) {
+        if let Some(origin) = self.origin(space, index) {
+            let label = match origin.export {
+                Some(export) if export != name => format!("{} as {}", export, name),
+                _ => name.to_string(),
+            };
+            self.edges.push((origin.node, node, Some(label), false));
+        }
+    }
+
+    fn write(&self, output: &mut dyn Write) -> Result<()> {
+        writeln!(output, "digraph component {{")?;
+        for (i, (label, shape)) in self.nodes.iter().enumerate() {
+            writeln!(
+                output,
+                "  n{} [label=\"{}\", shape={}];",
+                i,
+                escape(label),
+                shape
+            )?;
+        }
+        for (from, to, label, dashed) in self.edges.iter() {
+            let mut attrs = Vec::new();
+            if let Some(label) = label {
+                attrs.push(format!("label=\"{}\"", escape(label)));
+            }
+            if *dashed {
+                attrs.push("style=dashed".to_string());
+            }
+            write!(output, "  n{} -> n{}", from, to)?;
+            if !attrs.is_empty() {
+                write!(output, " [{}]", attrs.join(", "))?;
+            }
+            writeln!(output, ";")?;
+        }
+        writeln!(output, "}}")?;
+        Ok(())
+    }
+}
+
+/// Escapes `s` to be used within a quoted DOT string.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Returns all the core modules embedded within the `component` provided.
+fn core_modules(component: &[u8]) -> Result<Vec<CoreModule>> {
+    let mut modules = Vec::new();
+    let mut depth = 0;
+    for payload in Parser::new(0).parse_all(component) {
+        match payload? {
+            Payload::Version { encoding, .. } => {
+                if depth == 0 && encoding != Encoding::Component {
+                    bail!("input is a core module, not a component");
+                }
+                depth += 1;
+            }
+            Payload::ModuleSection { range, .. } => {
+                modules.push(CoreModule {
+                    range,
+                    depth: depth - 1,
+                });
+            }
+            Payload::End(_) => depth -= 1,
+            _ => {}
+        }
+    }
+    Ok(modules)
+}
+
+/// Ensures that `bytes` parse as a standalone core wasm module.
+fn validate_core_module(bytes: &[u8]) -> Result<()> {
+    for payload in Parser::new(0).parse_all(bytes) {
+        match payload? {
+            Payload::Version { encoding, .. } if encoding != Encoding::Module => {
+                bail!("not a core module")
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
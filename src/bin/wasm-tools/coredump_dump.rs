@@ -0,0 +1,196 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use wasmparser::{
+    CoreDumpInstancesSection, CoreDumpModulesSection, CoreDumpSection, CoreDumpStackSection,
+    CoreDumpValue, Name, NameSectionReader, Operator, Parser, Payload,
+};
+
+/// Render the contents of a WebAssembly coredump.
+///
+/// Coredumps, as defined by the tool-conventions repository, are wasm modules
+/// whose `core`, `coremodules`, `coreinstances`, and `corestack` custom
+/// sections describe the state of a crashed program. This prints the process
+/// name, the modules and instances involved, the values of globals, and each
+/// stack frame along with its locals and operand stack.
+///
+/// Coredumps don't contain the program's code, so pass the original modules
+/// with `--module` to resolve function names in stack frames.
+#[derive(clap::Parser)]
+pub struct Opts {
+    #[clap(flatten)]
+    io: wasm_tools::InputOutput,
+
+    /// The original module for each entry of the `coremodules` section, in
+    /// order, used to resolve function names.
+    #[clap(short, long = "module", value_name = "PATH")]
+    modules: Vec<PathBuf>,
+}
+
+#[derive(Default)]
+struct CoreDump<'a> {
+    process: Option<&'a str>,
+    modules: Vec<&'a str>,
+    instances: Option<CoreDumpInstancesSection>,
+    stacks: Vec<CoreDumpStackSection<'a>>,
+    globals: Vec<String>,
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let input = self.io.parse_input_wasm()?;
+        let dump = parse(&input)?;
+        let names = self
+            .modules
+            .iter()
+            .map(|path| {
+                let wasm = wat::parse_file(path)?;
+                function_names(&wasm)
+                    .with_context(|| format!("failed to read names from `{}`", path.display()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut output = self.io.output_writer()?;
+        match dump.process {
+            Some(name) => writeln!(output, "process `{}`", name)?,
+            None => bail!("input is not a coredump, it has no `core` custom section"),
+        }
+
+        if !dump.modules.is_empty() {
+            writeln!(output, "modules:")?;
+            for (i, module) in dump.modules.iter().enumerate() {
+                writeln!(output, "  {}: {}", i, module)?;
+            }
+        }
+
+        if let Some(instances) = &dump.instances {
+            writeln!(output, "instances:")?;
+            for (i, instance) in instances.instances.iter().enumerate() {
+                writeln!(
+                    output,
+                    "  {}: module {}, memories {:?}, globals {:?}",
+                    i, instance.module_index, instance.memories, instance.globals,
+                )?;
+            }
+        }
+
+        if !dump.globals.is_empty() {
+            writeln!(output, "globals:")?;
+            for (i, value) in dump.globals.iter().enumerate() {
+                writeln!(output, "  {}: {}", i, value)?;
+            }
+        }
+
+        for stack in dump.stacks.iter() {
+            writeln!(output, "thread `{}`:", stack.name)?;
+            for (i, frame) in stack.frames.iter().enumerate() {
+                let module = dump
+                    .instances
+                    .as_ref()
+                    .and_then(|s| s.instances.get(frame.instanceidx as usize))
+                    .map(|instance| instance.module_index as usize);
+                let name = module
+                    .and_then(|m| names.get(m))
+                    .and_then(|names| names.get(&frame.funcidx));
+                write!(output, "  #{:<3} func {}", i, frame.funcidx)?;
+                if let Some(name) = name {
+                    write!(output, " `{}`", name)?;
+                }
+                writeln!(
+                    output,
+                    " in instance {} at offset {:#x}",
+                    frame.instanceidx, frame.codeoffset
+                )?;
+                writeln!(output, "        locals: {}", values(&frame.locals))?;
+                writeln!(output, "        stack:  {}", values(&frame.stack))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse(wasm: &[u8]) -> Result<CoreDump<'_>> {
+    let mut dump = CoreDump::default();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::CustomSection(c) => match c.name() {
+                "core" => {
+                    let section = CoreDumpSection::new(c.data(), c.data_offset())?;
+                    dump.process = Some(section.name);
+                }
+                "coremodules" => {
+                    let section = CoreDumpModulesSection::new(c.data(), c.data_offset())?;
+                    dump.modules = section.modules;
+                }
+                "coreinstances" => {
+                    let section = CoreDumpInstancesSection::new(c.data(), c.data_offset())?;
+                    dump.instances = Some(section);
+                }
+                "corestack" => {
+                    let section = CoreDumpStackSection::new(c.data(), c.data_offset())?;
+                    dump.stacks.push(section);
+                }
+                _ => {}
+            },
+            Payload::GlobalSection(reader) => {
+                for global in reader {
+                    let global = global?;
+                    let mut reader = global.init_expr.get_operators_reader();
+                    let value = match reader.read()? {
+                        Operator::I32Const { value } => format!("i32 {}", value),
+                        Operator::I64Const { value } => format!("i64 {}", value),
+                        Operator::F32Const { value } => {
+                            format!("f32 {}", f32::from_bits(value.bits()))
+                        }
+                        Operator::F64Const { value } => {
+                            format!("f64 {}", f64::from_bits(value.bits()))
+                        }
+                        _ => format!("{:?} <unknown>", global.ty.content_type),
+                    };
+                    dump.globals.push(value);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(dump)
+}
+
+fn values(values: &[CoreDumpValue]) -> String {
+    if values.is_empty() {
+        return "(none)".to_string();
+    }
+    values
+        .iter()
+        .map(|value| match value {
+            CoreDumpValue::Missing => "<missing>".to_string(),
+            CoreDumpValue::I32(v) => format!("i32 {}", v),
+            CoreDumpValue::I64(v) => format!("i64 {}", v),
+            CoreDumpValue::F32(v) => format!("f32 {}", f32::from_bits(v.bits())),
+            CoreDumpValue::F64(v) => format!("f64 {}", f64::from_bits(v.bits())),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns the names of functions in the `name` section of `wasm`.
+fn function_names(wasm: &[u8]) -> Result<HashMap<u32, String>> {
+    let mut names = HashMap::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        let c = match payload? {
+            Payload::CustomSection(c) if c.name() == "name" => c,
+            _ => continue,
+        };
+        for name in NameSectionReader::new(c.data(), c.data_offset())? {
+            if let Name::Function(map) = name? {
+                let mut map = map.get_map()?;
+                for _ in 0..map.get_count() {
+                    let naming = map.read()?;
+                    names.insert(naming.index, naming.name.to_string());
+                }
+            }
+        }
+    }
+    Ok(names)
+}
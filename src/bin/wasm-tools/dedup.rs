@@ -0,0 +1,198 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use wasm_encoder::{CodeSection, FunctionSection, Module};
+use wasm_tools::translate::{self, Item, Translator};
+use wasmparser::{FunctionBody, ImportCounts, Parser, Payload, Validator};
+
+/// Merge functions of a module which have identical types and bodies.
+///
+/// Functions are duplicates of each other if they have the same type index
+/// and their bodies, including their locals, are byte-for-byte identical.
+/// The first of each set of duplicates is kept and all references to the
+/// others, from calls, `ref.func`, element segments, exports, and the start
+/// function, are redirected to it. The remaining functions are renumbered.
+///
+/// Merging functions can make callers of them identical in turn, so this is
+/// repeated until no more duplicates are found. Imported functions are never
+/// merged.
+///
+/// ## Example
+///
+/// $ wasm-tools dedup foo.wasm -o out.wasm
+#[derive(clap::Parser)]
+pub struct Opts {
+    #[clap(flatten)]
+    io: wasm_tools::InputOutput,
+
+    #[clap(flatten)]
+    debug: wasm_tools::DebugInfo,
+
+    /// Output the text format of WebAssembly instead of the binary format.
+    #[clap(short = 't', long)]
+    wat: bool,
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let input = self.io.parse_input_wasm()?;
+        Validator::new_with_features(wasm_tools::all_core_features())
+            .validate_all(&input)
+            .context("input failed to validate")?;
+
+        let mut output = input.clone();
+        let mut removed = 0;
+        loop {
+            let mut dedup = Dedup::new(&output)?;
+            if dedup.removed == 0 {
+                break;
+            }
+            removed += dedup.removed;
+            output = translate::module(&mut dedup, &output)?;
+        }
+
+        Validator::new_with_features(wasm_tools::all_core_features())
+            .validate_all(&output)
+            .context("output failed to validate")?;
+        let output = self.debug.check(&input, output)?;
+        eprintln!(
+            "removed {} duplicate functions ({} bytes saved)",
+            removed,
+            input.len() as i64 - output.len() as i64,
+        );
+
+        self.io.output(wasm_tools::Output::Wasm {
+            bytes: &output,
+            wat: self.wat,
+        })?;
+        Ok(())
+    }
+}
+
+struct Dedup {
+    /// The new index of each function, where duplicates have the index of
+    /// the function they're merged into.
+    old_to_new: HashMap<u32, u32>,
+    /// Whether each function defined in the module is kept.
+    kept: Vec<bool>,
+    removed: u32,
+    imported_funcs: u32,
+    functions: u32,
+    bodies: u32,
+}
+
+impl Dedup {
+    /// Finds the duplicate functions of the core module `wasm`.
+    fn new(wasm: &[u8]) -> Result<Dedup> {
+        let mut imported_funcs = 0;
+        let mut types = Vec::new();
+        let mut canonical = HashMap::new();
+        let mut old_to_new = HashMap::new();
+        let mut kept = Vec::new();
+        let mut removed = 0;
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::Version { encoding, .. } if encoding != wasmparser::Encoding::Module => {
+                    bail!("functions can only be deduplicated in core wasm modules")
+                }
+                Payload::ImportSection(reader) => {
+                    // Imported functions are never merged and keep their
+                    // indices.
+                    imported_funcs = ImportCounts::from_reader(reader)?.funcs;
+                    old_to_new.extend((0..imported_funcs).map(|i| (i, i)));
+                }
+                Payload::FunctionSection(reader) => {
+                    for ty in reader {
+                        types.push(ty?);
+                    }
+                }
+                Payload::CodeSectionEntry(body) => {
+                    let i = kept.len();
+                    let func = imported_funcs + i as u32;
+                    let key = (types[i], &wasm[body.range()]);
+                    match canonical.get(&key) {
+                        Some(first) => {
+                            old_to_new.insert(func, old_to_new[first]);
+                            kept.push(false);
+                            removed += 1;
+                        }
+                        None => {
+                            canonical.insert(key, func);
+                            old_to_new.insert(func, func - removed);
+                            kept.push(true);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(Dedup {
+            old_to_new,
+            kept,
+            removed,
+            imported_funcs,
+            functions: 0,
+            bodies: 0,
+        })
+    }
+
+    /// Returns whether the function at `old` is kept, or is merged into
+    /// another.
+    fn is_kept(&self, old: u32) -> bool {
+        old < self.imported_funcs || self.kept[(old - self.imported_funcs) as usize]
+    }
+}
+
+impl Translator for Dedup {
+    fn as_obj(&mut self) -> &mut dyn Translator {
+        self
+    }
+
+    fn translate_function(&mut self, ty: u32, s: &mut FunctionSection) -> translate::Result<()> {
+        let i = self.functions as usize;
+        self.functions += 1;
+        if self.kept[i] {
+            translate::function(self, ty, s)?;
+        }
+        Ok(())
+    }
+
+    fn translate_code(
+        &mut self,
+        body: FunctionBody<'_>,
+        s: &mut CodeSection,
+    ) -> translate::Result<()> {
+        let i = self.bodies as usize;
+        self.bodies += 1;
+        if self.kept[i] {
+            translate::code(self, body, s)?;
+        }
+        Ok(())
+    }
+
+    fn translate_custom_section(
+        &mut self,
+        section: &wasmparser::CustomSectionReader<'_>,
+        module: &mut Module,
+    ) -> translate::Result<()> {
+        if section.name() != "name" {
+            return translate::custom_section(self, section, module);
+        }
+        // The names of merged functions are discarded along with them.
+        let reader = wasmparser::NameSectionReader::new(section.data(), section.data_offset())?;
+        module.section(&translate::remap_names_with(reader, &mut |item, idx| {
+            Ok(match item {
+                Item::Function if !self.is_kept(idx) => None,
+                Item::Function => Some(self.old_to_new[&idx]),
+                _ => Some(idx),
+            })
+        })?);
+        Ok(())
+    }
+
+    fn remap(&mut self, item: Item, idx: u32) -> translate::Result<u32> {
+        match item {
+            Item::Function => Ok(self.old_to_new[&idx]),
+            _ => Ok(idx),
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
+use std::io::Write;
+use std::ops::Range;
+use wasmparser::{BinaryReader, Parser, Payload, SectionWithLimitedItems};
 
 /// Debugging utility to dump information about a wasm binary.
 ///
@@ -8,13 +11,278 @@ use anyhow::Result;
 pub struct Opts {
     #[clap(flatten)]
     io: wasm_tools::InputOutput,
+
+    #[clap(flatten)]
+    sections: wasm_tools::SectionFilter,
+
+    /// Instead of dumping the binary, verify that its sections exactly tile
+    /// the file.
+    ///
+    /// The header of each section is decoded independently of the parser and
+    /// checked against the range the parser reports for the section. Each
+    /// section must start where the previous one ended, the items of each
+    /// section must consume all of its contents, and the last section must
+    /// end at the end of the file or nested module. Every discrepancy is
+    /// printed along with its offset.
+    #[clap(long)]
+    verify_offsets: bool,
 }
 
 impl Opts {
     pub fn run(&self) -> Result<()> {
         let input = self.io.parse_input_wasm()?;
-        let output = self.io.output_writer()?;
-        wasmparser_dump::dump_wasm_into(&input, output)?;
+        let mut output = self.io.output_writer()?;
+        if self.verify_offsets {
+            let problems = verify_offsets(&input)?;
+            for problem in problems.iter() {
+                writeln!(output, "{}", problem)?;
+            }
+            if !problems.is_empty() {
+                bail!("found {} offset discrepancies", problems.len());
+            }
+            return Ok(());
+        }
+        wasmparser_dump::dump_wasm_into_filtered(&input, output, |payload| {
+            self.sections.matches(payload)
+        })?;
         Ok(())
     }
 }
+
+/// The bytes of a module or component which remain to be accounted for.
+struct Level {
+    /// The offset at which the next section is expected to start.
+    next: usize,
+    /// The offset at which the module or component ends.
+    end: usize,
+    /// The offset at which the next entry of the code section currently
+    /// being parsed is expected to start, and the end of the code section.
+    code: Option<(usize, usize)>,
+}
+
+/// Returns a description of each place where the sections of `wasm` don't
+/// exactly tile the bytes of the file.
+fn verify_offsets(wasm: &[u8]) -> Result<Vec<String>> {
+    let mut problems = Vec::new();
+    let mut levels = vec![Level {
+        next: 0,
+        end: wasm.len(),
+        code: None,
+    }];
+    for payload in Parser::new(0).parse_all(wasm) {
+        let payload = payload?;
+        let level = levels.last_mut().unwrap();
+
+        // Any payload other than an entry means the code section is done, in
+        // which case all of the entries should have been accounted for.
+        if let Payload::CodeSectionEntry(body) = &payload {
+            let (next, end) = level.code.as_mut().unwrap();
+            let mut reader = BinaryReader::new_with_offset(&wasm[*next..*end], *next);
+            let size = reader.read_var_u32()? as usize;
+            let start = reader.original_position();
+            if body.range() != (start..start + size) {
+                problems.push(format!(
+                    "code section entry at offset {:#x}: parser reported range {:#x?}, \
+                     but its header describes {:#x?}",
+                    next,
+                    body.range(),
+                    start..start + size
+                ));
+            }
+            *next = body.range().end;
+            continue;
+        }
+        if let Some((next, end)) = level.code.take() {
+            if next != end {
+                problems.push(unused(next, end, "code section"));
+            }
+        }
+
+        match &payload {
+            Payload::Version { range, .. } => {
+                if range.start != level.next {
+                    problems.push(misplaced("header", level.next, range.start));
+                }
+                level.next = range.end;
+            }
+            Payload::End(offset) => {
+                if level.next != level.end {
+                    problems.push(unused(level.next, level.end, "end of the binary"));
+                } else if *offset != level.end {
+                    problems.push(format!(
+                        "end of the binary at offset {:#x}: parser reported the end at \
+                         offset {:#x}",
+                        level.end, offset
+                    ));
+                }
+                levels.pop();
+            }
+            _ => {
+                let (id, range) = match payload.as_section() {
+                    Some(section) => section,
+                    None => continue,
+                };
+                let name = section_name(&payload);
+                check_header(wasm, level, id, &range, name, &mut problems)?;
+                level.next = range.end;
+                if let Some(pos) = unused_items(&payload)? {
+                    problems.push(unused(pos, range.end, name));
+                }
+                match &payload {
+                    Payload::CodeSectionStart { range, .. } => {
+                        let mut reader =
+                            BinaryReader::new_with_offset(&wasm[range.clone()], range.start);
+                        reader.read_var_u32()?;
+                        level.code = Some((reader.original_position(), range.end));
+                    }
+                    Payload::ModuleSection { range, .. }
+                    | Payload::ComponentSection { range, .. } => levels.push(Level {
+                        next: range.start,
+                        end: range.end,
+                        code: None,
+                    }),
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(problems)
+}
+
+/// Decodes the header of the section expected at `level.next` and checks that
+/// it agrees with the section `id` which the parser reported at `range`.
+fn check_header(
+    wasm: &[u8],
+    level: &Level,
+    id: u8,
+    range: &Range<usize>,
+    name: &str,
+    problems: &mut Vec<String>,
+) -> Result<()> {
+    let mut reader = BinaryReader::new_with_offset(&wasm[level.next..level.end], level.next);
+    let actual_id = reader.read_u8()?;
+    let size = reader.read_var_u32()? as usize;
+    let start = reader.original_position();
+    if actual_id != id {
+        problems.push(format!(
+            "{} at offset {:#x}: parser reported section id {}, but the header has id {}",
+            name, level.next, id, actual_id
+        ));
+    }
+    if *range != (start..start + size) {
+        if range.start > start {
+            problems.push(misplaced(name, start, range.start));
+        } else {
+            problems.push(format!(
+                "{} at offset {:#x}: parser reported range {:#x?}, but its header describes \
+                 {:#x?}",
+                name,
+                level.next,
+                range,
+                start..start + size
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the offset of any bytes of the section `payload` which follow its
+/// items.
+fn unused_items(payload: &Payload<'_>) -> Result<Option<usize>> {
+    fn check<R: SectionWithLimitedItems + Clone>(reader: &R) -> Result<Option<usize>> {
+        let mut reader = reader.clone();
+        for _ in 0..reader.get_count() {
+            reader.read()?;
+        }
+        Ok(if reader.eof() {
+            None
+        } else {
+            Some(reader.original_position())
+        })
+    }
+    use Payload::*;
+    match payload {
+        TypeSection(s) => check(s),
+        ImportSection(s) => check(s),
+        FunctionSection(s) => check(s),
+        TableSection(s) => check(s),
+        MemorySection(s) => check(s),
+        TagSection(s) => check(s),
+        GlobalSection(s) => check(s),
+        ExportSection(s) => check(s),
+        ElementSection(s) => check(s),
+        DataSection(s) => check(s),
+        InstanceSection(s) => check(s),
+        CoreTypeSection(s) => check(s),
+        ComponentInstanceSection(s) => check(s),
+        ComponentAliasSection(s) => check(s),
+        ComponentTypeSection(s) => check(s),
+        ComponentCanonicalSection(s) => check(s),
+        ComponentImportSection(s) => check(s),
+        ComponentExportSection(s) => check(s),
+        _ => Ok(None),
+    }
+}
+
+fn misplaced(name: &str, expected: usize, actual: usize) -> String {
+    if actual > expected {
+        format!(
+            "{} at offset {:#x}: gap of {} bytes since offset {:#x}",
+            name,
+            actual,
+            actual - expected,
+            expected
+        )
+    } else {
+        format!(
+            "{} at offset {:#x}: overlaps {} bytes before offset {:#x}",
+            name,
+            actual,
+            expected - actual,
+            expected
+        )
+    }
+}
+
+fn unused(start: usize, end: usize, name: &str) -> String {
+    format!(
+        "{} bytes at offset {:#x} aren't part of any item before the end of the {} at offset {:#x}",
+        end - start,
+        start,
+        name,
+        end
+    )
+}
+
+fn section_name(payload: &Payload<'_>) -> &'static str {
+    use Payload::*;
+    match payload {
+        TypeSection(_) => "type section",
+        ImportSection(_) => "import section",
+        FunctionSection(_) => "function section",
+        TableSection(_) => "table section",
+        MemorySection(_) => "memory section",
+        TagSection(_) => "tag section",
+        GlobalSection(_) => "global section",
+        ExportSection(_) => "export section",
+        StartSection { .. } => "start section",
+        ElementSection(_) => "element section",
+        DataCountSection { .. } => "data count section",
+        CodeSectionStart { .. } => "code section",
+        DataSection(_) => "data section",
+        CustomSection(_) => "custom section",
+        ModuleSection { .. } => "module section",
+        InstanceSection(_) => "core instance section",
+        CoreTypeSection(_) => "core type section",
+        ComponentSection { .. } => "component section",
+        ComponentInstanceSection(_) => "component instance section",
+        ComponentAliasSection(_) => "component alias section",
+        ComponentTypeSection(_) => "component type section",
+        ComponentCanonicalSection(_) => "canonical function section",
+        ComponentStartSection(_) => "component start section",
+        ComponentImportSection(_) => "component import section",
+        ComponentExportSection(_) => "component export section",
+        UnknownSection { .. } => "unknown section",
+        Version { .. } | CodeSectionEntry(_) | End(_) => unreachable!(),
+    }
+}
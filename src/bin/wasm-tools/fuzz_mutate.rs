@@ -0,0 +1,233 @@
+use anyhow::{bail, Context, Result};
+use arbitrary::Unstructured;
+use std::io::{stdin, Read};
+use std::path::PathBuf;
+use wasm_mutate::{ErrorKind, WasmMutate};
+use wasm_smith::{Module, SwarmConfig};
+use wasmparser::{Validator, WasmFeatures};
+
+/// Check that `wasm-mutate` always produces valid modules.
+///
+/// Each input is used to generate a valid module with `wasm-smith`, which is
+/// then mutated a number of times with `wasm-mutate`. Every mutated module is
+/// validated, and since mutating a valid module should always produce a valid
+/// module, an invalid one indicates a bug in `wasm-mutate`.
+///
+/// When an invalid module is found the original and mutated modules are
+/// written to `--artifact-dir`, the input which produced them is reported so
+/// it can be replayed, and the process exits with a nonzero status.
+///
+/// ## Example
+///
+/// Check 1000 inputs, generated the same way as `wasm-tools smith --seed`:
+///
+/// $ wasm-tools fuzz-mutate --seed 0 --count 1000
+///
+/// Replay a single input read from a file:
+///
+/// $ wasm-tools fuzz-mutate input.bin
+#[derive(clap::Parser)]
+pub struct Opts {
+    /// The input used to generate a module and choose mutations.
+    ///
+    /// `stdin` is used if neither this nor `--seed` is supplied.
+    #[clap(conflicts_with = "seed")]
+    input: Option<PathBuf>,
+
+    /// Generate inputs from numbers starting at this one instead of reading
+    /// them.
+    #[clap(long)]
+    seed: Option<u64>,
+
+    /// The number of inputs to generate starting at `--seed`.
+    #[clap(long, default_value_t = 1, requires = "seed")]
+    count: u64,
+
+    /// The number of bytes of input generated for each number with `--seed`.
+    #[clap(long, default_value_t = 4096, value_name = "BYTES")]
+    seed_len: usize,
+
+    /// The maximum number of mutations of each generated module to validate.
+    #[clap(long, default_value_t = 10)]
+    mutations: usize,
+
+    /// The fuel given to `wasm-mutate` for each generated module.
+    #[clap(long, default_value_t = 300)]
+    fuel: u64,
+
+    /// The directory to write the original and mutated modules to when an
+    /// invalid module is found.
+    #[clap(long, default_value = ".", value_name = "DIR")]
+    artifact_dir: PathBuf,
+}
+
+/// A mutated module which failed to validate.
+struct Failure {
+    original: Vec<u8>,
+    mutated: Vec<u8>,
+    mutate_seed: u64,
+    mutation: usize,
+    error: wasmparser::BinaryReaderError,
+}
+
+#[derive(Default)]
+struct Stats {
+    inputs: u64,
+    generated: u64,
+    mutations: u64,
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let mut stats = Stats::default();
+        match self.seed {
+            Some(start) => {
+                for seed in start..start.saturating_add(self.count) {
+                    let input = wasm_tools::seed_bytes(seed, self.seed_len);
+                    let replay = format!("--seed {} --seed-len {}", seed, self.seed_len);
+                    self.check_input(&input, Some(replay), &mut stats)?;
+                }
+            }
+            None => {
+                let (input, replay) = match &self.input {
+                    Some(path) => (
+                        std::fs::read(path)
+                            .with_context(|| format!("failed to read '{}'", path.display()))?,
+                        Some(path.display().to_string()),
+                    ),
+                    None => {
+                        let mut input = Vec::new();
+                        stdin()
+                            .read_to_end(&mut input)
+                            .context("failed to read <stdin>")?;
+                        (input, None)
+                    }
+                };
+                self.check_input(&input, replay, &mut stats)?;
+            }
+        }
+
+        eprintln!(
+            "checked {} mutations of {} modules generated from {} inputs, all valid",
+            stats.mutations, stats.generated, stats.inputs,
+        );
+        Ok(())
+    }
+
+    /// Generates and mutates a module from `input`, reporting how to replay
+    /// it with the arguments in `replay` if an invalid module is produced.
+    ///
+    /// If `replay` is `None` then the input is written to `--artifact-dir` to
+    /// be replayed from there.
+    fn check_input(&self, input: &[u8], replay: Option<String>, stats: &mut Stats) -> Result<()> {
+        stats.inputs += 1;
+        let failure = match self.check(input, stats) {
+            Some(failure) => failure,
+            None => return Ok(()),
+        };
+
+        std::fs::create_dir_all(&self.artifact_dir)
+            .with_context(|| format!("failed to create '{}'", self.artifact_dir.display()))?;
+        let original = self.artifact_dir.join("original.wasm");
+        let mutated = self.artifact_dir.join("mutated.wasm");
+        std::fs::write(&original, &failure.original)
+            .with_context(|| format!("failed to write '{}'", original.display()))?;
+        std::fs::write(&mutated, &failure.mutated)
+            .with_context(|| format!("failed to write '{}'", mutated.display()))?;
+        let replay = match replay {
+            Some(replay) => replay,
+            None => {
+                let path = self.artifact_dir.join("input.bin");
+                std::fs::write(&path, input)
+                    .with_context(|| format!("failed to write '{}'", path.display()))?;
+                path.display().to_string()
+            }
+        };
+        bail!(
+            "mutation {} of the module generated from the input produced an invalid module: {}\n\
+             \n\
+             the mutations used `wasm-mutate` seed {}, the original module was written \
+             to '{}' and the invalid module to '{}'\n\
+             \n\
+             replay with: wasm-tools fuzz-mutate {} --mutations {} --fuel {}",
+            failure.mutation,
+            failure.error,
+            failure.mutate_seed,
+            original.display(),
+            mutated.display(),
+            replay,
+            self.mutations,
+            self.fuel,
+        )
+    }
+
+    fn check(&self, input: &[u8], stats: &mut Stats) -> Option<Failure> {
+        let mut u = Unstructured::new(input);
+        let (original, config, mutate_seed) = match generate(&mut u) {
+            Ok(generated) => generated,
+            Err(e) => {
+                log::debug!("failed to generate a module: {}", e);
+                return None;
+            }
+        };
+        stats.generated += 1;
+
+        let mut wasm_mutate = WasmMutate::default();
+        wasm_mutate.seed(mutate_seed).fuel(self.fuel);
+        let mutations = match wasm_mutate.run(&original) {
+            Ok(mutations) => mutations,
+            Err(e) => {
+                log::debug!("failed to mutate the module: {}", e);
+                return None;
+            }
+        };
+
+        // As with the fuzz target for `wasm-mutate` only off-by-default
+        // features which were enabled when generating the module are enabled
+        // here, since `wasm-mutate` doesn't otherwise introduce them.
+        let features = WasmFeatures {
+            relaxed_simd: config.relaxed_simd_enabled,
+            multi_memory: config.max_memories > 1,
+            memory64: config.memory64_enabled,
+            threads: config.threads_enabled,
+            ..WasmFeatures::default()
+        };
+
+        for (mutation, mutated) in mutations.take(self.mutations).enumerate() {
+            let mutated = match mutated {
+                Ok(mutated) => mutated,
+                Err(e) if matches!(e.kind(), ErrorKind::NoMutationsApplicable) => continue,
+                Err(e) => {
+                    log::debug!("failed to mutate the module: {}", e);
+                    break;
+                }
+            };
+            stats.mutations += 1;
+            if let Err(error) = Validator::new_with_features(features).validate_all(&mutated) {
+                return Some(Failure {
+                    original: original.clone(),
+                    mutated,
+                    mutate_seed,
+                    mutation,
+                    error,
+                });
+            }
+        }
+        None
+    }
+}
+
+/// Generates a module with a random configuration from `u`, and the seed to
+/// mutate it with.
+fn generate(u: &mut Unstructured<'_>) -> arbitrary::Result<(Vec<u8>, SwarmConfig, u64)> {
+    let mut config: SwarmConfig = u.arbitrary()?;
+    config.simd_enabled = u.arbitrary()?;
+    config.relaxed_simd_enabled = config.simd_enabled && u.arbitrary()?;
+    config.memory64_enabled = u.arbitrary()?;
+    config.threads_enabled = u.arbitrary()?;
+    // `wasm-mutate` doesn't support the exception handling proposal.
+    config.exceptions_enabled = false;
+    let mutate_seed = u.arbitrary()?;
+    let module = Module::new(config.clone(), u)?;
+    Ok((module.to_bytes(), config, mutate_seed))
+}
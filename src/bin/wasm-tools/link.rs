@@ -0,0 +1,438 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use wasm_encoder::{
+    CodeSection, DataCountSection, DataSection, ElementSection, ExportSection, FunctionSection,
+    GlobalSection, ImportSection, MemorySection, Module, StartSection, TableSection, TagSection,
+    TypeSection,
+};
+use wasm_tools::translate::{self, Item, Translator};
+use wasmparser::{ExternalKind, FunctionBody, Parser, Payload, Type, TypeRef, Validator};
+
+/// Link two core WebAssembly modules into one.
+///
+/// The imports of the second module from a module named after the file stem
+/// of the first, such as `a` for `a.wasm`, are resolved to the exports of the
+/// first module with the same names. Other imports of either module are kept
+/// as imports of the output.
+///
+/// Every other item of both modules is kept, with those of the first module
+/// placed before those of the second. The exports of both modules are kept,
+/// and it's an error for them to have an export of the same name. It's also
+/// an error for both modules to have a start function. Custom sections,
+/// including the `name` section, aren't preserved.
+///
+/// The output may have more than one memory or table, which requires the
+/// multi-memory or reference types proposals respectively.
+///
+/// ## Example
+///
+/// $ wasm-tools link a.wasm b.wasm -o out.wasm
+#[derive(clap::Parser)]
+pub struct Opts {
+    /// The module whose exports are linked to.
+    a: PathBuf,
+
+    /// The module whose imports are resolved.
+    b: PathBuf,
+
+    #[clap(flatten)]
+    output: wasm_tools::OutputArg,
+
+    /// Output the text format of WebAssembly instead of the binary format.
+    #[clap(short = 't', long)]
+    wat: bool,
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let a_wasm = wat::parse_file(&self.a)?;
+        let b_wasm = wat::parse_file(&self.b)?;
+        let a = Items::parse(&a_wasm, &self.a)?;
+        let b = Items::parse(&b_wasm, &self.b)?;
+        let name = match self.a.file_stem().and_then(|s| s.to_str()) {
+            Some(name) => name,
+            None => bail!("`{}` has no file name to import from", self.a.display()),
+        };
+
+        let output = link(&a, &b, name)?;
+        Validator::new_with_features(wasm_tools::all_core_features())
+            .validate_all(&output)
+            .context("output failed to validate")?;
+
+        self.output.output(wasm_tools::Output::Wasm {
+            bytes: &output,
+            wat: self.wat,
+        })?;
+        Ok(())
+    }
+}
+
+/// The items of a core wasm module.
+#[derive(Default)]
+struct Items<'a> {
+    types: Vec<Type>,
+    imports: Vec<wasmparser::Import<'a>>,
+    functions: Vec<u32>,
+    tables: Vec<wasmparser::TableType>,
+    memories: Vec<wasmparser::MemoryType>,
+    tags: Vec<wasmparser::TagType>,
+    globals: Vec<wasmparser::Global<'a>>,
+    exports: Vec<wasmparser::Export<'a>>,
+    start: Option<u32>,
+    elements: Vec<wasmparser::Element<'a>>,
+    data_count: bool,
+    bodies: Vec<FunctionBody<'a>>,
+    data: Vec<wasmparser::Data<'a>>,
+}
+
+impl<'a> Items<'a> {
+    fn parse(wasm: &'a [u8], path: &Path) -> Result<Items<'a>> {
+        Validator::new_with_features(wasm_tools::all_core_features())
+            .validate_all(wasm)
+            .with_context(|| format!("`{}` failed to validate", path.display()))?;
+        let mut items = Items::default();
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::Version { encoding, .. } if encoding != wasmparser::Encoding::Module => {
+                    bail!("`{}` is not a core wasm module", path.display())
+                }
+                Payload::TypeSection(s) => items.types = s.into_iter().collect::<Result<_, _>>()?,
+                Payload::ImportSection(s) => {
+                    items.imports = s.into_iter().collect::<Result<_, _>>()?
+                }
+                Payload::FunctionSection(s) => {
+                    items.functions = s.into_iter().collect::<Result<_, _>>()?
+                }
+                Payload::TableSection(s) => {
+                    items.tables = s.into_iter().collect::<Result<_, _>>()?
+                }
+                Payload::MemorySection(s) => {
+                    items.memories = s.into_iter().collect::<Result<_, _>>()?
+                }
+                Payload::TagSection(s) => items.tags = s.into_iter().collect::<Result<_, _>>()?,
+                Payload::GlobalSection(s) => {
+                    items.globals = s.into_iter().collect::<Result<_, _>>()?
+                }
+                Payload::ExportSection(s) => {
+                    items.exports = s.into_iter().collect::<Result<_, _>>()?
+                }
+                Payload::StartSection { func, .. } => items.start = Some(func),
+                Payload::ElementSection(s) => {
+                    items.elements = s.into_iter().collect::<Result<_, _>>()?
+                }
+                Payload::DataCountSection { .. } => items.data_count = true,
+                Payload::CodeSectionEntry(body) => items.bodies.push(body),
+                Payload::DataSection(s) => items.data = s.into_iter().collect::<Result<_, _>>()?,
+                _ => {}
+            }
+        }
+        Ok(items)
+    }
+
+    /// Returns the number of imported items of kind `item`.
+    fn imported(&self, item: Item) -> u32 {
+        self.imports
+            .iter()
+            .filter(|i| import_item(&i.ty) == item)
+            .count() as u32
+    }
+
+    /// Returns the number of items of kind `item` defined by the module.
+    fn defined(&self, item: Item) -> u32 {
+        let len = match item {
+            Item::Function => self.functions.len(),
+            Item::Table => self.tables.len(),
+            Item::Memory => self.memories.len(),
+            Item::Tag => self.tags.len(),
+            Item::Global => self.globals.len(),
+            Item::Type => self.types.len(),
+            Item::Data => self.data.len(),
+            Item::Element => self.elements.len(),
+        };
+        len as u32
+    }
+
+    /// Returns the type of the item of kind `item` at index `idx`, in the
+    /// form it would be imported as.
+    fn entity_type(&self, item: Item, idx: u32) -> TypeRef {
+        let imported = self.imports.iter().filter(|i| import_item(&i.ty) == item);
+        if let Some(import) = imported.clone().nth(idx as usize) {
+            return import.ty;
+        }
+        let i = (idx - imported.count() as u32) as usize;
+        match item {
+            Item::Function => TypeRef::Func(self.functions[i]),
+            Item::Table => TypeRef::Table(self.tables[i]),
+            Item::Memory => TypeRef::Memory(self.memories[i]),
+            Item::Global => TypeRef::Global(self.globals[i].ty),
+            Item::Tag => TypeRef::Tag(self.tags[i]),
+            _ => unreachable!(),
+        }
+    }
+
+    fn func_type(&self, idx: u32) -> &wasmparser::FuncType {
+        let Type::Func(ty) = &self.types[idx as usize];
+        ty
+    }
+}
+
+const ITEMS: [Item; 5] = [
+    Item::Function,
+    Item::Table,
+    Item::Memory,
+    Item::Tag,
+    Item::Global,
+];
+
+/// Adds each of the sections to `module` if it has any items.
+macro_rules! sections {
+    ($module:ident, $($section:ident),*) => {
+        $(
+            if !$section.is_empty() {
+                $module.section(&$section);
+            }
+        )*
+    };
+}
+
+/// Links `a` and `b` into a single module, resolving imports of `b` from the
+/// module `name` to the exports of `a`.
+fn link(a: &Items<'_>, b: &Items<'_>, name: &str) -> Result<Vec<u8>> {
+    if a.start.is_some() && b.start.is_some() {
+        bail!("both modules have a start function");
+    }
+    for export in b.exports.iter() {
+        if a.exports.iter().any(|e| e.name == export.name) {
+            bail!("both modules have an export named `{}`", export.name);
+        }
+    }
+
+    // The index of the export of `a` which satisfies each import of `b`, if
+    // it's resolved.
+    let mut resolved = Vec::new();
+    for import in b.imports.iter() {
+        if import.module != name {
+            resolved.push(None);
+            continue;
+        }
+        let item = import_item(&import.ty);
+        let export = match a.exports.iter().find(|e| e.name == import.name) {
+            Some(export) => export,
+            None => bail!(
+                "unresolved import `{}::{}`: the first module has no such export",
+                import.module,
+                import.name
+            ),
+        };
+        if export_item(export.kind) != item {
+            bail!(
+                "import `{}::{}` is a {:?} but the export is a {:?}",
+                import.module,
+                import.name,
+                item,
+                export_item(export.kind)
+            );
+        }
+        if !compatible(b, &import.ty, a, &a.entity_type(item, export.index)) {
+            bail!(
+                "import `{}::{}` has a type which doesn't match its export",
+                import.module,
+                import.name
+            );
+        }
+        resolved.push(Some(export.index));
+    }
+
+    // Each index space of the output is, in order, the imports of `a`, the
+    // unresolved imports of `b`, the items defined by `a`, and then those
+    // defined by `b`.
+    let mut a_map = Remap::default();
+    let mut b_map = Remap::default();
+    for item in ITEMS {
+        let a_imported = a.imported(item);
+        let b_unresolved = b
+            .imports
+            .iter()
+            .zip(&resolved)
+            .filter(|(i, r)| import_item(&i.ty) == item && r.is_none())
+            .count() as u32;
+        let a_defined = a.defined(item);
+
+        let a_indices = a_map.0.entry(item).or_default();
+        a_indices.extend(0..a_imported);
+        a_indices.extend((0..a_defined).map(|i| a_imported + b_unresolved + i));
+
+        let mut unresolved = 0;
+        let b_indices = b_map.0.entry(item).or_default();
+        for (import, resolved) in b.imports.iter().zip(&resolved) {
+            if import_item(&import.ty) != item {
+                continue;
+            }
+            match resolved {
+                Some(idx) => b_indices.push(a_map.0[&item][*idx as usize]),
+                None => {
+                    b_indices.push(a_imported + unresolved);
+                    unresolved += 1;
+                }
+            }
+        }
+        b_indices.extend((0..b.defined(item)).map(|i| a_imported + b_unresolved + a_defined + i));
+    }
+    for item in [Item::Type, Item::Data, Item::Element] {
+        a_map.0.insert(item, (0..a.defined(item)).collect());
+        b_map.0.insert(
+            item,
+            (0..b.defined(item)).map(|i| a.defined(item) + i).collect(),
+        );
+    }
+
+    let mut module = Module::new();
+    let mut types = TypeSection::new();
+    for (items, map) in [(a, &mut a_map), (b, &mut b_map)] {
+        for ty in items.types.iter() {
+            translate::type_def(map, ty.clone(), &mut types)?;
+        }
+    }
+    sections!(module, types);
+
+    let mut imports = ImportSection::new();
+    for import in a.imports.iter() {
+        translate::import_def(&mut a_map, *import, &mut imports)?;
+    }
+    for (import, resolved) in b.imports.iter().zip(&resolved) {
+        if resolved.is_none() {
+            translate::import_def(&mut b_map, *import, &mut imports)?;
+        }
+    }
+    sections!(module, imports);
+
+    let mut functions = FunctionSection::new();
+    let mut tables = TableSection::new();
+    let mut memories = MemorySection::new();
+    let mut tags = TagSection::new();
+    let mut globals = GlobalSection::new();
+    let mut exports = ExportSection::new();
+    let mut elements = ElementSection::new();
+    let mut code = CodeSection::new();
+    let mut data = DataSection::new();
+    for (items, map) in [(a, &mut a_map), (b, &mut b_map)] {
+        for ty in items.functions.iter() {
+            translate::function(map, *ty, &mut functions)?;
+        }
+        for ty in items.tables.iter() {
+            translate::table(map, *ty, &mut tables)?;
+        }
+        for ty in items.memories.iter() {
+            translate::memory(map, *ty, &mut memories)?;
+        }
+        for ty in items.tags.iter() {
+            translate::tag(map, *ty, &mut tags)?;
+        }
+        for global in items.globals.iter() {
+            translate::global(map, *global, &mut globals)?;
+        }
+        for export in items.exports.iter() {
+            translate::export(map, export, &mut exports)?;
+        }
+        for element in items.elements.iter() {
+            translate::element(map, element.clone(), &mut elements)?;
+        }
+        for body in items.bodies.iter() {
+            translate::code(map, *body, &mut code)?;
+        }
+        for segment in items.data.iter() {
+            translate::data(map, segment.clone(), &mut data)?;
+        }
+    }
+    sections!(module, functions, tables, memories, tags, globals, exports);
+    if let Some(start) = a.start {
+        module.section(&StartSection {
+            function_index: a_map.remap(Item::Function, start)?,
+        });
+    }
+    if let Some(start) = b.start {
+        module.section(&StartSection {
+            function_index: b_map.remap(Item::Function, start)?,
+        });
+    }
+    sections!(module, elements);
+    if a.data_count || b.data_count {
+        module.section(&DataCountSection { count: data.len() });
+    }
+    sections!(module, code, data);
+    Ok(module.finish())
+}
+
+/// Returns whether an item of type `actual` in `a` can be used to satisfy an
+/// import of type `expected` in `b`.
+fn compatible(b: &Items<'_>, expected: &TypeRef, a: &Items<'_>, actual: &TypeRef) -> bool {
+    fn limits<T: PartialOrd>(expected: (T, Option<T>), actual: (T, Option<T>)) -> bool {
+        actual.0 >= expected.0
+            && match (expected.1, actual.1) {
+                (None, _) => true,
+                (Some(expected), Some(actual)) => actual <= expected,
+                (Some(_), None) => false,
+            }
+    }
+    match (expected, actual) {
+        (TypeRef::Func(expected), TypeRef::Func(actual)) => {
+            b.func_type(*expected) == a.func_type(*actual)
+        }
+        (TypeRef::Tag(expected), TypeRef::Tag(actual)) => {
+            b.func_type(expected.func_type_idx) == a.func_type(actual.func_type_idx)
+        }
+        (TypeRef::Global(expected), TypeRef::Global(actual)) => expected == actual,
+        (TypeRef::Table(expected), TypeRef::Table(actual)) => {
+            expected.element_type == actual.element_type
+                && limits(
+                    (expected.initial, expected.maximum),
+                    (actual.initial, actual.maximum),
+                )
+        }
+        (TypeRef::Memory(expected), TypeRef::Memory(actual)) => {
+            expected.memory64 == actual.memory64
+                && expected.shared == actual.shared
+                && expected.page_size_log2 == actual.page_size_log2
+                && limits(
+                    (expected.initial, expected.maximum),
+                    (actual.initial, actual.maximum),
+                )
+        }
+        _ => false,
+    }
+}
+
+fn import_item(ty: &TypeRef) -> Item {
+    match ty {
+        TypeRef::Func(_) => Item::Function,
+        TypeRef::Table(_) => Item::Table,
+        TypeRef::Memory(_) => Item::Memory,
+        TypeRef::Global(_) => Item::Global,
+        TypeRef::Tag(_) => Item::Tag,
+    }
+}
+
+fn export_item(kind: ExternalKind) -> Item {
+    match kind {
+        ExternalKind::Func => Item::Function,
+        ExternalKind::Table => Item::Table,
+        ExternalKind::Memory => Item::Memory,
+        ExternalKind::Global => Item::Global,
+        ExternalKind::Tag => Item::Tag,
+    }
+}
+
+/// The new index of every item of one of the modules being linked.
+#[derive(Default)]
+struct Remap(HashMap<Item, Vec<u32>>);
+
+impl Translator for Remap {
+    fn as_obj(&mut self) -> &mut dyn Translator {
+        self
+    }
+
+    fn remap(&mut self, item: Item, idx: u32) -> translate::Result<u32> {
+        Ok(self.0[&item][idx as usize])
+    }
+}
@@ -0,0 +1,319 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use wasmparser::{
+    FunctionBody, ImportCounts, MemoryType, Operator, Parser, Payload, TypeRef, Validator,
+};
+
+/// Report instructions of a module which are guaranteed to trap.
+///
+/// The module is validated and then each function is checked for:
+///
+/// * Integer division or remainder by a constant zero, and signed division
+///   of the constant minimum value by a constant -1.
+/// * `memory.init` with a constant offset and length which are out of bounds
+///   of the data segment.
+/// * An `unreachable` which is reached on every path through the function.
+///
+/// The analysis is conservative, and only reports instructions which trap
+/// whenever they're executed. For example an `unreachable` preceded by a
+/// call isn't reported since the call may never return. Operands are known
+/// to be constant if they're pushed by a constant instruction, or read from
+/// a local which was set to a constant earlier in the same block. Each
+/// finding is printed as a warning along with the index of the function and
+/// the offset of the instruction, since trapping code is valid and may be
+/// intentional.
+///
+/// ## Example
+///
+/// $ wasm-tools lint foo.wasm
+///
+/// $ wasm-tools lint --memory-bounds foo.wasm
+#[derive(clap::Parser)]
+pub struct Opts {
+    #[clap(flatten)]
+    io: wasm_tools::InputOutput,
+
+    /// Also report `memory.copy`, `memory.fill`, and `memory.init` with a
+    /// constant offset and length which are out of bounds of the initial
+    /// size of the memory.
+    ///
+    /// Such an instruction traps unless the memory has been grown before it's
+    /// executed.
+    #[clap(long)]
+    memory_bounds: bool,
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let input = self.io.parse_input_wasm()?;
+        Validator::new_with_features(wasm_tools::all_core_features())
+            .validate_all(&input)
+            .context("input failed to validate")?;
+
+        let mut imported_funcs = 0;
+        let mut memories = Vec::new();
+        let mut data_lens = Vec::new();
+        let mut bodies = Vec::new();
+        for payload in Parser::new(0).parse_all(&input) {
+            match payload? {
+                Payload::Version { encoding, .. } if encoding != wasmparser::Encoding::Module => {
+                    bail!("only core wasm modules can be linted")
+                }
+                Payload::ImportSection(reader) => {
+                    imported_funcs = ImportCounts::from_reader(reader.clone())?.funcs;
+                    for import in reader {
+                        if let TypeRef::Memory(ty) = import?.ty {
+                            memories.push(ty);
+                        }
+                    }
+                }
+                Payload::MemorySection(reader) => {
+                    for ty in reader {
+                        memories.push(ty?);
+                    }
+                }
+                Payload::DataSection(reader) => {
+                    for data in reader {
+                        data_lens.push(data?.data.len() as u64);
+                    }
+                }
+                Payload::CodeSectionEntry(body) => bodies.push(body),
+                _ => {}
+            }
+        }
+
+        // Functions are linted once the whole module has been parsed since
+        // the data section follows the code section.
+        let memories = if self.memory_bounds {
+            Some(&memories[..])
+        } else {
+            None
+        };
+        for (i, body) in bodies.iter().enumerate() {
+            for (offset, message) in lint_function(body, &data_lens, memories)? {
+                eprintln!(
+                    "warning: func {} at offset {:#x}: {}",
+                    imported_funcs + i as u32,
+                    offset,
+                    message
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the offset of each instruction of `body` which is guaranteed to
+/// trap along with a description of why, given the lengths of the module's
+/// data segments.
+///
+/// Accesses out of bounds of the initial size of `memories` are also
+/// reported if they're given.
+fn lint_function(
+    body: &FunctionBody<'_>,
+    data_lens: &[u64],
+    memories: Option<&[MemoryType]>,
+) -> Result<Vec<(usize, String)>> {
+    let mut findings = Vec::new();
+    // The values pushed by the constant instructions, or by reads of
+    // constant locals, which immediately precede the current one, which are
+    // therefore its operands.
+    let mut consts = Vec::new();
+    // The locals which were set to a constant since the start of the
+    // current straight-line run of instructions.
+    let mut locals = HashMap::new();
+    // Whether every path through the function so far reaches the current
+    // instruction, for instructions at the top level of the function.
+    let mut always_reached = true;
+    let mut depth = 0;
+    let mut reader = body.get_operators_reader()?;
+    reader.allow_memarg64(true);
+    while !reader.eof() {
+        let (op, offset) = reader.read_with_offset()?;
+        let operand = |n: usize| consts.len().checked_sub(n).map(|i| consts[i]);
+        let finding = match &op {
+            Operator::I32DivS | Operator::I64DivS => {
+                let min = match op {
+                    Operator::I32DivS => i32::MIN.into(),
+                    _ => i64::MIN,
+                };
+                match (operand(2), operand(1)) {
+                    (_, Some(0)) => Some(divide_by_zero(&op)),
+                    (Some(lhs), Some(-1)) if lhs == min => Some(format!(
+                        "`{}` overflows dividing the minimum value by -1",
+                        name(&op)
+                    )),
+                    _ => None,
+                }
+            }
+            Operator::I32DivU
+            | Operator::I32RemS
+            | Operator::I32RemU
+            | Operator::I64DivU
+            | Operator::I64RemS
+            | Operator::I64RemU => match operand(1) {
+                Some(0) => Some(divide_by_zero(&op)),
+                _ => None,
+            },
+            Operator::MemoryInit { data_index, mem } => {
+                // The offset and length are always `i32`s, and are
+                // interpreted as unsigned.
+                let len = data_lens[*data_index as usize];
+                match (operand(3), operand(2), operand(1)) {
+                    (_, Some(src), Some(n))
+                        if u64::from(src as u32) + u64::from(n as u32) > len =>
+                    {
+                        Some(format!(
+                            "`memory.init` of {} bytes at offset {} is out of bounds of data \
+                             segment {} of {} bytes",
+                            n as u32, src as u32, data_index, len
+                        ))
+                    }
+                    (Some(dst), _, Some(n)) => memories.and_then(|memories| {
+                        let n = u64::from(n as u32);
+                        memory_bounds(&op, memories, *mem, dst, n, "to")
+                    }),
+                    _ => None,
+                }
+            }
+            Operator::MemoryFill { mem } => match (memories, operand(3), operand(1)) {
+                (Some(memories), Some(dst), Some(n)) => {
+                    let n = index(&memories[*mem as usize], n);
+                    memory_bounds(&op, memories, *mem, dst, n, "at")
+                }
+                _ => None,
+            },
+            Operator::MemoryCopy { dst_mem, src_mem } => {
+                match (memories, operand(3), operand(2), operand(1)) {
+                    (Some(memories), Some(dst), Some(src), Some(n)) => {
+                        // The length is only an `i64` if both memories are
+                        // 64-bit.
+                        let n = match memories[*dst_mem as usize].memory64 {
+                            true => index(&memories[*src_mem as usize], n),
+                            false => u64::from(n as u32),
+                        };
+                        memory_bounds(&op, memories, *dst_mem, dst, n, "to")
+                            .or_else(|| memory_bounds(&op, memories, *src_mem, src, n, "from"))
+                    }
+                    _ => None,
+                }
+            }
+            Operator::Unreachable if depth == 0 && always_reached => {
+                Some("`unreachable` is reached on every path through the function".to_string())
+            }
+            _ => None,
+        };
+        findings.extend(finding.map(|f| (offset, f)));
+
+        match op {
+            Operator::LocalSet { local_index } | Operator::LocalTee { local_index } => {
+                match consts.last() {
+                    Some(value) => locals.insert(local_index, *value),
+                    None => locals.remove(&local_index),
+                };
+            }
+            // Other paths with different values of locals may join at any of
+            // these.
+            Operator::Loop { .. }
+            | Operator::Else
+            | Operator::End
+            | Operator::Catch { .. }
+            | Operator::CatchAll
+            | Operator::Delegate { .. } => locals.clear(),
+            _ => {}
+        }
+        match op {
+            Operator::I32Const { value } => consts.push(i64::from(value)),
+            Operator::I64Const { value } => consts.push(value),
+            Operator::LocalGet { local_index } if locals.contains_key(&local_index) => {
+                consts.push(locals[&local_index])
+            }
+            // The value set is popped, while the value teed stays where it is.
+            Operator::LocalSet { .. } => {
+                consts.pop();
+            }
+            Operator::LocalTee { .. } => {}
+            _ => consts.clear(),
+        }
+        match op {
+            Operator::Block { .. }
+            | Operator::Loop { .. }
+            | Operator::If { .. }
+            | Operator::Try { .. } => depth += 1,
+            Operator::End | Operator::Delegate { .. } => depth -= 1,
+            // Anything which may leave the function, or not continue to the
+            // next instruction, means that later instructions aren't
+            // necessarily reached.
+            Operator::Br { .. }
+            | Operator::BrIf { .. }
+            | Operator::BrTable { .. }
+            | Operator::Return
+            | Operator::Call { .. }
+            | Operator::CallIndirect { .. }
+            | Operator::ReturnCall { .. }
+            | Operator::ReturnCallIndirect { .. }
+            | Operator::Throw { .. }
+            | Operator::Rethrow { .. } => always_reached = false,
+            // Only the first `unreachable` on every path is reported.
+            Operator::Unreachable if depth == 0 => always_reached = false,
+            _ => {}
+        }
+    }
+    Ok(findings)
+}
+
+/// Returns a description of the access of `len` bytes of memory `mem` at the
+/// constant `offset` if it's out of bounds of the initial size of the memory.
+fn memory_bounds(
+    op: &Operator<'_>,
+    memories: &[MemoryType],
+    mem: u32,
+    offset: i64,
+    len: u64,
+    direction: &str,
+) -> Option<String> {
+    let ty = &memories[mem as usize];
+    let offset = index(ty, offset);
+    let size = u128::from(ty.initial) * u128::from(ty.page_size());
+    if u128::from(offset) + u128::from(len) <= size {
+        return None;
+    }
+    let name = match op {
+        Operator::MemoryInit { .. } => "memory.init",
+        Operator::MemoryFill { .. } => "memory.fill",
+        Operator::MemoryCopy { .. } => "memory.copy",
+        _ => unreachable!(),
+    };
+    Some(format!(
+        "`{}` of {} bytes {} offset {} is out of bounds of the initial {} bytes of memory {}",
+        name, len, direction, offset, size, mem
+    ))
+}
+
+/// Interprets the constant `value` as an unsigned index into a memory of
+/// type `ty`.
+fn index(ty: &MemoryType, value: i64) -> u64 {
+    match ty.memory64 {
+        true => value as u64,
+        false => u64::from(value as u32),
+    }
+}
+
+fn divide_by_zero(op: &Operator<'_>) -> String {
+    format!("`{}` divides by a constant zero", name(op))
+}
+
+/// Returns the text format name of a numeric binary operator.
+fn name(op: &Operator<'_>) -> &'static str {
+    match op {
+        Operator::I32DivS => "i32.div_s",
+        Operator::I32DivU => "i32.div_u",
+        Operator::I32RemS => "i32.rem_s",
+        Operator::I32RemU => "i32.rem_u",
+        Operator::I64DivS => "i64.div_s",
+        Operator::I64DivU => "i64.div_u",
+        Operator::I64RemS => "i64.rem_s",
+        Operator::I64RemU => "i64.rem_u",
+        _ => unreachable!(),
+    }
+}
@@ -54,6 +54,21 @@ subcommands! {
     (objdump, "objdump")
     (strip, "strip")
     (compose, "compose")
+    (normalize_leb, "normalize-leb")
+    (pack_data, "pack-data")
+    (component, "component")
+    (metrics, "metrics")
+    (coredump_dump, "coredump-dump")
+    (fuzz_mutate, "fuzz-mutate")
+    (add_names, "add-names")
+    (atomics, "atomics")
+    (rename_custom_section, "rename-custom-section")
+    (build_id, "build-id")
+    (subset, "subset")
+    (pack_elements, "pack-elements")
+    (dedup, "dedup")
+    (lint, "lint")
+    (link, "link")
 }
 
 fn main() -> ExitCode {
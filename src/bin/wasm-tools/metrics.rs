@@ -0,0 +1,423 @@
+use anyhow::{bail, Result};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use wasmparser::{
+    Encoding, FunctionBody, ImportCounts, Name, NameSectionReader, Operator, Parser, Payload, Type,
+};
+
+/// Report complexity metrics for each function in a WebAssembly module.
+///
+/// For each function defined in the module this reports:
+///
+/// * the number of instructions in its body,
+///
+/// * its cyclomatic complexity, the number of linearly independent paths
+///   through the function's control flow graph,
+///
+/// * the maximum depth of nested `block`, `loop`, `if`, and `try`
+///   instructions, and
+///
+/// * the number of locals, including parameters.
+///
+/// Functions are identified by their index and, if the module has a `name`
+/// section, their name.
+#[derive(clap::Parser)]
+pub struct Opts {
+    #[clap(flatten)]
+    io: wasm_tools::InputOutput,
+
+    /// The metric to sort functions by.
+    ///
+    /// Functions are sorted with the largest value first, except when sorting
+    /// by index.
+    #[clap(long, value_enum, default_value_t = SortBy::Index)]
+    sort_by: SortBy,
+
+    /// Print metrics as JSON instead of as a table.
+    #[clap(long)]
+    json: bool,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum SortBy {
+    Index,
+    Instructions,
+    Complexity,
+    Depth,
+    Locals,
+}
+
+#[derive(serde::Serialize)]
+struct Metrics {
+    index: u32,
+    name: Option<String>,
+    instructions: u32,
+    complexity: u32,
+    max_depth: u32,
+    locals: u32,
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let wasm = self.io.parse_input_wasm()?;
+        let mut metrics = function_metrics(&wasm)?;
+
+        match self.sort_by {
+            SortBy::Index => metrics.sort_by_key(|m| m.index),
+            SortBy::Instructions => metrics.sort_by_key(|m| std::cmp::Reverse(m.instructions)),
+            SortBy::Complexity => metrics.sort_by_key(|m| std::cmp::Reverse(m.complexity)),
+            SortBy::Depth => metrics.sort_by_key(|m| std::cmp::Reverse(m.max_depth)),
+            SortBy::Locals => metrics.sort_by_key(|m| std::cmp::Reverse(m.locals)),
+        }
+
+        let mut output = self.io.output_writer()?;
+        if self.json {
+            serde_json::to_writer_pretty(&mut output, &metrics)?;
+            writeln!(output)?;
+            return Ok(());
+        }
+        writeln!(
+            output,
+            "{:>8} {:>12} {:>10} {:>6} {:>6}  name",
+            "index", "instructions", "complexity", "depth", "locals"
+        )?;
+        for m in metrics.iter() {
+            writeln!(
+                output,
+                "{:>8} {:>12} {:>10} {:>6} {:>6}  {}",
+                m.index,
+                m.instructions,
+                m.complexity,
+                m.max_depth,
+                m.locals,
+                m.name.as_deref().unwrap_or(""),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Computes the metrics of every function defined in the core module `wasm`.
+fn function_metrics(wasm: &[u8]) -> Result<Vec<Metrics>> {
+    let mut types = Vec::new();
+    let mut func_types = Vec::new();
+    let mut imported_funcs = 0;
+    let mut names = HashMap::new();
+    let mut bodies = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::Version {
+                encoding: Encoding::Component,
+                ..
+            } => bail!("components are not supported"),
+            Payload::TypeSection(reader) => {
+                for ty in reader {
+                    let Type::Func(ty) = ty?;
+                    types.push(ty.params().len() as u32);
+                }
+            }
+            Payload::ImportSection(reader) => {
+                imported_funcs = ImportCounts::from_reader(reader)?.funcs;
+            }
+            Payload::FunctionSection(reader) => {
+                for ty in reader {
+                    func_types.push(ty?);
+                }
+            }
+            Payload::CodeSectionEntry(body) => bodies.push(body),
+            Payload::CustomSection(c) if c.name() == "name" => {
+                let reader = NameSectionReader::new(c.data(), c.data_offset())?;
+                // Names are only used to make the output friendlier, so an
+                // invalid name section is ignored.
+                let _ = read_function_names(reader, &mut names);
+            }
+            _ => {}
+        }
+    }
+
+    let mut metrics = Vec::new();
+    for (i, body) in bodies.iter().enumerate() {
+        let index = imported_funcs + i as u32;
+        let params = match func_types.get(i).and_then(|ty| types.get(*ty as usize)) {
+            Some(params) => *params,
+            None => bail!("function {} has an invalid type", index),
+        };
+        let mut m = body_metrics(body)?;
+        m.index = index;
+        m.name = names.get(&index).cloned();
+        m.locals += params;
+        metrics.push(m);
+    }
+    Ok(metrics)
+}
+
+fn read_function_names(
+    reader: NameSectionReader<'_>,
+    names: &mut HashMap<u32, String>,
+) -> Result<()> {
+    for name in reader {
+        if let Name::Function(map) = name? {
+            let mut map = map.get_map()?;
+            for _ in 0..map.get_count() {
+                let naming = map.read()?;
+                names.insert(naming.index, naming.name.to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Computes the metrics of a single function body, other than its index and
+/// name.
+fn body_metrics(body: &FunctionBody<'_>) -> Result<Metrics> {
+    let mut locals = 0;
+    for local in body.get_locals_reader()? {
+        locals += local?.0;
+    }
+
+    let mut cfg = Cfg::new();
+    let mut instructions = 0;
+    let mut reader = body.get_operators_reader()?;
+    reader.allow_memarg64(true);
+    for op in reader {
+        instructions += 1;
+        cfg.operator(&op?)?;
+    }
+
+    Ok(Metrics {
+        index: 0,
+        name: None,
+        instructions,
+        complexity: cfg.cyclomatic_complexity(),
+        max_depth: cfg.max_depth,
+        locals,
+    })
+}
+
+/// A control flow graph of basic blocks, reconstructed from the structured
+/// control flow instructions of a function body.
+///
+/// Only the edges between blocks are recorded since that's all that's
+/// necessary to compute the cyclomatic complexity of the function.
+struct Cfg {
+    /// The successors of each basic block.
+    edges: Vec<Vec<usize>>,
+    /// The block that instructions are currently being appended to.
+    current: usize,
+    /// The stack of control frames that are currently open.
+    frames: Vec<Frame>,
+    max_depth: u32,
+}
+
+/// The block of the function's entry.
+const ENTRY: usize = 0;
+/// A block which all returns, traps, and throws flow to.
+const EXIT: usize = 1;
+
+struct Frame {
+    kind: FrameKind,
+    /// The block executed after this frame's `end`.
+    end: usize,
+}
+
+enum FrameKind {
+    Block,
+    Loop {
+        header: usize,
+    },
+    If {
+        /// The block executed when the condition is false, if an `else` hasn't
+        /// been seen yet.
+        alternative: Option<usize>,
+    },
+    Try {
+        /// The first block of the `try` body, from which any handler may be
+        /// entered.
+        body: usize,
+    },
+}
+
+impl Cfg {
+    fn new() -> Cfg {
+        Cfg {
+            edges: vec![Vec::new(), Vec::new()],
+            current: ENTRY,
+            // The function body itself is an implicit block whose end is a
+            // return.
+            frames: vec![Frame {
+                kind: FrameKind::Block,
+                end: EXIT,
+            }],
+            max_depth: 0,
+        }
+    }
+
+    fn new_block(&mut self) -> usize {
+        self.edges.push(Vec::new());
+        self.edges.len() - 1
+    }
+
+    fn edge(&mut self, from: usize, to: usize) {
+        self.edges[from].push(to);
+    }
+
+    /// Starts a new block following an unconditional transfer of control.
+    ///
+    /// Nothing branches to the new block so it's unreachable unless it's the
+    /// target of a later branch.
+    fn terminate(&mut self) {
+        self.current = self.new_block();
+    }
+
+    fn push(&mut self, kind: FrameKind) {
+        let end = self.new_block();
+        self.frames.push(Frame { kind, end });
+        self.max_depth = self.max_depth.max(self.frames.len() as u32 - 1);
+    }
+
+    /// Returns the block that a branch to `relative_depth` jumps to.
+    fn branch_target(&self, relative_depth: u32) -> Result<usize> {
+        let frame = match self.frames.len().checked_sub(relative_depth as usize + 1) {
+            Some(i) => &self.frames[i],
+            None => bail!("branch depth {} is out of bounds", relative_depth),
+        };
+        Ok(match frame.kind {
+            FrameKind::Loop { header } => header,
+            _ => frame.end,
+        })
+    }
+
+    fn frame(&mut self) -> Result<&mut Frame> {
+        match self.frames.last_mut() {
+            Some(frame) => Ok(frame),
+            None => bail!("control frames are unbalanced"),
+        }
+    }
+
+    fn operator(&mut self, op: &Operator<'_>) -> Result<()> {
+        match op {
+            Operator::Block { .. } => self.push(FrameKind::Block),
+            Operator::Loop { .. } => {
+                let header = self.new_block();
+                self.edge(self.current, header);
+                self.current = header;
+                self.push(FrameKind::Loop { header });
+            }
+            Operator::If { .. } => {
+                let consequent = self.new_block();
+                let alternative = self.new_block();
+                self.edge(self.current, consequent);
+                self.edge(self.current, alternative);
+                self.current = consequent;
+                self.push(FrameKind::If {
+                    alternative: Some(alternative),
+                });
+            }
+            Operator::Else => {
+                let current = self.current;
+                let frame = self.frame()?;
+                let end = frame.end;
+                let alternative = match &mut frame.kind {
+                    FrameKind::If { alternative } => alternative.take(),
+                    _ => None,
+                };
+                let alternative = match alternative {
+                    Some(block) => block,
+                    None => bail!("`else` found outside of an `if`"),
+                };
+                self.edge(current, end);
+                self.current = alternative;
+            }
+            Operator::Try { .. } => {
+                let body = self.new_block();
+                self.edge(self.current, body);
+                self.current = body;
+                self.push(FrameKind::Try { body });
+            }
+            Operator::Catch { .. } | Operator::CatchAll => {
+                let current = self.current;
+                let frame = self.frame()?;
+                let (end, body) = match frame.kind {
+                    FrameKind::Try { body } => (frame.end, body),
+                    _ => bail!("`catch` found outside of a `try`"),
+                };
+                self.edge(current, end);
+                self.current = self.new_block();
+                self.edge(body, self.current);
+            }
+            Operator::Delegate { .. } | Operator::End => {
+                let frame = match self.frames.pop() {
+                    Some(frame) => frame,
+                    None => bail!("control frames are unbalanced"),
+                };
+                if let FrameKind::If {
+                    alternative: Some(alternative),
+                } = frame.kind
+                {
+                    self.edge(alternative, frame.end);
+                }
+                self.edge(self.current, frame.end);
+                self.current = frame.end;
+            }
+            Operator::Br { relative_depth } => {
+                let target = self.branch_target(*relative_depth)?;
+                self.edge(self.current, target);
+                self.terminate();
+            }
+            Operator::BrIf { relative_depth } => {
+                let target = self.branch_target(*relative_depth)?;
+                let next = self.new_block();
+                self.edge(self.current, target);
+                self.edge(self.current, next);
+                self.current = next;
+            }
+            Operator::BrTable { targets } => {
+                let mut seen = HashSet::new();
+                for depth in targets.targets().chain(Some(Ok(targets.default()))) {
+                    let target = self.branch_target(depth?)?;
+                    if seen.insert(target) {
+                        self.edge(self.current, target);
+                    }
+                }
+                self.terminate();
+            }
+            Operator::Return
+            | Operator::ReturnCall { .. }
+            | Operator::ReturnCallIndirect { .. }
+            | Operator::Unreachable
+            | Operator::Throw { .. }
+            | Operator::Rethrow { .. } => {
+                self.edge(self.current, EXIT);
+                self.terminate();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Returns the cyclomatic complexity of the graph, `E - N + 2`, where `E`
+    /// and `N` are the number of edges and blocks reachable from the entry.
+    fn cyclomatic_complexity(&self) -> u32 {
+        let mut reachable = vec![false; self.edges.len()];
+        let mut stack = vec![ENTRY];
+        reachable[ENTRY] = true;
+        while let Some(block) = stack.pop() {
+            for succ in self.edges[block].iter() {
+                if !reachable[*succ] {
+                    reachable[*succ] = true;
+                    stack.push(*succ);
+                }
+            }
+        }
+
+        let nodes = reachable.iter().filter(|r| **r).count();
+        let edges = self
+            .edges
+            .iter()
+            .zip(&reachable)
+            .filter(|(_, r)| **r)
+            .map(|(succs, _)| succs.len())
+            .sum::<usize>();
+        (edges + 2).saturating_sub(nodes).max(1) as u32
+    }
+}
@@ -0,0 +1,104 @@
+use anyhow::{Context, Result};
+use wasm_encoder::{CodeSection, Function, ValType};
+use wasm_tools::translate::{self, DefaultTranslator, Translator};
+use wasmparser::{FunctionBody, Validator};
+
+/// Re-encodes all LEB128 integers in a module in their minimal form.
+///
+/// Some producers emit padded, non-minimal LEB128 encodings which are valid
+/// but waste space and make builds harder to reproduce. This subcommand
+/// rewrites every integer field of a core wasm module, including section and
+/// item sizes, with its shortest encoding. Active element segments of
+/// `funcref`s for table 0 are also written in the MVP encoding, without an
+/// explicit table index, and the module is otherwise unchanged. The number of
+/// bytes saved is reported on stderr.
+#[derive(clap::Parser)]
+pub struct Opts {
+    #[clap(flatten)]
+    io: wasm_tools::InputOutput,
+
+    /// Also coalesce the local declarations of each function, merging
+    /// consecutive groups of locals of the same type into one group.
+    ///
+    /// Local indices, and so the meaning of each function, are unchanged.
+    /// This gives producers which declare locals differently the same
+    /// encoding, for example to diff their output.
+    #[clap(long)]
+    flatten_locals: bool,
+
+    #[clap(flatten)]
+    debug: wasm_tools::DebugInfo,
+
+    /// Output the text format of WebAssembly instead of the binary format.
+    #[clap(short = 't', long)]
+    wat: bool,
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let input = self.io.parse_input_wasm()?;
+        let output = if self.flatten_locals {
+            let output = translate::module(&mut FlattenLocals, &input)?;
+            Validator::new_with_features(wasm_tools::all_core_features())
+                .validate_all(&output)
+                .context("output failed to validate")?;
+            output
+        } else {
+            translate::module(&mut DefaultTranslator, &input)?
+        };
+        let output = self.debug.check(&input, output)?;
+        eprintln!(
+            "normalized {} bytes to {} bytes ({} bytes saved)",
+            input.len(),
+            output.len(),
+            input.len() as i64 - output.len() as i64,
+        );
+
+        self.io.output(wasm_tools::Output::Wasm {
+            bytes: &output,
+            wat: self.wat,
+        })?;
+        Ok(())
+    }
+}
+
+/// A translator which coalesces the local declarations of each function.
+struct FlattenLocals;
+
+impl Translator for FlattenLocals {
+    fn as_obj(&mut self) -> &mut dyn Translator {
+        self
+    }
+
+    fn translate_code(
+        &mut self,
+        body: FunctionBody<'_>,
+        s: &mut CodeSection,
+    ) -> translate::Result<()> {
+        let mut locals: Vec<(u32, ValType)> = Vec::new();
+        for local in body.get_locals_reader()? {
+            let (count, ty) = local?;
+            let ty = self.translate_ty(&ty)?;
+            match locals.last_mut() {
+                Some((n, last)) if *last == ty => {
+                    *n = n
+                        .checked_add(count)
+                        .ok_or_else(|| translate::Error::other("too many locals"))?;
+                }
+                // Empty groups declare no locals and are dropped.
+                _ if count == 0 => {}
+                _ => locals.push((count, ty)),
+            }
+        }
+        let mut func = Function::new(locals);
+
+        let mut reader = body.get_operators_reader()?;
+        reader.allow_memarg64(true);
+        for op in reader {
+            let op = op?;
+            func.instruction(&self.translate_op(&op)?);
+        }
+        s.function(&func);
+        Ok(())
+    }
+}
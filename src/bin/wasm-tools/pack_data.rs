@@ -0,0 +1,420 @@
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use wasm_encoder::{ConstExpr, DataSection, DataSegment, DataSegmentMode, Module};
+use wasm_tools::translate::{self, Item, Translator};
+use wasmparser::{DataKind, Operator, Parser, Payload, TypeRef};
+
+/// Shrinks the data segments of a module.
+///
+/// This subcommand rewrites the data section of a core wasm module to take up
+/// less space without changing its behavior:
+///
+/// * Adjacent or overlapping active segments are merged into one segment.
+///
+/// * Active segments containing only zeros are removed from memories defined
+///   within the module, since memories start zeroed.
+///
+/// * Identical passive segments are deduplicated and `memory.init`
+///   instructions are updated to refer to the remaining segment.
+///
+/// Only active segments with constant offsets which are known to be in-bounds
+/// of the memory's minimum size are modified, and segments referred to by
+/// `memory.init` or `data.drop` are never merged or removed. Passive segments
+/// are only deduplicated if they're never dropped with `data.drop` since the
+/// remaining segment would otherwise be dropped for all of its users.
+#[derive(clap::Parser)]
+pub struct Opts {
+    #[clap(flatten)]
+    io: wasm_tools::InputOutput,
+
+    #[clap(flatten)]
+    debug: wasm_tools::DebugInfo,
+
+    /// Output the text format of WebAssembly instead of the binary format.
+    #[clap(short = 't', long)]
+    wat: bool,
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let input = self.io.parse_input_wasm()?;
+        let mut packer = Packer::new(&input)?;
+        let output = translate::module(&mut packer, &input)?;
+        let output = self.debug.check(&input, output)?;
+        eprintln!(
+            "packed {} data segments into {} ({} bytes saved)",
+            packer.segments.len(),
+            packer.new_count,
+            input.len() as i64 - output.len() as i64,
+        );
+
+        self.io.output(wasm_tools::Output::Wasm {
+            bytes: &output,
+            wat: self.wat,
+        })?;
+        Ok(())
+    }
+}
+
+struct Memory {
+    imported: bool,
+    memory64: bool,
+    min_bytes: u64,
+}
+
+struct Segment<'a> {
+    data: &'a [u8],
+    kind: SegmentKind,
+}
+
+enum SegmentKind {
+    Passive,
+    Active {
+        memory: u32,
+        /// The constant, in-bounds range of memory that this segment
+        /// initializes, if known.
+        range: Option<Range<u64>>,
+    },
+}
+
+/// What to do with each of the original data segments.
+enum Action {
+    Keep,
+    Remove,
+    /// Replace the segment with an active segment initializing `data` at
+    /// `offset`, the result of merging it with subsequent segments.
+    Merge {
+        offset: u64,
+        data: Vec<u8>,
+    },
+}
+
+struct Packer<'a> {
+    memories: Vec<Memory>,
+    segments: Vec<Segment<'a>>,
+    actions: Vec<Action>,
+    old_to_new: HashMap<u32, u32>,
+    new_count: u32,
+    next_segment: usize,
+}
+
+impl<'a> Packer<'a> {
+    fn new(wasm: &'a [u8]) -> Result<Packer<'a>> {
+        let mut memories = Vec::new();
+        let mut segments = Vec::new();
+        let mut initialized = HashSet::new();
+        let mut dropped = HashSet::new();
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::ImportSection(reader) => {
+                    for import in reader {
+                        if let TypeRef::Memory(ty) = import?.ty {
+                            memories.push(Memory::new(&ty, true));
+                        }
+                    }
+                }
+                Payload::MemorySection(reader) => {
+                    for ty in reader {
+                        memories.push(Memory::new(&ty?, false));
+                    }
+                }
+                Payload::DataSection(reader) => {
+                    for data in reader {
+                        let data = data?;
+                        let kind = match data.kind {
+                            DataKind::Passive => SegmentKind::Passive,
+                            DataKind::Active {
+                                memory_index,
+                                offset_expr,
+                            } => SegmentKind::Active {
+                                memory: memory_index,
+                                range: const_offset(&offset_expr)?.and_then(|offset| {
+                                    let memory = memories.get(memory_index as usize)?;
+                                    let end = offset.checked_add(data.data.len() as u64)?;
+                                    if end <= memory.min_bytes {
+                                        Some(offset..end)
+                                    } else {
+                                        None
+                                    }
+                                }),
+                            },
+                        };
+                        segments.push(Segment {
+                            data: data.data,
+                            kind,
+                        });
+                    }
+                }
+                Payload::CodeSectionEntry(body) => {
+                    let mut reader = body.get_operators_reader()?;
+                    reader.allow_memarg64(true);
+                    for op in reader {
+                        match op? {
+                            Operator::MemoryInit { data_index, .. } => {
+                                initialized.insert(data_index);
+                            }
+                            Operator::DataDrop { data_index } => {
+                                dropped.insert(data_index);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut packer = Packer {
+            memories,
+            segments,
+            actions: Vec::new(),
+            old_to_new: HashMap::new(),
+            new_count: 0,
+            next_segment: 0,
+        };
+        packer.plan(&initialized, &dropped);
+        Ok(packer)
+    }
+
+    /// Decides what to do with each data segment, filling in `actions` and
+    /// `old_to_new`.
+    fn plan(&mut self, initialized: &HashSet<u32>, dropped: &HashSet<u32>) {
+        let referenced = |i: usize| {
+            let i = i as u32;
+            initialized.contains(&i) || dropped.contains(&i)
+        };
+
+        // Determine the active segments which are candidates for merging or
+        // removal: those with a known range which aren't referenced by any
+        // instruction.
+        let mut candidates = self
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| match &segment.kind {
+                SegmentKind::Active {
+                    memory,
+                    range: Some(range),
+                } if !referenced(i) => Some((*memory, range.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // Segments of zeros can be removed if nothing else initializes the
+        // memory they cover, since the memory is otherwise already zeroed.
+        let mut remove = vec![false; self.segments.len()];
+        for (i, candidate) in candidates.iter().enumerate() {
+            let (memory, range) = match candidate {
+                Some(c) => c,
+                None => continue,
+            };
+            if self.memories[*memory as usize].imported
+                || self.segments[i].data.iter().any(|b| *b != 0)
+            {
+                continue;
+            }
+            let overlaps = self.segments.iter().enumerate().any(|(j, other)| {
+                i != j
+                    && match &other.kind {
+                        SegmentKind::Active {
+                            memory: m,
+                            range: Some(r),
+                        } => m == memory && r.start < range.end && range.start < r.end,
+                        // Segments with unknown ranges may overlap anything.
+                        SegmentKind::Active { memory: m, .. } => m == memory,
+                        SegmentKind::Passive => false,
+                    }
+            });
+            remove[i] = !overlaps;
+        }
+        for (i, remove) in remove.iter().enumerate() {
+            if *remove {
+                candidates[i] = None;
+            }
+        }
+
+        // Merge runs of candidates which touch or overlap. Only passive
+        // segments, which don't initialize memory at instantiation, and
+        // removed segments may appear between merged segments so the order in
+        // which memory is initialized is preserved.
+        let mut actions = Vec::new();
+        let mut merged_into = vec![None; self.segments.len()];
+        let mut run: Option<(usize, u32, Range<u64>)> = None;
+        for i in 0..self.segments.len() {
+            if remove[i] {
+                actions.push(Action::Remove);
+                continue;
+            }
+            let (memory, range) = match &candidates[i] {
+                Some(c) => c.clone(),
+                None => {
+                    actions.push(Action::Keep);
+                    if let SegmentKind::Active { .. } = self.segments[i].kind {
+                        run = None;
+                    }
+                    continue;
+                }
+            };
+            if let Some((start, run_memory, run_range)) = &mut run {
+                if *run_memory == memory
+                    && range.start <= run_range.end
+                    && run_range.start <= range.end
+                {
+                    run_range.start = run_range.start.min(range.start);
+                    run_range.end = run_range.end.max(range.end);
+                    merged_into[i] = Some(*start);
+                    actions.push(Action::Remove);
+                    continue;
+                }
+            }
+            run = Some((i, memory, range));
+            actions.push(Action::Keep);
+        }
+
+        // Build the contents of each merged segment, applying the merged
+        // segments in their original order so later segments take precedence.
+        let mut groups = HashMap::<usize, Vec<usize>>::new();
+        for (i, into) in merged_into.iter().enumerate() {
+            if let Some(into) = into {
+                groups.entry(*into).or_insert_with(|| vec![*into]).push(i);
+            }
+        }
+        for (start, group) in groups {
+            let range = |i: usize| match &self.segments[i].kind {
+                SegmentKind::Active {
+                    range: Some(range), ..
+                } => range.clone(),
+                _ => unreachable!(),
+            };
+            let offset = group.iter().map(|i| range(*i).start).min().unwrap();
+            let end = group.iter().map(|i| range(*i).end).max().unwrap();
+            let mut data = vec![0; (end - offset) as usize];
+            for i in group {
+                let r = range(i);
+                data[(r.start - offset) as usize..(r.end - offset) as usize]
+                    .copy_from_slice(self.segments[i].data);
+            }
+            actions[start] = Action::Merge { offset, data };
+        }
+
+        // Deduplicate passive segments which are never dropped, and then
+        // assign new indices to all the remaining segments.
+        let mut passive = HashMap::<&[u8], u32>::new();
+        for (i, segment) in self.segments.iter().enumerate() {
+            let i = i as u32;
+            if let Action::Remove = actions[i as usize] {
+                continue;
+            }
+            if let SegmentKind::Passive = segment.kind {
+                if !dropped.contains(&i) {
+                    if let Some(new) = passive.get(segment.data) {
+                        self.old_to_new.insert(i, *new);
+                        actions[i as usize] = Action::Remove;
+                        continue;
+                    }
+                    passive.insert(segment.data, self.new_count);
+                }
+            }
+            self.old_to_new.insert(i, self.new_count);
+            self.new_count += 1;
+        }
+        self.actions = actions;
+    }
+}
+
+impl Memory {
+    fn new(ty: &wasmparser::MemoryType, imported: bool) -> Memory {
+        Memory {
+            imported,
+            memory64: ty.memory64,
+            min_bytes: ty.initial.saturating_mul(ty.page_size()),
+        }
+    }
+}
+
+/// Returns the value of `expr` if it's a single `i32.const` or `i64.const`.
+fn const_offset(expr: &wasmparser::ConstExpr<'_>) -> Result<Option<u64>> {
+    let mut reader = expr.get_operators_reader();
+    let offset = match reader.read()? {
+        Operator::I32Const { value } => u64::from(value as u32),
+        Operator::I64Const { value } => value as u64,
+        _ => return Ok(None),
+    };
+    match reader.read()? {
+        Operator::End if reader.eof() => Ok(Some(offset)),
+        _ => Ok(None),
+    }
+}
+
+impl Translator for Packer<'_> {
+    fn as_obj(&mut self) -> &mut dyn Translator {
+        self
+    }
+
+    fn translate_data(
+        &mut self,
+        d: wasmparser::Data<'_>,
+        s: &mut DataSection,
+    ) -> translate::Result<()> {
+        let i = self.next_segment;
+        self.next_segment += 1;
+        match &self.actions[i] {
+            Action::Keep => translate::data(self, d, s),
+            Action::Remove => Ok(()),
+            Action::Merge { offset, data } => {
+                let memory_index = match d.kind {
+                    DataKind::Active { memory_index, .. } => memory_index,
+                    DataKind::Passive => unreachable!(),
+                };
+                let offset = if self.memories[memory_index as usize].memory64 {
+                    ConstExpr::i64_const(*offset as i64)
+                } else {
+                    ConstExpr::i32_const(*offset as i32)
+                };
+                s.segment(DataSegment {
+                    mode: DataSegmentMode::Active {
+                        memory_index,
+                        offset: &offset,
+                    },
+                    data: data.iter().copied(),
+                });
+                Ok(())
+            }
+        }
+    }
+
+    fn translate_data_count(&mut self, _count: u32) -> translate::Result<u32> {
+        Ok(self.new_count)
+    }
+
+    fn translate_custom_section(
+        &mut self,
+        section: &wasmparser::CustomSectionReader<'_>,
+        module: &mut Module,
+    ) -> translate::Result<()> {
+        if section.name() != "name" {
+            return translate::custom_section(self, section, module);
+        }
+        let reader = wasmparser::NameSectionReader::new(section.data(), section.data_offset())?;
+        module.section(&translate::remap_item_names(
+            reader,
+            Item::Data,
+            &self.old_to_new,
+        )?);
+        Ok(())
+    }
+
+    fn remap(&mut self, item: Item, idx: u32) -> translate::Result<u32> {
+        match item {
+            Item::Data => match self.old_to_new.get(&idx) {
+                Some(idx) => Ok(*idx),
+                None => Err(translate::Error::other(format!(
+                    "data segment {} was removed",
+                    idx
+                ))),
+            },
+            _ => Ok(idx),
+        }
+    }
+}
@@ -0,0 +1,468 @@
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use wasm_encoder::{ConstExpr, ElementMode, ElementSection, ElementSegment, Elements, Module};
+use wasm_tools::translate::{self, ConstExprKind, Item, Translator};
+use wasmparser::{ElementItem, ElementKind, Operator, Parser, Payload, TypeRef, Validator};
+
+/// Shrinks the element segments of a module.
+///
+/// This subcommand rewrites the element section of a core wasm module to take
+/// up less space without changing its behavior:
+///
+/// * Adjacent or overlapping active segments for the same table are merged
+///   into one segment.
+///
+/// * Active segments containing only `ref.null` are removed from tables
+///   defined within the module, since tables start filled with null.
+///
+/// * Empty segments are removed.
+///
+/// Only active segments with constant offsets which are known to be in-bounds
+/// of the table's minimum size are modified, and segments referred to by
+/// `table.init` or `elem.drop` are never merged or removed. The indices of the
+/// remaining segments are updated in `table.init` and `elem.drop`
+/// instructions and in the `name` section.
+#[derive(clap::Parser)]
+pub struct Opts {
+    #[clap(flatten)]
+    io: wasm_tools::InputOutput,
+
+    #[clap(flatten)]
+    debug: wasm_tools::DebugInfo,
+
+    /// Output the text format of WebAssembly instead of the binary format.
+    #[clap(short = 't', long)]
+    wat: bool,
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let input = self.io.parse_input_wasm()?;
+        let mut packer = Packer::new(&input)?;
+        let output = translate::module(&mut packer, &input)?;
+        Validator::new_with_features(wasm_tools::all_core_features())
+            .validate_all(&output)
+            .context("output failed to validate")?;
+        let output = self.debug.check(&input, output)?;
+        eprintln!(
+            "packed {} element segments into {} ({} bytes saved)",
+            packer.segments.len(),
+            packer.new_count,
+            input.len() as i64 - output.len() as i64,
+        );
+
+        self.io.output(wasm_tools::Output::Wasm {
+            bytes: &output,
+            wat: self.wat,
+        })?;
+        Ok(())
+    }
+}
+
+struct Table {
+    imported: bool,
+    initial: u32,
+}
+
+/// An item of an element segment.
+#[derive(Clone, Copy)]
+enum Value<'a> {
+    Func(u32),
+    Expr(wasmparser::ConstExpr<'a>),
+}
+
+struct Segment<'a> {
+    values: Vec<Value<'a>>,
+    kind: SegmentKind,
+}
+
+enum SegmentKind {
+    /// A passive or declared segment.
+    Inactive,
+    Active {
+        table: u32,
+        /// The constant, in-bounds range of the table that this segment
+        /// initializes, if known.
+        range: Option<Range<u32>>,
+    },
+}
+
+/// What to do with each of the original element segments.
+enum Action<'a> {
+    Keep,
+    Remove,
+    /// Replace the segment with an active segment initializing `values` at
+    /// `offset`, the result of merging it with subsequent segments.
+    Merge {
+        offset: u32,
+        values: Vec<Value<'a>>,
+    },
+}
+
+struct Packer<'a> {
+    segments: Vec<Segment<'a>>,
+    actions: Vec<Action<'a>>,
+    old_to_new: HashMap<u32, u32>,
+    new_count: u32,
+    next_segment: usize,
+}
+
+impl<'a> Packer<'a> {
+    fn new(wasm: &'a [u8]) -> Result<Packer<'a>> {
+        let mut tables = Vec::new();
+        let mut segments = Vec::new();
+        let mut referenced = HashSet::new();
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::ImportSection(reader) => {
+                    for import in reader {
+                        if let TypeRef::Table(ty) = import?.ty {
+                            tables.push(Table {
+                                imported: true,
+                                initial: ty.initial,
+                            });
+                        }
+                    }
+                }
+                Payload::TableSection(reader) => {
+                    for ty in reader {
+                        tables.push(Table {
+                            imported: false,
+                            initial: ty?.initial,
+                        });
+                    }
+                }
+                Payload::ElementSection(reader) => {
+                    for element in reader {
+                        let element = element?;
+                        let mut values = Vec::new();
+                        let mut reader = element.items.get_items_reader()?;
+                        for _ in 0..reader.get_count() {
+                            values.push(match reader.read()? {
+                                ElementItem::Func(idx) => Value::Func(idx),
+                                ElementItem::Expr(expr) => Value::Expr(expr),
+                            });
+                        }
+                        let kind = match element.kind {
+                            ElementKind::Passive | ElementKind::Declared => SegmentKind::Inactive,
+                            ElementKind::Active {
+                                table_index,
+                                offset_expr,
+                            } => SegmentKind::Active {
+                                table: table_index,
+                                range: const_offset(&offset_expr)?.and_then(|offset| {
+                                    let table = tables.get(table_index as usize)?;
+                                    let end = offset.checked_add(values.len() as u32)?;
+                                    if end <= table.initial {
+                                        Some(offset..end)
+                                    } else {
+                                        None
+                                    }
+                                }),
+                            },
+                        };
+                        segments.push(Segment { values, kind });
+                    }
+                }
+                Payload::CodeSectionEntry(body) => {
+                    let mut reader = body.get_operators_reader()?;
+                    reader.allow_memarg64(true);
+                    for op in reader {
+                        match op? {
+                            Operator::TableInit { elem_index, .. }
+                            | Operator::ElemDrop { elem_index } => {
+                                referenced.insert(elem_index);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut packer = Packer {
+            segments,
+            actions: Vec::new(),
+            old_to_new: HashMap::new(),
+            new_count: 0,
+            next_segment: 0,
+        };
+        packer.plan(&tables, &referenced)?;
+        Ok(packer)
+    }
+
+    /// Decides what to do with each element segment, filling in `actions`
+    /// and `old_to_new`.
+    fn plan(&mut self, tables: &[Table], referenced: &HashSet<u32>) -> Result<()> {
+        let referenced = |i: usize| referenced.contains(&(i as u32));
+
+        // Determine the active segments which are candidates for merging or
+        // removal: those with a known range which aren't referenced by any
+        // instruction.
+        let mut candidates = self
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| match &segment.kind {
+                SegmentKind::Active {
+                    table,
+                    range: Some(range),
+                } if !referenced(i) => Some((*table, range.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // Empty segments don't initialize anything, and segments of nulls can
+        // be removed if nothing else initializes the slots they cover, since
+        // the table otherwise already holds null there.
+        let mut remove = vec![false; self.segments.len()];
+        for (i, segment) in self.segments.iter().enumerate() {
+            if segment.values.is_empty() {
+                remove[i] = match segment.kind {
+                    SegmentKind::Inactive => !referenced(i),
+                    SegmentKind::Active { .. } => candidates[i].is_some(),
+                };
+                continue;
+            }
+            let (table, range) = match &candidates[i] {
+                Some(c) => c,
+                None => continue,
+            };
+            if tables[*table as usize].imported || !all_null(&segment.values)? {
+                continue;
+            }
+            let overlaps = self.segments.iter().enumerate().any(|(j, other)| {
+                i != j
+                    && match &other.kind {
+                        SegmentKind::Active {
+                            table: t,
+                            range: Some(r),
+                        } => t == table && r.start < range.end && range.start < r.end,
+                        // Segments with unknown ranges may overlap anything.
+                        SegmentKind::Active { table: t, .. } => t == table,
+                        SegmentKind::Inactive => false,
+                    }
+            });
+            remove[i] = !overlaps;
+        }
+        for (i, remove) in remove.iter().enumerate() {
+            if *remove {
+                candidates[i] = None;
+            }
+        }
+
+        // Merge runs of candidates which touch or overlap. Only inactive
+        // segments, which don't initialize tables at instantiation, and
+        // removed segments may appear between merged segments so the order in
+        // which tables are initialized is preserved.
+        let mut actions = Vec::new();
+        let mut merged_into = vec![None; self.segments.len()];
+        let mut run: Option<(usize, u32, Range<u32>)> = None;
+        for i in 0..self.segments.len() {
+            if remove[i] {
+                actions.push(Action::Remove);
+                continue;
+            }
+            let (table, range) = match &candidates[i] {
+                Some(c) => c.clone(),
+                None => {
+                    actions.push(Action::Keep);
+                    if let SegmentKind::Active { .. } = self.segments[i].kind {
+                        run = None;
+                    }
+                    continue;
+                }
+            };
+            if let Some((start, run_table, run_range)) = &mut run {
+                if *run_table == table
+                    && range.start <= run_range.end
+                    && run_range.start <= range.end
+                {
+                    run_range.start = run_range.start.min(range.start);
+                    run_range.end = run_range.end.max(range.end);
+                    merged_into[i] = Some(*start);
+                    actions.push(Action::Remove);
+                    continue;
+                }
+            }
+            run = Some((i, table, range));
+            actions.push(Action::Keep);
+        }
+
+        // Build the contents of each merged segment, applying the merged
+        // segments in their original order so later segments take precedence.
+        let mut groups = HashMap::<usize, Vec<usize>>::new();
+        for (i, into) in merged_into.iter().enumerate() {
+            if let Some(into) = into {
+                groups.entry(*into).or_insert_with(|| vec![*into]).push(i);
+            }
+        }
+        for (start, group) in groups {
+            let range = |i: usize| match &self.segments[i].kind {
+                SegmentKind::Active {
+                    range: Some(range), ..
+                } => range.clone(),
+                _ => unreachable!(),
+            };
+            let offset = group.iter().map(|i| range(*i).start).min().unwrap();
+            let end = group.iter().map(|i| range(*i).end).max().unwrap();
+            let mut values = vec![None; (end - offset) as usize];
+            for i in group {
+                let r = range(i);
+                for (slot, value) in values[(r.start - offset) as usize..(r.end - offset) as usize]
+                    .iter_mut()
+                    .zip(&self.segments[i].values)
+                {
+                    *slot = Some(*value);
+                }
+            }
+            // Merged segments touch or overlap, so every slot is filled.
+            let values = values.into_iter().map(|v| v.unwrap()).collect();
+            actions[start] = Action::Merge { offset, values };
+        }
+
+        for (i, action) in actions.iter().enumerate() {
+            if let Action::Remove = action {
+                continue;
+            }
+            self.old_to_new.insert(i as u32, self.new_count);
+            self.new_count += 1;
+        }
+        self.actions = actions;
+        Ok(())
+    }
+}
+
+/// Returns whether all of `values` are `ref.null`.
+fn all_null(values: &[Value<'_>]) -> Result<bool> {
+    for value in values {
+        let expr = match value {
+            Value::Func(_) => return Ok(false),
+            Value::Expr(expr) => expr,
+        };
+        let mut reader = expr.get_operators_reader();
+        if !matches!(reader.read()?, Operator::RefNull { .. }) {
+            return Ok(false);
+        }
+        if !matches!(reader.read()?, Operator::End) || !reader.eof() {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Returns the value of `expr` if it's a single `i32.const`.
+fn const_offset(expr: &wasmparser::ConstExpr<'_>) -> Result<Option<u32>> {
+    let mut reader = expr.get_operators_reader();
+    let offset = match reader.read()? {
+        Operator::I32Const { value } => value as u32,
+        _ => return Ok(None),
+    };
+    match reader.read()? {
+        Operator::End if reader.eof() => Ok(Some(offset)),
+        _ => Ok(None),
+    }
+}
+
+impl Translator for Packer<'_> {
+    fn as_obj(&mut self) -> &mut dyn Translator {
+        self
+    }
+
+    fn translate_element(
+        &mut self,
+        e: wasmparser::Element<'_>,
+        s: &mut ElementSection,
+    ) -> translate::Result<()> {
+        let i = self.next_segment;
+        self.next_segment += 1;
+        let (offset, values) = match &self.actions[i] {
+            Action::Keep => return translate::element(self, e, s),
+            Action::Remove => return Ok(()),
+            Action::Merge { offset, values } => (*offset, values.clone()),
+        };
+        let table = match e.kind {
+            ElementKind::Active { table_index, .. } => table_index,
+            ElementKind::Passive | ElementKind::Declared => unreachable!(),
+        };
+        let table = self.remap(Item::Table, table)?;
+        let element_type = self.translate_ty(&e.ty)?;
+        let offset = ConstExpr::i32_const(offset as i32);
+
+        // Function indices are only encoded as such if no merged segment used
+        // expressions.
+        let mut functions = Vec::new();
+        let mut exprs = Vec::new();
+        let uses_exprs = values.iter().any(|v| matches!(v, Value::Expr(_)));
+        for value in values {
+            match value {
+                Value::Func(idx) => {
+                    let idx = self.remap(Item::Function, idx)?;
+                    if uses_exprs {
+                        exprs.push(ConstExpr::ref_func(idx));
+                    } else {
+                        functions.push(idx);
+                    }
+                }
+                Value::Expr(expr) => {
+                    exprs.push(self.translate_const_expr(
+                        &expr,
+                        &e.ty,
+                        ConstExprKind::ElementFunction,
+                    )?);
+                }
+            }
+        }
+        s.segment(ElementSegment {
+            mode: ElementMode::Active {
+                // Prefer the MVP encoding where possible since it's the most
+                // compact form.
+                table: if table == 0 && e.ty == wasmparser::ValType::FuncRef {
+                    None
+                } else {
+                    Some(table)
+                },
+                offset: &offset,
+            },
+            element_type,
+            elements: if uses_exprs {
+                Elements::Expressions(&exprs)
+            } else {
+                Elements::Functions(&functions)
+            },
+        });
+        Ok(())
+    }
+
+    fn translate_custom_section(
+        &mut self,
+        section: &wasmparser::CustomSectionReader<'_>,
+        module: &mut Module,
+    ) -> translate::Result<()> {
+        if section.name() != "name" {
+            return translate::custom_section(self, section, module);
+        }
+        let reader = wasmparser::NameSectionReader::new(section.data(), section.data_offset())?;
+        module.section(&translate::remap_item_names(
+            reader,
+            Item::Element,
+            &self.old_to_new,
+        )?);
+        Ok(())
+    }
+
+    fn remap(&mut self, item: Item, idx: u32) -> translate::Result<u32> {
+        match item {
+            Item::Element => match self.old_to_new.get(&idx) {
+                Some(idx) => Ok(*idx),
+                None => Err(translate::Error::other(format!(
+                    "element segment {} was removed",
+                    idx
+                ))),
+            },
+            _ => Ok(idx),
+        }
+    }
+}
@@ -1,10 +1,26 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use clap::Parser;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use wasm_encoder::{
+    Component, ComponentSectionId, CustomSection, Encode, InstanceSection, ModuleArg, RawSection,
+};
+use wasmparser::Payload;
 
 /// Parse the WebAssembly text format.
 ///
 /// This subcommand will parse the provided input as the WebAssembly text format
 /// and optionally write the binary form to a provided file.
+///
+/// # Function locations
+///
+/// With `--name-locations` a custom section named `function-locations` is
+/// appended to the module, recording the line of the text on which each
+/// function is defined or imported. Its contents are a vector, in the same
+/// encoding as vectors in the binary format, of pairs of a function index and
+/// a line number, both encoded as `u32` LEB128s. Entries are sorted by
+/// function index and line numbers start at 1.
 #[derive(Parser)]
 pub struct Opts {
     #[clap(flatten)]
@@ -13,11 +29,70 @@ pub struct Opts {
     /// Output the text format of WebAssembly instead of the binary format.
     #[clap(short = 't', long)]
     wat: bool,
+
+    /// Print a table of the time spent decoding each section to stderr.
+    ///
+    /// Every item of each section, including the operators of each function
+    /// body, is decoded with `wasmparser` to measure how long that section
+    /// takes to parse. The table is sorted with the slowest section first.
+    #[clap(long)]
+    timings: bool,
+
+    /// Decode the binary this many times and print the minimum, median, and
+    /// maximum time taken and the throughput to stderr.
+    ///
+    /// As with `--timings` every item of each section is decoded with
+    /// `wasmparser`, without validation, to measure the raw speed of the
+    /// parser. The output is unaffected.
+    #[clap(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    count: Option<u32>,
+
+    /// Treat the input as a `.wast` script and link its modules into a single
+    /// component.
+    ///
+    /// Each module in the script is instantiated in order, with its imports
+    /// satisfied by the exports of modules previously named with `register`.
+    /// It's an error for an import to name a module which hasn't been
+    /// registered or an export which the registered module doesn't have.
+    /// Directives other than modules and `register` are ignored.
+    #[clap(long)]
+    link: bool,
+
+    /// Append a custom section to the module recording the line of the
+    /// input on which each function is defined.
+    ///
+    /// See the function locations above for the layout of the section. The
+    /// input must be the text format of a core module.
+    #[clap(long, conflicts_with = "link")]
+    name_locations: bool,
 }
 
 impl Opts {
     pub fn run(&self) -> Result<()> {
-        let binary = self.io.parse_input_wasm()?;
+        let binary = if self.link {
+            let (contents, path) = self.io.read_input()?;
+            let path = path.unwrap_or(Path::new("<stdin>"));
+            let contents = std::str::from_utf8(&contents)
+                .with_context(|| format!("input file `{}` was not valid utf-8", path.display()))?;
+            link_wast(contents, path)?
+        } else if self.name_locations {
+            let (contents, path) = self.io.read_input()?;
+            let path = path.unwrap_or(Path::new("<stdin>"));
+            if contents.starts_with(b"\0asm") {
+                bail!("`--name-locations` requires the text format as input");
+            }
+            let contents = std::str::from_utf8(&contents)
+                .with_context(|| format!("input file `{}` was not valid utf-8", path.display()))?;
+            parse_with_locations(contents, path)?
+        } else {
+            self.io.parse_input_wasm()?
+        };
+        if self.timings {
+            print_timings(&binary)?;
+        }
+        if let Some(count) = self.count {
+            print_throughput(&binary, count)?;
+        }
         self.io.output(wasm_tools::Output::Wasm {
             bytes: &binary,
             wat: self.wat,
@@ -25,3 +100,333 @@ impl Opts {
         Ok(())
     }
 }
+
+struct Timing {
+    section: String,
+    bytes: usize,
+    time: Duration,
+}
+
+fn print_timings(wasm: &[u8]) -> Result<()> {
+    let mut timings: Vec<Timing> = Vec::new();
+    let mut code: Option<usize> = None;
+    let mut payloads = wasmparser::Parser::new(0).parse_all(wasm);
+    loop {
+        let start = Instant::now();
+        let payload = match payloads.next() {
+            Some(payload) => payload?,
+            None => break,
+        };
+        decode(&payload)?;
+        let time = start.elapsed();
+
+        // Function bodies are accounted to the code section they're contained
+        // within.
+        if let Payload::CodeSectionEntry(_) = payload {
+            if let Some(i) = code {
+                timings[i].time += time;
+            }
+            continue;
+        }
+        let section = match &payload {
+            Payload::CodeSectionStart { .. } => {
+                code = Some(timings.len());
+                "code".to_string()
+            }
+            Payload::CustomSection(c) => format!("custom {:?}", c.name()),
+            Payload::UnknownSection { id, .. } => format!("unknown {}", id),
+            other => match section_name(other) {
+                Some(name) => name.to_string(),
+                None => continue,
+            },
+        };
+        let bytes = payload.as_section().map_or(0, |(_, range)| range.len());
+        timings.push(Timing {
+            section,
+            bytes,
+            time,
+        });
+    }
+
+    timings.sort_by_key(|t| std::cmp::Reverse(t.time));
+    let total = timings.iter().map(|t| t.time).sum::<Duration>();
+    eprintln!("{:30} {:>12} {:>14}", "section", "bytes", "time");
+    for timing in timings.iter() {
+        eprintln!(
+            "{:30} {:>12} {:>14}",
+            timing.section,
+            timing.bytes,
+            format!("{:.2?}", timing.time),
+        );
+    }
+    eprintln!(
+        "{:30} {:>12} {:>14}",
+        "total",
+        wasm.len(),
+        format!("{:.2?}", total)
+    );
+    Ok(())
+}
+
+fn print_throughput(wasm: &[u8], count: u32) -> Result<()> {
+    let mut times = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let start = Instant::now();
+        for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+            decode(&payload?)?;
+        }
+        times.push(start.elapsed());
+    }
+
+    times.sort();
+    let median = times[times.len() / 2];
+    let throughput = wasm.len() as f64 / median.as_secs_f64() / 1_000_000.0;
+    eprintln!(
+        "parsed {} bytes {} times: min {:.2?}, median {:.2?}, max {:.2?} ({:.2} MB/s)",
+        wasm.len(),
+        count,
+        times[0],
+        median,
+        times[times.len() - 1],
+        throughput,
+    );
+    Ok(())
+}
+
+/// Decodes every item within `payload`, since section readers otherwise
+/// lazily decode their contents.
+fn decode(payload: &Payload<'_>) -> Result<()> {
+    fn all<T, U>(reader: T) -> Result<()>
+    where
+        T: IntoIterator<Item = wasmparser::Result<U>>,
+    {
+        for item in reader {
+            item?;
+        }
+        Ok(())
+    }
+
+    match payload {
+        Payload::TypeSection(s) => all(s.clone()),
+        Payload::ImportSection(s) => all(s.clone()),
+        Payload::FunctionSection(s) => all(s.clone()),
+        Payload::TableSection(s) => all(s.clone()),
+        Payload::MemorySection(s) => all(s.clone()),
+        Payload::TagSection(s) => all(s.clone()),
+        Payload::GlobalSection(s) => all(s.clone()),
+        Payload::ExportSection(s) => all(s.clone()),
+        Payload::ElementSection(s) => all(s.clone()),
+        Payload::DataSection(s) => all(s.clone()),
+        Payload::CodeSectionEntry(body) => {
+            all(body.get_locals_reader()?)?;
+            let mut reader = body.get_operators_reader()?;
+            reader.allow_memarg64(true);
+            all(reader)
+        }
+        Payload::InstanceSection(s) => all(s.clone()),
+        Payload::CoreTypeSection(s) => all(s.clone()),
+        Payload::ComponentInstanceSection(s) => all(s.clone()),
+        Payload::ComponentAliasSection(s) => all(s.clone()),
+        Payload::ComponentTypeSection(s) => all(s.clone()),
+        Payload::ComponentCanonicalSection(s) => all(s.clone()),
+        Payload::ComponentImportSection(s) => all(s.clone()),
+        Payload::ComponentExportSection(s) => all(s.clone()),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the name of the section that `payload` represents, or `None` if
+/// it doesn't have a row of its own in the timings table.
+fn section_name(payload: &Payload<'_>) -> Option<&'static str> {
+    Some(match payload {
+        Payload::TypeSection(_) => "type",
+        Payload::ImportSection(_) => "import",
+        Payload::FunctionSection(_) => "function",
+        Payload::TableSection(_) => "table",
+        Payload::MemorySection(_) => "memory",
+        Payload::TagSection(_) => "tag",
+        Payload::GlobalSection(_) => "global",
+        Payload::ExportSection(_) => "export",
+        Payload::StartSection { .. } => "start",
+        Payload::ElementSection(_) => "element",
+        Payload::DataCountSection { .. } => "data count",
+        Payload::DataSection(_) => "data",
+        Payload::InstanceSection(_) => "core instance",
+        Payload::CoreTypeSection(_) => "core type",
+        Payload::ComponentInstanceSection(_) => "component instance",
+        Payload::ComponentAliasSection(_) => "component alias",
+        Payload::ComponentTypeSection(_) => "component type",
+        Payload::ComponentCanonicalSection(_) => "component canonical",
+        Payload::ComponentStartSection(_) => "component start",
+        Payload::ComponentImportSection(_) => "component import",
+        Payload::ComponentExportSection(_) => "component export",
+        _ => return None,
+    })
+}
+
+/// Assembles the text of the module `text`, appending a
+/// `function-locations` custom section with the line of each function.
+fn parse_with_locations(text: &str, path: &Path) -> Result<Vec<u8>> {
+    let set_location = |mut e: wast::Error| {
+        e.set_path(path);
+        e.set_text(text);
+        e
+    };
+    let buf = wast::parser::ParseBuffer::new(text).map_err(set_location)?;
+    let mut wat = wast::parser::parse::<wast::Wat>(&buf).map_err(set_location)?;
+    let fields = match &wat {
+        wast::Wat::Module(wast::core::Module {
+            kind: wast::core::ModuleKind::Text(fields),
+            ..
+        }) => fields,
+        wast::Wat::Module(_) => bail!("`--name-locations` requires a module in the text format"),
+        wast::Wat::Component(_) => bail!("`--name-locations` doesn't support components"),
+    };
+
+    // Imported functions come before those defined in the module in the
+    // index space, and each are in the order they appear in the text.
+    let mut imports = Vec::new();
+    let mut defined = Vec::new();
+    for field in fields {
+        match field {
+            wast::core::ModuleField::Import(import) => {
+                if let wast::core::ItemKind::Func(_) = import.item.kind {
+                    imports.push(import.span);
+                }
+            }
+            wast::core::ModuleField::Func(func) => match func.kind {
+                wast::core::FuncKind::Import(_) => imports.push(func.span),
+                wast::core::FuncKind::Inline { .. } => defined.push(func.span),
+            },
+            _ => {}
+        }
+    }
+    let mut locations = Vec::new();
+    ((imports.len() + defined.len()) as u32).encode(&mut locations);
+    for (index, span) in imports.into_iter().chain(defined).enumerate() {
+        let (line, _col) = span.linecol_in(text);
+        (index as u32).encode(&mut locations);
+        (line as u32 + 1).encode(&mut locations);
+    }
+
+    let mut binary = wat.encode().map_err(set_location)?;
+    binary.push(0);
+    CustomSection {
+        name: "function-locations",
+        data: &locations,
+    }
+    .encode(&mut binary);
+    Ok(binary)
+}
+
+/// The exports of a core instance created while linking a `.wast` script.
+struct LinkedInstance {
+    exports: HashSet<String>,
+}
+
+/// Assembles the modules of the `.wast` script `wast` into a component which
+/// instantiates each of them, resolving imports to the exports of registered
+/// modules.
+fn link_wast(wast: &str, path: &Path) -> Result<Vec<u8>> {
+    let set_location = |mut e: wast::Error| {
+        e.set_path(path);
+        e.set_text(wast);
+        e
+    };
+    let buf = wast::parser::ParseBuffer::new(wast).map_err(set_location)?;
+    let script = wast::parser::parse::<wast::Wast>(&buf).map_err(set_location)?;
+
+    let mut component = Component::new();
+    let mut instances: Vec<LinkedInstance> = Vec::new();
+    let mut ids = HashMap::new();
+    let mut registered = HashMap::new();
+    for directive in script.directives {
+        match directive {
+            wast::WastDirective::Wat(mut module) => {
+                let id = match &module {
+                    wast::QuoteWat::Wat(wast::Wat::Module(m)) => m.id,
+                    wast::QuoteWat::QuoteModule(..) => None,
+                    _ => bail!("components cannot be linked"),
+                };
+                let binary = module.encode().map_err(set_location)?;
+
+                let mut args = Vec::new();
+                let mut exports = HashSet::new();
+                for payload in wasmparser::Parser::new(0).parse_all(&binary) {
+                    match payload? {
+                        Payload::ImportSection(reader) => {
+                            for import in reader {
+                                let import = import?;
+                                let instance = match registered.get(import.module) {
+                                    Some(instance) => *instance,
+                                    None => bail!(
+                                        "unresolved import `{}::{}`: no module is registered as `{}`",
+                                        import.module,
+                                        import.name,
+                                        import.module,
+                                    ),
+                                };
+                                let linked: &LinkedInstance = &instances[instance as usize];
+                                if !linked.exports.contains(import.name) {
+                                    bail!(
+                                        "unresolved import `{}::{}`: the module registered as `{}` has no such export",
+                                        import.module,
+                                        import.name,
+                                        import.module,
+                                    );
+                                }
+                                if !args.iter().any(|(name, _)| name == import.module) {
+                                    args.push((import.module.to_string(), instance));
+                                }
+                            }
+                        }
+                        Payload::ExportSection(reader) => {
+                            for export in reader {
+                                exports.insert(export?.name.to_string());
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+
+                let index = instances.len() as u32;
+                component.section(&RawSection {
+                    id: ComponentSectionId::CoreModule.into(),
+                    data: &binary,
+                });
+                let mut section = InstanceSection::new();
+                section.instantiate(
+                    index,
+                    args.iter()
+                        .map(|(name, instance)| (name.as_str(), ModuleArg::Instance(*instance))),
+                );
+                component.section(&section);
+                instances.push(LinkedInstance { exports });
+                if let Some(id) = id {
+                    ids.insert(id.name(), index);
+                }
+            }
+            wast::WastDirective::Register { span, name, module } => {
+                let instance = match module {
+                    Some(id) => match ids.get(id.name()) {
+                        Some(instance) => *instance,
+                        None => {
+                            let msg = format!("unknown module `${}`", id.name());
+                            return Err(set_location(wast::Error::new(id.span(), msg)).into());
+                        }
+                    },
+                    None => match instances.len().checked_sub(1) {
+                        Some(instance) => instance as u32,
+                        None => {
+                            let msg = "no module to register".to_string();
+                            return Err(set_location(wast::Error::new(span, msg)).into());
+                        }
+                    },
+                };
+                registered.insert(name, instance);
+            }
+            _ => {}
+        }
+    }
+    Ok(component.finish())
+}
@@ -11,6 +11,37 @@ pub struct Opts {
     /// as comments for debugging.
     #[clap(short, long)]
     print_offsets: bool,
+
+    /// Inline function types which are used exactly once at their use site
+    /// and refer to other function types by name instead of by index.
+    #[clap(long)]
+    abbreviate_types: bool,
+
+    /// Print the exports of items defined in a module on the items
+    /// themselves, as in `(func (export "foo") ...)`, instead of as separate
+    /// `export` fields.
+    #[clap(long)]
+    inline_exports: bool,
+
+    /// The maximum width of printed lines, beyond which long lists such as
+    /// `br_table` targets and data segment strings are wrapped.
+    ///
+    /// By default lines are never wrapped.
+    #[clap(long, value_name = "WIDTH")]
+    wat_column_limit: Option<usize>,
+
+    /// The maximum depth of nested blocks within functions to print, beyond
+    /// which instructions are replaced with `(; ... ;)`.
+    ///
+    /// A depth of 0 only prints the instructions at the top level of each
+    /// function. The output is meant for skimming the structure of large
+    /// functions and isn't equivalent to the input. By default all
+    /// instructions are printed.
+    #[clap(long, value_name = "N")]
+    max_depth: Option<u32>,
+
+    #[clap(flatten)]
+    sections: wasm_tools::SectionFilter,
 }
 
 impl Opts {
@@ -18,6 +49,18 @@ impl Opts {
         let wasm = self.io.parse_input_wasm()?;
         let mut printer = wasmprinter::Printer::new();
         printer.print_offsets(self.print_offsets);
+        printer.abbreviate_types(self.abbreviate_types);
+        printer.inline_exports(self.inline_exports);
+        printer.max_line_width(self.wat_column_limit);
+        printer.max_depth(self.max_depth);
+        let sections = self.sections.clone();
+        printer.section_filter(move |payload| match payload {
+            // Function bodies are printed as part of the function section.
+            wasmparser::Payload::FunctionSection(_) => {
+                sections.includes("functions") || sections.includes("code")
+            }
+            _ => sections.matches(payload),
+        });
         let wat = printer.print(&wasm)?;
         self.io.output(wasm_tools::Output::Wat(&wat))?;
         Ok(())
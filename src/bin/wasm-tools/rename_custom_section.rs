@@ -0,0 +1,125 @@
+use anyhow::{anyhow, bail, Result};
+use std::collections::HashMap;
+use wasm_encoder::{CustomSection, Encode, RawSection};
+use wasmparser::{Parser, Payload};
+
+/// Rename custom sections of a WebAssembly module or component.
+///
+/// The contents and position of each renamed custom section are preserved,
+/// only its name is rewritten. Only the custom sections of the top-level
+/// module or component are renamed, nested modules and components are left
+/// as-is.
+///
+/// It's an error for a renamed section to end up with the same name as
+/// another custom section.
+///
+/// ## Example
+///
+/// $ wasm-tools rename-custom-section foo.wasm --rename producers=old-producers -o out.wasm
+#[derive(clap::Parser)]
+pub struct Opts {
+    #[clap(flatten)]
+    io: wasm_tools::InputOutput,
+
+    /// A rename to apply, in the form `OLD=NEW`.
+    ///
+    /// This option may be passed multiple times.
+    #[clap(long, value_name = "OLD=NEW", value_parser = parse_rename, required = true)]
+    rename: Vec<(String, String)>,
+
+    /// Output the text format of WebAssembly instead of the binary format.
+    #[clap(short = 't', long)]
+    wat: bool,
+}
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let input = self.io.parse_input_wasm()?;
+
+        let mut renames = HashMap::new();
+        for (old, new) in self.rename.iter() {
+            if renames.insert(old.as_str(), new.as_str()).is_some() {
+                bail!("custom section `{}` is renamed more than once", old);
+            }
+        }
+
+        let mut output = Vec::new();
+        // The final name of each custom section, and its original name if it
+        // was renamed.
+        let mut names = Vec::new();
+        let mut depth = 0;
+        for payload in Parser::new(0).parse_all(&input) {
+            let payload = payload?;
+            match &payload {
+                Payload::Version { range, .. } => {
+                    if depth == 0 {
+                        output.extend_from_slice(&input[range.clone()]);
+                    }
+                    depth += 1;
+                    continue;
+                }
+                Payload::End(_) => {
+                    depth -= 1;
+                    continue;
+                }
+                _ if depth > 1 => continue,
+                Payload::CustomSection(c) => {
+                    if let Some(new) = renames.get(c.name()) {
+                        names.push((*new, Some(c.name())));
+                        output.push(0);
+                        CustomSection {
+                            name: new,
+                            data: c.data(),
+                        }
+                        .encode(&mut output);
+                        continue;
+                    }
+                    names.push((c.name(), None));
+                }
+                _ => {}
+            }
+            if let Some((id, range)) = payload.as_section() {
+                output.push(id);
+                RawSection {
+                    id,
+                    data: &input[range],
+                }
+                .encode(&mut output);
+            }
+        }
+
+        for (old, _) in self.rename.iter() {
+            if !names.iter().any(|(_, from)| *from == Some(old.as_str())) {
+                log::warn!("no custom section named `{}` was found", old);
+            }
+        }
+        for (i, (name, from)) in names.iter().enumerate() {
+            if from.is_none() {
+                continue;
+            }
+            if names
+                .iter()
+                .enumerate()
+                .any(|(j, (n, _))| i != j && n == name)
+            {
+                bail!(
+                    "renaming would result in multiple custom sections named `{}`",
+                    name
+                );
+            }
+        }
+
+        self.io.output(wasm_tools::Output::Wasm {
+            bytes: &output,
+            wat: self.wat,
+        })?;
+        Ok(())
+    }
+}
+
+fn parse_rename(arg: &str) -> Result<(String, String)> {
+    let (old, new) = arg
+        .split_once('=')
+        .ok_or_else(|| anyhow!("rename `{}` is not of the form `OLD=NEW`", arg))?;
+    Ok((old.to_string(), new.to_string()))
+}
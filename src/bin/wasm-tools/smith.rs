@@ -1,10 +1,12 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use arbitrary::Arbitrary;
 use clap::Parser;
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::io::{stdin, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::str::FromStr;
 use wasm_smith::{InstructionKind, InstructionKinds, MaybeInvalidModule, Module};
 
 /// A WebAssembly test case generator.
@@ -25,6 +27,15 @@ use wasm_smith::{InstructionKind, InstructionKinds, MaybeInvalidModule, Module};
 ///
 /// $ head -c 100 /dev/urandom | wasm-smith -o test.wasm
 ///
+/// Generate a corpus of 100 modules into the `corpus` directory, named
+/// `0.wasm` through `99.wasm`:
+///
+/// $ wasm-smith --seed 0 --count 100 -o corpus
+///
+/// Generate the same corpus across 8 threads:
+///
+/// $ wasm-smith --seed 0 --count 100 --parallel 8 -o corpus
+///
 /// ## Exit Codes
 ///
 /// * 0: Success.
@@ -38,8 +49,42 @@ pub struct Opts {
     /// The arbitrary input seed.
     ///
     /// `stdin` is used if this argument is not supplied.
+    #[clap(conflicts_with = "seed")]
     input: Option<PathBuf>,
 
+    /// Generate the input seed from this number instead of reading it.
+    ///
+    /// The same number always produces the same input seed, and so the same
+    /// module for a given configuration.
+    #[clap(long)]
+    seed: Option<u64>,
+
+    /// Generate a corpus of this many modules, one for each seed starting at
+    /// `--seed`, into the directory passed with `-o`.
+    ///
+    /// Each module is named after the seed it was generated from, such as
+    /// `3.wasm`. Seeds for which no module could be generated are skipped.
+    #[clap(long, requires = "output")]
+    count: Option<u64>,
+
+    /// The number of threads to generate a corpus with `--count` across.
+    ///
+    /// Each module only depends on the seed it's generated from, so the
+    /// corpus is the same regardless of the number of threads.
+    #[clap(
+        long,
+        requires = "count",
+        default_value_t = 1,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+    )]
+    parallel: u64,
+
+    /// The number of bytes of input seed produced for each number with
+    /// `--seed`.
+    #[clap(long, default_value_t = 4096, value_name = "BYTES")]
+    seed_len: usize,
+
     #[clap(flatten)]
     output: wasm_tools::OutputArg,
 
@@ -105,6 +150,8 @@ struct Config {
     max_exports: Option<usize>,
     #[clap(long = "export-everything")]
     export_everything: Option<bool>,
+    #[clap(long = "deterministic-names")]
+    deterministic_names: Option<bool>,
     #[clap(long = "min-element-segments")]
     min_element_segments: Option<usize>,
     #[clap(long = "max-element-segments")]
@@ -183,6 +230,18 @@ struct Config {
     /// `--allowed-instructions numeric,control,parametric`
     #[clap(long = "allowed-instructions", use_value_delimiter = true)]
     allowed_instructions: Option<Vec<InstructionKind>>,
+    /// Bias the choice of instructions toward or away from some kinds.
+    ///
+    /// Each bias is a kind of instruction, as with `--allowed-instructions`,
+    /// and how much more likely instructions of that kind are to be chosen,
+    /// e.g., `--instruction-bias vector=10,memory=0.5`. Kinds which aren't
+    /// listed have a bias of 1.
+    #[clap(
+        long = "instruction-bias",
+        use_value_delimiter = true,
+        value_name = "KIND=BIAS"
+    )]
+    instruction_bias: Option<Vec<InstructionBias>>,
     #[clap(long = "threads")]
     #[serde(rename = "threads")]
     threads_enabled: Option<bool>,
@@ -190,11 +249,17 @@ struct Config {
 
 impl Opts {
     pub fn run(&self) -> Result<()> {
-        let seed = match &self.input {
-            Some(f) => {
+        let config = self.config()?;
+        if let Some(count) = self.count {
+            return self.generate_corpus(&config, count);
+        }
+
+        let seed = match (self.seed, &self.input) {
+            (Some(seed), _) => wasm_tools::seed_bytes(seed, self.seed_len),
+            (None, Some(f)) => {
                 std::fs::read(f).with_context(|| format!("failed to read '{}'", f.display()))?
             }
-            None => {
+            (None, None) => {
                 let mut seed = Vec::new();
                 stdin()
                     .read_to_end(&mut seed)
@@ -203,39 +268,10 @@ impl Opts {
             }
         };
 
-        let mut u = arbitrary::Unstructured::new(&seed);
-        let wasm_bytes = if self.maybe_invalid {
-            MaybeInvalidModule::arbitrary(&mut u)
-                .unwrap_or_else(|e| {
-                    eprintln!("error: failed to generate module: {}", e);
-                    process::exit(2);
-                })
-                .to_bytes()
-        } else {
-            let json = match &self.config {
-                Some(path) => {
-                    let json = std::fs::read_to_string(&path).with_context(|| {
-                        format!("failed to read json config: {}", path.display())
-                    })?;
-                    serde_json::from_str(&json).with_context(|| {
-                        format!("failed to decode json config: {}", path.display())
-                    })?
-                }
-                None => Config::default(),
-            };
-            let config = CliAndJsonConfig {
-                json,
-                cli: self.module_config.clone(),
-            };
-            let mut module = Module::new(config, &mut u).unwrap_or_else(|e| {
-                eprintln!("error: failed to generate module: {}", e);
-                process::exit(2);
-            });
-            if self.ensure_termination {
-                module.ensure_termination(self.fuel.unwrap_or(100));
-            }
-            module.to_bytes()
-        };
+        let wasm_bytes = self.generate(&config, &seed).unwrap_or_else(|e| {
+            eprintln!("error: failed to generate module: {}", e);
+            process::exit(2);
+        });
 
         self.output.output(wasm_tools::Output::Wasm {
             bytes: &wasm_bytes,
@@ -243,6 +279,127 @@ impl Opts {
         })?;
         Ok(())
     }
+
+    fn config(&self) -> Result<CliAndJsonConfig> {
+        let json = match &self.config {
+            Some(path) => {
+                let json = std::fs::read_to_string(path)
+                    .with_context(|| format!("failed to read json config: {}", path.display()))?;
+                serde_json::from_str(&json)
+                    .with_context(|| format!("failed to decode json config: {}", path.display()))?
+            }
+            None => Config::default(),
+        };
+        Ok(CliAndJsonConfig {
+            json,
+            cli: self.module_config.clone(),
+        })
+    }
+
+    fn generate(&self, config: &CliAndJsonConfig, seed: &[u8]) -> arbitrary::Result<Vec<u8>> {
+        let mut u = arbitrary::Unstructured::new(seed);
+        if self.maybe_invalid {
+            return Ok(MaybeInvalidModule::arbitrary(&mut u)?.to_bytes());
+        }
+        let mut module = Module::new(config.clone(), &mut u)?;
+        if self.ensure_termination {
+            module.ensure_termination(self.fuel.unwrap_or(100));
+        }
+        Ok(module.to_bytes())
+    }
+
+    fn generate_corpus(&self, config: &CliAndJsonConfig, count: u64) -> Result<()> {
+        let dir = self.output.output_path().unwrap();
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create directory '{}'", dir.display()))?;
+        let start = self.seed.unwrap_or(0);
+        let end = match start.checked_add(count) {
+            Some(end) => end,
+            None => bail!(
+                "seeds overflow starting from {} with a count of {}",
+                start,
+                count
+            ),
+        };
+        // Each thread generates every `--parallel`th seed, starting from its
+        // own offset from `start`.
+        let threads = self.parallel;
+        std::thread::scope(|s| {
+            let workers = (0..threads.min(count))
+                .map(|i| {
+                    s.spawn(move || {
+                        let mut seed = start + i;
+                        while seed < end {
+                            self.generate_corpus_entry(config, dir, seed)?;
+                            seed = match seed.checked_add(threads) {
+                                Some(seed) => seed,
+                                None => break,
+                            };
+                        }
+                        Ok(())
+                    })
+                })
+                .collect::<Vec<_>>();
+            workers
+                .into_iter()
+                .try_for_each(|worker| worker.join().unwrap())
+        })
+    }
+
+    /// Generates the module for `seed` into the corpus directory `dir`,
+    /// skipping it if it can't be generated.
+    fn generate_corpus_entry(
+        &self,
+        config: &CliAndJsonConfig,
+        dir: &Path,
+        seed: u64,
+    ) -> Result<()> {
+        let wasm_bytes = match self.generate(config, &wasm_tools::seed_bytes(seed, self.seed_len)) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::warn!("failed to generate module for seed {}: {}", seed, e);
+                return Ok(());
+            }
+        };
+        let (contents, extension) = if self.wat {
+            (wasmprinter::print_bytes(&wasm_bytes)?.into_bytes(), "wat")
+        } else {
+            (wasm_bytes, "wasm")
+        };
+        let path = dir.join(format!("{}.{}", seed, extension));
+        std::fs::write(&path, contents)
+            .with_context(|| format!("failed to write '{}'", path.display()))?;
+        Ok(())
+    }
+}
+
+/// A bias of `--instruction-bias`, written as `KIND=BIAS`.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(try_from = "String")]
+struct InstructionBias(InstructionKind, f32);
+
+impl FromStr for InstructionBias {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, bias) = match s.split_once('=') {
+            Some(pair) => pair,
+            None => bail!("expected `KIND=BIAS`, found `{}`", s),
+        };
+        let kind = kind.parse().map_err(anyhow::Error::msg)?;
+        let bias = bias
+            .parse()
+            .with_context(|| format!("invalid bias `{}`", bias))?;
+        Ok(InstructionBias(kind, bias))
+    }
+}
+
+impl TryFrom<String> for InstructionBias {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
 }
 
 macro_rules! fields {
@@ -276,6 +433,7 @@ impl wasm_smith::Config for CliAndJsonConfig {
         (min_exports, usize, 0),
         (max_exports, usize, 100),
         (export_everything, bool, false),
+        (deterministic_names, bool, false),
         (min_element_segments, usize, 0),
         (max_element_segments, usize, 100),
         (min_data_segments, usize, 0),
@@ -328,6 +486,16 @@ impl wasm_smith::Config for CliAndJsonConfig {
         }
     }
 
+    fn instruction_bias(&self) -> HashMap<InstructionKind, f32> {
+        self.cli
+            .instruction_bias
+            .as_ref()
+            .or(self.json.instruction_bias.as_ref())
+            .map_or_else(HashMap::new, |biases| {
+                biases.iter().map(|b| (b.0, b.1)).collect()
+            })
+    }
+
     fn available_imports(&self) -> Option<Cow<'static, [u8]>> {
         let file = self
             .cli
@@ -6,7 +6,12 @@ use wasmparser::{Encoding, Parser, Payload::*};
 ///
 /// This command will by default strip all custom sections such as DWARF
 /// debugging information from a wasm file. It will not strip the `name` section
-/// by default unless the `--all` flag is passed.
+/// by default unless the `--all` flag is passed. The `--debug` flag can be used
+/// to strip only DWARF debugging information.
+///
+/// All sections which aren't stripped are copied to the output as-is and in
+/// the same order as the input, so custom sections which remain keep their
+/// position relative to each other and to the known sections around them.
 #[derive(clap::Parser)]
 pub struct Opts {
     #[clap(flatten)]
@@ -20,6 +25,11 @@ pub struct Opts {
     #[clap(long, short, value_name = "REGEX")]
     delete: Vec<String>,
 
+    /// Remove only DWARF debugging information, the `.debug_*` custom
+    /// sections, in addition to any sections matching `--delete`.
+    #[clap(long)]
+    debug: bool,
+
     /// Output the text format of WebAssembly instead of the binary format.
     #[clap(short = 't', long)]
     wat: bool,
@@ -36,9 +46,10 @@ impl Opts {
                 return true;
             }
 
-            // If any section was called out by name only delete those sections.
-            if !to_delete.is_empty() {
-                return to_delete.is_match(name);
+            // If any section was called out by name, or debug information was
+            // requested to be stripped, only delete those sections.
+            if self.debug || !to_delete.is_empty() {
+                return (self.debug && name.starts_with(".debug_")) || to_delete.is_match(name);
             }
 
             // Finally default strip everything but the `name` section.
@@ -47,6 +58,8 @@ impl Opts {
 
         let mut module = wasm_encoder::Module::new();
 
+        // Note that sections are copied in the order they're parsed, which
+        // preserves the position of custom sections which aren't stripped.
         for payload in Parser::new(0).parse_all(&input) {
             let payload = payload?;
             match &payload {
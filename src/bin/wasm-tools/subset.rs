@@ -0,0 +1,586 @@
+use anyhow::{bail, Context, Result};
+use std::collections::{HashMap, HashSet};
+use wasm_encoder::{
+    CodeSection, DataSection, ElementMode, ElementSection, ElementSegment, Elements,
+    FunctionSection, GlobalSection, ImportSection, MemorySection, Module, RawSection, SectionId,
+    TableSection, TagSection, TypeSection,
+};
+use wasm_tools::translate::{self, Item, Translator};
+use wasmparser::{
+    DataKind, ElementKind, ExternalKind, FunctionBody, ImportCounts, Operator, Parser, Payload,
+    TypeRef, Validator,
+};
+
+/// Extract the part of a module reachable from some of its exports.
+///
+/// Only the exports named with `--export` are kept, along with every item
+/// they transitively refer to through instructions, initializers, and
+/// segments. All other items, including unused imports, are removed and the
+/// indices of the remaining items are renumbered. This is useful for creating
+/// a minimal reproduction of a bug in one export of a large module.
+///
+/// The start function, if any, is always kept since it runs when the module
+/// is instantiated. Active element and data segments are kept if the table or
+/// memory they initialize is kept.
+///
+/// ## Example
+///
+/// $ wasm-tools subset foo.wasm --export run -o min.wasm
+#[derive(clap::Parser)]
+pub struct Opts {
+    #[clap(flatten)]
+    io: wasm_tools::InputOutput,
+
+    /// The name of an export to keep.
+    ///
+    /// This option may be passed multiple times.
+    #[clap(long = "export", value_name = "NAME", required = true)]
+    exports: Vec<String>,
+
+    #[clap(flatten)]
+    debug: wasm_tools::DebugInfo,
+
+    /// Output the text format of WebAssembly instead of the binary format.
+    #[clap(short = 't', long)]
+    wat: bool,
+}
+
+const ITEMS: [Item; 8] = [
+    Item::Function,
+    Item::Table,
+    Item::Memory,
+    Item::Tag,
+    Item::Global,
+    Item::Type,
+    Item::Data,
+    Item::Element,
+];
+
+impl Opts {
+    pub fn run(&self) -> Result<()> {
+        let input = self.io.parse_input_wasm()?;
+        Validator::new_with_features(wasm_tools::all_core_features())
+            .validate_all(&input)
+            .context("input failed to validate")?;
+        let items = Items::parse(&input)?;
+
+        let mut roots = Vec::new();
+        for name in self.exports.iter() {
+            let export = match items.exports.iter().find(|e| e.name == name) {
+                Some(export) => export,
+                None => bail!("no export named `{}` in the module", name),
+            };
+            let item = match export.kind {
+                ExternalKind::Func => Item::Function,
+                ExternalKind::Table => Item::Table,
+                ExternalKind::Memory => Item::Memory,
+                ExternalKind::Global => Item::Global,
+                ExternalKind::Tag => Item::Tag,
+            };
+            roots.push((item, export.index));
+        }
+        roots.extend(items.start.map(|f| (Item::Function, f)));
+        let live = items.live(roots)?;
+
+        let mut subset = Subset {
+            exports: self.exports.iter().cloned().collect(),
+            undeclared: items.undeclared(&live, &self.exports)?,
+            elements: items.count(Item::Element),
+            imported_funcs: items.imported_funcs,
+            ..Subset::default()
+        };
+        for item in ITEMS {
+            let map = subset.old_to_new.entry(item).or_default();
+            for idx in (0..items.count(item)).filter(|i| live.contains(&(item, *i))) {
+                let new = map.len() as u32;
+                map.insert(idx, new);
+            }
+        }
+
+        let mut output = translate::module(&mut subset, &input)?;
+        if subset.elements == 0 && !subset.undeclared.is_empty() {
+            output = subset.add_element_section(&output)?;
+        }
+        Validator::new_with_features(wasm_tools::all_core_features())
+            .validate_all(&output)
+            .context("output failed to validate")?;
+        let output = self.debug.check(&input, output)?;
+        eprintln!(
+            "kept {} of {} functions ({} bytes saved)",
+            subset.old_to_new[&Item::Function].len(),
+            items.count(Item::Function),
+            input.len() as i64 - output.len() as i64,
+        );
+
+        self.io.output(wasm_tools::Output::Wasm {
+            bytes: &output,
+            wat: self.wat,
+        })?;
+        Ok(())
+    }
+}
+
+/// The items of the input module which may refer to other items.
+#[derive(Default)]
+struct Items<'a> {
+    /// The type of each function, and its body if it's defined in the module.
+    funcs: Vec<(u32, Option<FunctionBody<'a>>)>,
+    imported_funcs: u32,
+    tags: Vec<u32>,
+    globals: Vec<Option<wasmparser::Global<'a>>>,
+    elements: Vec<wasmparser::Element<'a>>,
+    data: Vec<wasmparser::Data<'a>>,
+    exports: Vec<wasmparser::Export<'a>>,
+    start: Option<u32>,
+    types: u32,
+    tables: u32,
+    memories: u32,
+}
+
+impl<'a> Items<'a> {
+    fn parse(wasm: &'a [u8]) -> Result<Items<'a>> {
+        let mut items = Items::default();
+        let mut bodies = 0;
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::Version { encoding, .. } if encoding != wasmparser::Encoding::Module => {
+                    bail!("subsets can only be extracted from core wasm modules")
+                }
+                Payload::TypeSection(reader) => items.types += reader.get_count(),
+                Payload::ImportSection(reader) => {
+                    let counts = ImportCounts::from_reader(reader.clone())?;
+                    items.imported_funcs = counts.funcs;
+                    items.tables += counts.tables;
+                    items.memories += counts.memories;
+                    for import in reader {
+                        match import?.ty {
+                            TypeRef::Func(ty) => items.funcs.push((ty, None)),
+                            TypeRef::Global(_) => items.globals.push(None),
+                            TypeRef::Tag(ty) => items.tags.push(ty.func_type_idx),
+                            TypeRef::Table(_) | TypeRef::Memory(_) => {}
+                        }
+                    }
+                }
+                Payload::FunctionSection(reader) => {
+                    for ty in reader {
+                        items.funcs.push((ty?, None));
+                    }
+                }
+                Payload::TableSection(reader) => items.tables += reader.get_count(),
+                Payload::MemorySection(reader) => items.memories += reader.get_count(),
+                Payload::TagSection(reader) => {
+                    for ty in reader {
+                        items.tags.push(ty?.func_type_idx);
+                    }
+                }
+                Payload::GlobalSection(reader) => {
+                    for global in reader {
+                        items.globals.push(Some(global?));
+                    }
+                }
+                Payload::ExportSection(reader) => {
+                    for export in reader {
+                        items.exports.push(export?);
+                    }
+                }
+                Payload::StartSection { func, .. } => items.start = Some(func),
+                Payload::ElementSection(reader) => {
+                    for element in reader {
+                        items.elements.push(element?);
+                    }
+                }
+                Payload::DataSection(reader) => {
+                    for data in reader {
+                        items.data.push(data?);
+                    }
+                }
+                Payload::CodeSectionEntry(body) => {
+                    items.funcs[(items.imported_funcs + bodies) as usize].1 = Some(body);
+                    bodies += 1;
+                }
+                _ => {}
+            }
+        }
+        Ok(items)
+    }
+
+    fn count(&self, item: Item) -> u32 {
+        let len = match item {
+            Item::Function => self.funcs.len(),
+            Item::Tag => self.tags.len(),
+            Item::Global => self.globals.len(),
+            Item::Element => self.elements.len(),
+            Item::Data => self.data.len(),
+            Item::Type => return self.types,
+            Item::Table => return self.tables,
+            Item::Memory => return self.memories,
+        };
+        len as u32
+    }
+
+    /// Returns every item transitively referred to by `roots`.
+    fn live(&self, roots: Vec<(Item, u32)>) -> Result<HashSet<(Item, u32)>> {
+        let mut live = HashSet::new();
+        let mut worklist = roots;
+        while let Some((item, idx)) = worklist.pop() {
+            if !live.insert((item, idx)) {
+                continue;
+            }
+            let mut refs = Refs::default();
+            let i = idx as usize;
+            match item {
+                Item::Function => {
+                    let (ty, body) = self.funcs[i];
+                    refs.0.push((Item::Type, ty));
+                    if let Some(body) = body {
+                        translate::code(&mut refs, body, &mut CodeSection::new())?;
+                    }
+                }
+                Item::Tag => refs.0.push((Item::Type, self.tags[i])),
+                Item::Global => {
+                    if let Some(global) = self.globals[i] {
+                        translate::global(&mut refs, global, &mut GlobalSection::new())?;
+                    }
+                }
+                Item::Element => {
+                    let element = self.elements[i].clone();
+                    translate::element(&mut refs, element, &mut ElementSection::new())?;
+                }
+                Item::Data => {
+                    let data = self.data[i].clone();
+                    translate::data(&mut refs, data, &mut DataSection::new())?;
+                }
+                // Tables and memories keep the active segments which
+                // initialize them.
+                Item::Table => {
+                    for (j, element) in self.elements.iter().enumerate() {
+                        if let ElementKind::Active { table_index, .. } = element.kind {
+                            if table_index == idx {
+                                refs.0.push((Item::Element, j as u32));
+                            }
+                        }
+                    }
+                }
+                Item::Memory => {
+                    for (j, data) in self.data.iter().enumerate() {
+                        if let DataKind::Active { memory_index, .. } = data.kind {
+                            if memory_index == idx {
+                                refs.0.push((Item::Data, j as u32));
+                            }
+                        }
+                    }
+                }
+                Item::Type => {}
+            }
+            worklist.extend(refs.0);
+        }
+        Ok(live)
+    }
+
+    /// Returns the functions referenced with `ref.func` by the `live` code
+    /// which aren't declared by any of the items which will be kept.
+    fn undeclared(&self, live: &HashSet<(Item, u32)>, exports: &[String]) -> Result<Vec<u32>> {
+        let mut declared = HashSet::new();
+        let mut refs = Refs::default();
+        for (i, element) in self.elements.iter().enumerate() {
+            if live.contains(&(Item::Element, i as u32)) {
+                translate::element(&mut refs, element.clone(), &mut ElementSection::new())?;
+            }
+        }
+        for (i, global) in self.globals.iter().enumerate() {
+            if let Some(global) = global {
+                if live.contains(&(Item::Global, i as u32)) {
+                    translate::global(&mut refs, *global, &mut GlobalSection::new())?;
+                }
+            }
+        }
+        for export in self.exports.iter() {
+            if export.kind == ExternalKind::Func && exports.iter().any(|e| e == export.name) {
+                refs.0.push((Item::Function, export.index));
+            }
+        }
+        for (item, idx) in refs.0 {
+            if item == Item::Function {
+                declared.insert(idx);
+            }
+        }
+
+        let mut undeclared = Vec::new();
+        for (i, (_, body)) in self.funcs.iter().enumerate() {
+            let body = match body {
+                Some(body) if live.contains(&(Item::Function, i as u32)) => body,
+                _ => continue,
+            };
+            let mut reader = body.get_operators_reader()?;
+            reader.allow_memarg64(true);
+            for op in reader {
+                if let Operator::RefFunc { function_index } = op? {
+                    if declared.insert(function_index) {
+                        undeclared.push(function_index);
+                    }
+                }
+            }
+        }
+        undeclared.sort_unstable();
+        Ok(undeclared)
+    }
+}
+
+/// A translator which records the items referred to by whatever it
+/// translates.
+#[derive(Default)]
+struct Refs(Vec<(Item, u32)>);
+
+impl Translator for Refs {
+    fn as_obj(&mut self) -> &mut dyn Translator {
+        self
+    }
+
+    fn remap(&mut self, item: Item, idx: u32) -> translate::Result<u32> {
+        self.0.push((item, idx));
+        Ok(idx)
+    }
+}
+
+#[derive(Default)]
+struct Subset {
+    old_to_new: HashMap<Item, HashMap<u32, u32>>,
+    /// The number of items of each kind translated so far.
+    seen: HashMap<Item, u32>,
+    exports: HashSet<String>,
+    /// Functions referenced with `ref.func` which need to be declared by a
+    /// new element segment.
+    undeclared: Vec<u32>,
+    elements: u32,
+    imported_funcs: u32,
+    bodies: u32,
+}
+
+impl Subset {
+    /// Advances past the next item of kind `item` in the input, returning
+    /// whether it's kept.
+    fn next(&mut self, item: Item) -> bool {
+        let seen = self.seen.entry(item).or_insert(0);
+        let idx = *seen;
+        *seen += 1;
+        self.old_to_new[&item].contains_key(&idx)
+    }
+
+    /// Appends a segment declaring the functions in `undeclared` to `s`.
+    fn declare_functions(&mut self, s: &mut ElementSection) -> translate::Result<()> {
+        let functions = self
+            .undeclared
+            .clone()
+            .into_iter()
+            .map(|f| self.remap(Item::Function, f))
+            .collect::<translate::Result<Vec<_>>>()?;
+        s.segment(ElementSegment {
+            mode: ElementMode::Declared,
+            element_type: wasm_encoder::ValType::FuncRef,
+            elements: Elements::Functions(&functions),
+        });
+        Ok(())
+    }
+
+    /// Adds an element section declaring the functions in `undeclared` to
+    /// the translated module `wasm`, which has no element section.
+    fn add_element_section(&mut self, wasm: &[u8]) -> Result<Vec<u8>> {
+        let mut elements = ElementSection::new();
+        self.declare_functions(&mut elements)?;
+        let mut module = Module::new();
+        let mut added = false;
+        for payload in Parser::new(0).parse_all(wasm) {
+            let (id, range) = match payload?.as_section() {
+                Some(section) => section,
+                None => continue,
+            };
+            // The element section precedes the data count, code, and data
+            // sections.
+            if !added
+                && [SectionId::DataCount, SectionId::Code, SectionId::Data]
+                    .iter()
+                    .any(|s| *s as u8 == id)
+            {
+                module.section(&elements);
+                added = true;
+            }
+            module.section(&RawSection {
+                id,
+                data: &wasm[range],
+            });
+        }
+        if !added {
+            module.section(&elements);
+        }
+        Ok(module.finish())
+    }
+}
+
+impl Translator for Subset {
+    fn as_obj(&mut self) -> &mut dyn Translator {
+        self
+    }
+
+    fn translate_type_def(
+        &mut self,
+        ty: wasmparser::Type,
+        s: &mut TypeSection,
+    ) -> translate::Result<()> {
+        if self.next(Item::Type) {
+            translate::type_def(self, ty, s)?;
+        }
+        Ok(())
+    }
+
+    fn translate_import(
+        &mut self,
+        import: wasmparser::Import<'_>,
+        s: &mut ImportSection,
+    ) -> translate::Result<()> {
+        let item = match import.ty {
+            TypeRef::Func(_) => Item::Function,
+            TypeRef::Table(_) => Item::Table,
+            TypeRef::Memory(_) => Item::Memory,
+            TypeRef::Global(_) => Item::Global,
+            TypeRef::Tag(_) => Item::Tag,
+        };
+        if self.next(item) {
+            translate::import_def(self, import, s)?;
+        }
+        Ok(())
+    }
+
+    fn translate_function(&mut self, ty: u32, s: &mut FunctionSection) -> translate::Result<()> {
+        if self.next(Item::Function) {
+            translate::function(self, ty, s)?;
+        }
+        Ok(())
+    }
+
+    fn translate_table(
+        &mut self,
+        ty: wasmparser::TableType,
+        s: &mut TableSection,
+    ) -> translate::Result<()> {
+        if self.next(Item::Table) {
+            translate::table(self, ty, s)?;
+        }
+        Ok(())
+    }
+
+    fn translate_memory(
+        &mut self,
+        ty: wasmparser::MemoryType,
+        s: &mut MemorySection,
+    ) -> translate::Result<()> {
+        if self.next(Item::Memory) {
+            translate::memory(self, ty, s)?;
+        }
+        Ok(())
+    }
+
+    fn translate_tag(
+        &mut self,
+        ty: wasmparser::TagType,
+        s: &mut TagSection,
+    ) -> translate::Result<()> {
+        if self.next(Item::Tag) {
+            translate::tag(self, ty, s)?;
+        }
+        Ok(())
+    }
+
+    fn translate_global(
+        &mut self,
+        g: wasmparser::Global,
+        s: &mut GlobalSection,
+    ) -> translate::Result<()> {
+        if self.next(Item::Global) {
+            translate::global(self, g, s)?;
+        }
+        Ok(())
+    }
+
+    fn translate_export(
+        &mut self,
+        e: &wasmparser::Export<'_>,
+        s: &mut wasm_encoder::ExportSection,
+    ) -> translate::Result<()> {
+        if self.exports.contains(e.name) {
+            translate::export(self, e, s)?;
+        }
+        Ok(())
+    }
+
+    fn translate_element(
+        &mut self,
+        e: wasmparser::Element<'_>,
+        s: &mut ElementSection,
+    ) -> translate::Result<()> {
+        if self.next(Item::Element) {
+            translate::element(self, e, s)?;
+        }
+
+        // Functions referenced with `ref.func` whose declarations were
+        // removed are declared by a new segment at the end of the section,
+        // after all the existing segments so their indices are unchanged.
+        if self.seen[&Item::Element] == self.elements && !self.undeclared.is_empty() {
+            self.declare_functions(s)?;
+        }
+        Ok(())
+    }
+
+    fn translate_data(
+        &mut self,
+        d: wasmparser::Data<'_>,
+        s: &mut DataSection,
+    ) -> translate::Result<()> {
+        if self.next(Item::Data) {
+            translate::data(self, d, s)?;
+        }
+        Ok(())
+    }
+
+    fn translate_data_count(&mut self, _count: u32) -> translate::Result<u32> {
+        Ok(self.old_to_new[&Item::Data].len() as u32)
+    }
+
+    fn translate_code(
+        &mut self,
+        body: FunctionBody<'_>,
+        s: &mut CodeSection,
+    ) -> translate::Result<()> {
+        let func = self.imported_funcs + self.bodies;
+        self.bodies += 1;
+        if self.old_to_new[&Item::Function].contains_key(&func) {
+            translate::code(self, body, s)?;
+        }
+        Ok(())
+    }
+
+    fn translate_custom_section(
+        &mut self,
+        section: &wasmparser::CustomSectionReader<'_>,
+        module: &mut Module,
+    ) -> translate::Result<()> {
+        if section.name() != "name" {
+            return translate::custom_section(self, section, module);
+        }
+        let reader = wasmparser::NameSectionReader::new(section.data(), section.data_offset())?;
+        let old_to_new = &self.old_to_new;
+        module.section(&translate::remap_names_with(reader, &mut |item, idx| {
+            Ok(old_to_new[&item].get(&idx).copied())
+        })?);
+        Ok(())
+    }
+
+    fn remap(&mut self, item: Item, idx: u32) -> translate::Result<u32> {
+        match self.old_to_new[&item].get(&idx) {
+            Some(idx) => Ok(*idx),
+            None => Err(translate::Error::other(format!(
+                "{:?} {} was removed",
+                item, idx
+            ))),
+        }
+    }
+}
@@ -1,8 +1,19 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
 use std::mem;
-use std::time::Instant;
-use wasmparser::{FuncValidatorAllocations, Parser, ValidPayload, Validator, WasmFeatures};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use wasmparser::{
+    BinaryReader, BinaryReaderError, BlockType, DataKind, ElementItem, ElementKind, Encoding,
+    ExternalKind, FuncType, FuncValidatorAllocations, GlobalType, ImportCounts, MemoryType,
+    Operator, OperatorsReader, Parser, Payload, TableType, Type, TypeRef, ValType, ValidPayload,
+    Validator, ValidatorLimits, WasmFeatures,
+};
+use ValType::{I32, I64};
 
 /// Validate a WebAssembly binary
 ///
@@ -22,7 +33,146 @@ use wasmparser::{FuncValidatorAllocations, Parser, ValidPayload, Validator, Wasm
 ///
 /// # Validate `mvp.wasm` without any Wasm feature proposals enabled.
 /// $ wasm-tools validate --features=-all mvp.wasm
+///
+/// # Skip validating `foo.wasm` if it previously validated successfully.
+/// $ wasm-tools validate --cache-dir .cache foo.wasm
+///
+/// # Reject `untrusted.wasm` if it defines more than 10000 functions.
+/// $ wasm-tools validate --limit functions=10000 untrusted.wasm
+///
+/// # Reject `sandboxed.wasm` if any function grows memory or adds floats.
+/// $ wasm-tools validate --forbid memory.grow,f32.add,f64.add sandboxed.wasm
+///
+/// # Give up on validating `untrusted.wasm` if it takes over 5 seconds.
+/// $ wasm-tools validate --timeout 5s untrusted.wasm
+///
+/// # Validate `app.wasm` and check its imports against WASI preview1.
+/// $ wasm-tools validate --wasi app.wasm
+///
+/// # Check that `plugin.wasm` only imports what the host provides.
+/// $ wasm-tools validate --allowed-imports allowed.json plugin.wasm
+///
+/// # Check that the branch hints of `optimized.wasm` aren't stale.
+/// $ wasm-tools validate --branch-hints optimized.wasm
+///
+/// # Check that `plugin.wasm` behaves the same in every engine.
+/// $ wasm-tools validate --deterministic plugin.wasm
+///
+/// # Validate `foo.wasm` and print an overview of what it contains.
+/// $ wasm-tools validate --summary foo.wasm
+///
+/// # Validate `huge.wasm` without reading it all into memory.
+/// $ wasm-tools validate --mmap huge.wasm
+///
+/// # Print whether `foo.wasm` is valid as JSON for a CI pipeline.
+/// $ wasm-tools validate --json foo.wasm
+///
+/// # Validate modules sent over stdin until it's closed.
+/// $ wasm-tools validate --serve
+/// ```
+///
+/// # JSON output
+///
+/// With `--json` the result is printed to stdout as a JSON object instead of
+/// errors being printed to stderr:
+///
+/// ```json
+/// {
+///   "valid": false,
+///   "error": {
+///     "message": "func 0 failed to validate: type mismatch: ...",
+///     "offset": 37
+///   },
+///   "features": ["mutable-global", "saturating-float-to-int", ...]
+/// }
+/// ```
+///
+/// The `error` key is only present if the input is invalid, and its `offset`
+/// is `null` if the error isn't at a particular offset in the binary. The
+/// enabled features are listed in `features`. The process still exits with a
+/// nonzero status if the input is invalid.
+///
+/// # Summary
+///
+/// With `--summary` an overview of a valid module is printed to stderr:
+///
+/// ```text
+/// functions: 3
+/// imports: 1
+/// exports: 2
+/// memories: 1
+/// globals: 0
+/// tables: 0
+/// code size: 42 bytes
+/// start function: none
+/// features used: mutable-global
 /// ```
+///
+/// Imported functions, memories, globals, and tables are included in their
+/// counts, and the code size is the size of the code section. The features
+/// used are the proposals whose types, operators, or other constructs appear
+/// in the module, found in a single pass over it. With `--json` the summary
+/// is instead included in the output as a `summary` object with the keys
+/// `functions`, `imports`, `exports`, `memories`, `globals`, `tables`,
+/// `code_size`, `start`, and `features_used`, where `start` is the index of
+/// the start function or `null`. Components aren't supported with
+/// `--summary`.
+///
+/// # Deterministic execution
+///
+/// With `--deterministic` every use of an operator whose result may differ
+/// between engines, or between runs on the same engine, is reported after
+/// validation. The operators reported are:
+///
+/// * Scalar float arithmetic which may produce a NaN, since the bit pattern
+///   of that NaN isn't specified: `add`, `sub`, `mul`, `div`, `sqrt`, `min`,
+///   `max`, `ceil`, `floor`, `trunc`, and `nearest` on `f32` and `f64`, along
+///   with `f32.demote_f64` and `f64.promote_f32`.
+/// * The same operators on the `f32x4` and `f64x2` SIMD types, along with
+///   `f32x4.demote_f64x2_zero` and `f64x2.promote_low_f32x4`.
+/// * All operators of the relaxed SIMD proposal, whose results are
+///   implementation-defined.
+/// * `memory.grow` and `table.grow`, which may fail depending on the
+///   resources available to the host.
+///
+/// Operators which only move or reinterpret the bits of floats, such as
+/// `f32.abs`, `f32.neg`, `f32.copysign`, and comparisons, are deterministic
+/// and aren't reported. Components aren't supported with `--deterministic`.
+///
+/// # Allowed imports
+///
+/// The file passed to `--allowed-imports` is a JSON array of the imports
+/// which the module may have:
+///
+/// ```json
+/// [
+///   { "module": "env", "name": "log", "type": "(func (param i32 i32))" },
+///   { "module": "env", "name": "memory", "type": "(memory 1 16)" }
+/// ]
+/// ```
+///
+/// The `type` of each entry is written as it would be in an import of the
+/// text format. An import of the module is allowed only if an entry has the
+/// same module and name and exactly the same type. Every import which isn't
+/// allowed is reported. Components aren't supported with `--allowed-imports`.
+///
+/// # Server protocol
+///
+/// With `--serve` modules are read from stdin and a result is written to
+/// stdout for each one, in order, until stdin is closed. Each module is sent
+/// as a frame of a 4-byte little-endian length followed by that many bytes of
+/// the module in either the binary or text format. Each result is a status
+/// byte followed by a 4-byte little-endian length and that many bytes of a
+/// UTF-8 message, where the status is:
+///
+/// * 0 - the module is valid and the message is empty.
+/// * 1 - the module is invalid and the message describes why.
+/// * 2 - the frame is longer than `--max-frame-size`, its contents are
+///   skipped and the message describes the error.
+///
+/// All other options, such as `--features` and `--cache-dir`, apply to each
+/// module. A frame truncated by stdin being closed is reported on stderr and
+/// the process exits with an error.
 #[derive(clap::Parser)]
 pub struct Opts {
     /// Comma-separated list of WebAssembly features to enable during validation.
@@ -33,6 +183,115 @@ pub struct Opts {
     #[clap(long, short = 'f', value_parser = parse_features)]
     features: Option<WasmFeatures>,
 
+    /// A limit on the number of items in the module, in the form `NAME=MAX`.
+    ///
+    /// Modules exceeding a limit are rejected as soon as the section
+    /// declaring the items is reached. Imported items count towards the
+    /// limit of their kind. The names which may be limited are "functions",
+    /// "globals", "tables", "memories", "types", "elements", and "data". This
+    /// option may be passed multiple times.
+    #[clap(long = "limit", value_name = "NAME=MAX", value_parser = parse_limit)]
+    limits: Vec<Limit>,
+
+    /// Comma-separated list of operators which may not appear in functions.
+    ///
+    /// Operators are named as in the text format, for example "memory.grow"
+    /// or "f32.add", and using one in a function is reported as an error at
+    /// the offending instruction. This option may be passed multiple times.
+    #[clap(long, value_name = "OPS", value_delimiter = ',', value_parser = parse_operator)]
+    forbid: Vec<String>,
+
+    /// The longest that validation may take, such as "500ms", "5s", or "1m",
+    /// before it's abandoned with an error.
+    ///
+    /// The time taken is checked before each function is validated, so a
+    /// single function may run over the timeout. With `--serve` this applies
+    /// to each module separately.
+    #[clap(long, value_name = "DURATION", value_parser = parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Directory in which to record modules which successfully validated.
+    ///
+    /// Entries are keyed on a hash of the input and the enabled features, and
+    /// validation is skipped if a matching entry is found. Failures to
+    /// validate are never recorded.
+    #[clap(long, value_name = "DIR")]
+    cache_dir: Option<PathBuf>,
+
+    /// Ignore any existing entry in `--cache-dir` and always validate the
+    /// input, recording the result if it succeeds.
+    #[clap(long, requires = "cache_dir")]
+    no_cache: bool,
+
+    /// After validating, check that all imports from WASI match the signatures
+    /// of the functions that WASI defines.
+    ///
+    /// Imports from the `wasi_snapshot_preview1` module are checked against
+    /// the functions of WASI preview1, and any import with a mismatched type
+    /// or which WASI doesn't define is reported. Imports from `wasi:*`
+    /// interfaces have no fixed core wasm signatures and aren't checked.
+    #[clap(long)]
+    wasi: bool,
+
+    /// After validating, check that every hint of the
+    /// `metadata.code.branch_hint` custom section points at a `br_if` or `if`
+    /// instruction.
+    ///
+    /// Hints which point elsewhere, such as after a tool rewrote the code
+    /// without updating them, are reported along with the index of their
+    /// function and their offset within its body.
+    #[clap(long)]
+    branch_hints: bool,
+
+    /// After validating, report every use of an operator which may not
+    /// execute deterministically.
+    ///
+    /// See the deterministic execution section above for the operators which
+    /// are reported.
+    #[clap(long)]
+    deterministic: bool,
+
+    /// After validating, check that every import of the module is listed in
+    /// the JSON file given.
+    ///
+    /// See the allowed imports section above for the format of the file.
+    #[clap(long, value_name = "FILE", value_parser = parse_allowed_imports)]
+    allowed_imports: Option<AllowedImports>,
+
+    /// After validating, print a summary of the module's contents and the
+    /// features it uses.
+    ///
+    /// See the summary section above for what's included.
+    #[clap(long, conflicts_with = "serve")]
+    summary: bool,
+
+    /// Memory-map the input file instead of reading it into memory.
+    ///
+    /// This reduces peak memory usage when validating large binaries. The
+    /// input must be a binary file, not stdin or the text format.
+    #[cfg(feature = "mmap")]
+    #[clap(long, conflicts_with = "serve")]
+    mmap: bool,
+
+    /// Print the result as JSON to stdout instead of printing errors to
+    /// stderr.
+    ///
+    /// See the JSON output above for details of the format.
+    #[clap(long, conflicts_with = "serve")]
+    json: bool,
+
+    /// Validate a stream of modules read from stdin, writing a result for
+    /// each to stdout, instead of validating a single input.
+    ///
+    /// See the server protocol above for details of the framing of modules
+    /// and results.
+    #[clap(long)]
+    serve: bool,
+
+    /// The maximum size, in bytes, of a module sent with `--serve`.
+    #[clap(long, requires = "serve", default_value_t = 1 << 30, value_name = "BYTES")]
+    max_frame_size: u32,
+
     #[clap(flatten)]
     io: wasm_tools::InputOutput,
 }
@@ -49,12 +308,161 @@ impl Opts {
         // `Validator` we're using as we navigate nested modules (the module
         // linking proposal) and any functions found are deferred to get
         // validated later.
-        let mut validator = Validator::new_with_features(self.features.unwrap_or_default());
+        let features = self.features.unwrap_or_default();
+        let mut limits = ValidatorLimits::default();
+        for (accessor, max) in self.limits.iter() {
+            *accessor(&mut limits) = Some(*max);
+        }
+        if self.serve {
+            if self.io.input_path().is_some() {
+                bail!("`--serve` reads modules from stdin and doesn't take an input file");
+            }
+            return self.serve(features, limits);
+        }
+        if self.json {
+            let result = self.read_input().and_then(|input| {
+                self.check(&input, features, limits)?;
+                match self.summary {
+                    true => summarize(&input).map(Some),
+                    false => Ok(None),
+                }
+            });
+            return print_json(&result, &features);
+        }
+        let input = self.read_input()?;
+        self.check(&input, features, limits)?;
+        if self.summary {
+            eprint!("{}", summarize(&input)?);
+        }
+        Ok(())
+    }
+
+    /// Validates `wasm` along with any other checks requested, consulting
+    /// and updating the cache if one is configured.
+    fn check(&self, wasm: &[u8], features: WasmFeatures, limits: ValidatorLimits) -> Result<()> {
+        let cache_entry = self
+            .cache_dir
+            .as_ref()
+            .map(|dir| dir.join(self.cache_key(wasm, &features, &limits)));
+        if let Some(entry) = &cache_entry {
+            if !self.no_cache && entry.exists() {
+                log::info!("validation skipped, found `{}`", entry.display());
+                return Ok(());
+            }
+        }
+
+        self.validate(wasm, features, limits)?;
+        if self.wasi {
+            check_wasi_imports(wasm)?;
+        }
+        if self.branch_hints {
+            check_branch_hints(wasm)?;
+        }
+        if self.deterministic {
+            check_deterministic(wasm)?;
+        }
+        if let Some(allowed) = &self.allowed_imports {
+            check_allowed_imports(wasm, allowed)?;
+        }
+
+        if let Some(entry) = &cache_entry {
+            record_success(entry)?;
+        }
+        Ok(())
+    }
+
+    /// Validates each module framed on stdin until it's closed, writing the
+    /// results to stdout.
+    fn serve(&self, features: WasmFeatures, limits: ValidatorLimits) -> Result<()> {
+        let mut stdin = std::io::stdin().lock();
+        let mut stdout = std::io::stdout().lock();
+        let mut frames = 0;
+        loop {
+            let mut len = [0; 4];
+            match read_frame_bytes(&mut stdin, &mut len)? {
+                0 => break,
+                4 => {}
+                _ => bail!("stdin closed in the length of frame {}", frames),
+            }
+            let len = u32::from_le_bytes(len);
+
+            let (status, message) = if len > self.max_frame_size {
+                let skipped =
+                    std::io::copy(&mut (&mut stdin).take(len.into()), &mut std::io::sink())?;
+                if skipped != u64::from(len) {
+                    bail!("stdin closed in the contents of frame {}", frames);
+                }
+                let message = format!(
+                    "frame {} is {} bytes which exceeds the maximum of {}",
+                    frames, len, self.max_frame_size
+                );
+                (2, message)
+            } else {
+                let mut contents = vec![0; len as usize];
+                if read_frame_bytes(&mut stdin, &mut contents)? != contents.len() {
+                    bail!("stdin closed in the contents of frame {}", frames);
+                }
+                let result = wat::parse_bytes(&contents)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|wasm| self.check(&wasm, features, limits));
+                match result {
+                    Ok(()) => (0, String::new()),
+                    Err(e) => (1, format!("{:?}", e)),
+                }
+            };
+
+            stdout.write_all(&[status])?;
+            stdout.write_all(&(message.len() as u32).to_le_bytes())?;
+            stdout.write_all(message.as_bytes())?;
+            stdout.flush()?;
+            frames += 1;
+        }
+        log::info!("validated {} frames", frames);
+        Ok(())
+    }
+
+    fn read_input(&self) -> Result<Input> {
+        #[cfg(feature = "mmap")]
+        if self.mmap {
+            let path = match self.io.input_path() {
+                Some(path) => path,
+                None => anyhow::bail!("`--mmap` requires an input file, not stdin"),
+            };
+            let file = std::fs::File::open(path)
+                .with_context(|| format!("failed to open `{}`", path.display()))?;
+            // SAFETY: the mapping is only read, and modifications to the file
+            // by other processes while it's being validated are not supported.
+            let map = unsafe { memmap2::Mmap::map(&file) }
+                .with_context(|| format!("failed to map `{}`", path.display()))?;
+            if !map.starts_with(b"\0asm") {
+                anyhow::bail!(
+                    "`--mmap` requires a binary input, but `{}` is not a wasm binary",
+                    path.display()
+                );
+            }
+            return Ok(Input::Mapped(map));
+        }
+        Ok(Input::Owned(self.io.parse_input_wasm()?))
+    }
+
+    fn validate(&self, wasm: &[u8], features: WasmFeatures, limits: ValidatorLimits) -> Result<()> {
+        let mut validator = Validator::new_with_features(features);
+        validator.set_limits(limits);
+        for op in self.forbid.iter() {
+            // Names of operators were already checked by `parse_operator`.
+            validator.forbid_operator(op);
+        }
         let mut functions_to_validate = Vec::new();
-        let wasm = self.io.parse_input_wasm()?;
 
         let start = Instant::now();
-        for payload in Parser::new(0).parse_all(&wasm) {
+        let check_timeout = || match self.timeout {
+            Some(timeout) if start.elapsed() >= timeout => {
+                bail!("validation timed out after {:?}", timeout)
+            }
+            _ => Ok(()),
+        };
+        for payload in Parser::new(0).parse_all(wasm) {
+            check_timeout()?;
             match validator.payload(&payload?)? {
                 ValidPayload::Ok | ValidPayload::Parser(_) | ValidPayload::End(_) => {}
                 ValidPayload::Func(validator, body) => {
@@ -67,10 +475,11 @@ impl Opts {
         // After we've validate the entire wasm module we'll use `rayon` to iterate
         // over all functions in parallel and perform parallel validation of the
         // input wasm module.
-        let start = Instant::now();
+        let functions_start = Instant::now();
         functions_to_validate.into_par_iter().try_for_each_init(
             FuncValidatorAllocations::default,
             |allocs, (to_validate, body)| -> Result<_> {
+                check_timeout()?;
                 let mut validator = to_validate.into_validator(mem::take(allocs));
                 validator
                     .validate(&body)
@@ -79,35 +488,1052 @@ impl Opts {
                 Ok(())
             },
         )?;
-        log::info!("functions validated in {:?}", start.elapsed());
+        log::info!("functions validated in {:?}", functions_start.elapsed());
         Ok(())
     }
 }
 
-fn parse_features(arg: &str) -> Result<WasmFeatures> {
-    let mut ret = WasmFeatures::default();
+#[derive(serde::Serialize)]
+struct JsonResult {
+    valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<JsonError>,
+    features: Vec<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<Summary>,
+}
+
+#[derive(serde::Serialize)]
+struct JsonError {
+    message: String,
+    /// The offset in the binary of the error, if it was found while parsing
+    /// or validating.
+    offset: Option<usize>,
+}
+
+/// Prints `result` as JSON to stdout, exiting with a nonzero status if
+/// validation failed.
+fn print_json(result: &Result<Option<Summary>>, features: &WasmFeatures) -> Result<()> {
+    let mut features = *features;
+    let error = result.as_ref().err().map(|e| JsonError {
+        message: format!("{:#}", e),
+        offset: e
+            .chain()
+            .find_map(|e| e.downcast_ref::<BinaryReaderError>())
+            .map(|e| e.offset()),
+    });
+    let json = JsonResult {
+        valid: error.is_none(),
+        error,
+        features: FEATURES
+            .iter()
+            .filter(|(_, accessor)| *accessor(&mut features))
+            .map(|(name, _)| *name)
+            .collect(),
+        summary: result.as_ref().ok().cloned().flatten(),
+    };
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, &json)?;
+    writeln!(stdout)?;
+    stdout.flush()?;
+    if !json.valid {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Fills `buf` from `reader`, returning how many bytes were read before the
+/// end of the input was reached.
+fn read_frame_bytes(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(anyhow::Error::from(e).context("failed to read stdin")),
+        }
+    }
+    Ok(read)
+}
+
+/// The bytes of the input, which outlive any borrows of them while parsing.
+enum Input {
+    Owned(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl Deref for Input {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Input::Owned(bytes) => bytes,
+            #[cfg(feature = "mmap")]
+            Input::Mapped(map) => map,
+        }
+    }
+}
+
+impl Opts {
+    /// Returns the name of the cache entry recording that `wasm` is valid
+    /// with `features` enabled, within `limits`, and with the other checks
+    /// requested.
+    ///
+    /// The version of `wasm-tools` is included in the key since fixes to
+    /// validation may change whether a module is valid.
+    fn cache_key(&self, wasm: &[u8], features: &WasmFeatures, limits: &ValidatorLimits) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(env!("CARGO_PKG_VERSION"));
+        hasher.update([0]);
+        hasher.update(format!("{:?}", features));
+        hasher.update([0]);
+        hasher.update(format!("{:?}", limits));
+        hasher.update([0]);
+        hasher.update(format!("{:?}", self.forbid));
+        hasher.update([0]);
+        hasher.update([
+            self.wasi as u8,
+            self.deterministic as u8,
+            self.branch_hints as u8,
+        ]);
+        hasher.update(format!("{:?}", self.allowed_imports));
+        hasher.update([0]);
+        hasher.update(wasm);
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+fn record_success(entry: &Path) -> Result<()> {
+    let dir = entry.parent().unwrap();
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("failed to create cache directory `{}`", dir.display()))?;
+    std::fs::write(entry, "valid\n")
+        .with_context(|| format!("failed to write cache entry `{}`", entry.display()))?;
+    Ok(())
+}
+
+/// The name of the module which WASI preview1 functions are imported from.
+const WASI_PREVIEW1: &str = "wasi_snapshot_preview1";
+
+/// The core wasm signatures of the functions of WASI preview1, as
+/// `(name, params, results)`, where all parameters are `i32` other than the
+/// `i64` parameters listed.
+#[rustfmt::skip]
+const WASI_PREVIEW1_FUNCTIONS: &[(&str, &[ValType], &[ValType])] = &[
+    ("args_get", &[I32, I32], &[I32]),
+    ("args_sizes_get", &[I32, I32], &[I32]),
+    ("environ_get", &[I32, I32], &[I32]),
+    ("environ_sizes_get", &[I32, I32], &[I32]),
+    ("clock_res_get", &[I32, I32], &[I32]),
+    ("clock_time_get", &[I32, I64, I32], &[I32]),
+    ("fd_advise", &[I32, I64, I64, I32], &[I32]),
+    ("fd_allocate", &[I32, I64, I64], &[I32]),
+    ("fd_close", &[I32], &[I32]),
+    ("fd_datasync", &[I32], &[I32]),
+    ("fd_fdstat_get", &[I32, I32], &[I32]),
+    ("fd_fdstat_set_flags", &[I32, I32], &[I32]),
+    ("fd_fdstat_set_rights", &[I32, I64, I64], &[I32]),
+    ("fd_filestat_get", &[I32, I32], &[I32]),
+    ("fd_filestat_set_size", &[I32, I64], &[I32]),
+    ("fd_filestat_set_times", &[I32, I64, I64, I32], &[I32]),
+    ("fd_pread", &[I32, I32, I32, I64, I32], &[I32]),
+    ("fd_prestat_get", &[I32, I32], &[I32]),
+    ("fd_prestat_dir_name", &[I32, I32, I32], &[I32]),
+    ("fd_pwrite", &[I32, I32, I32, I64, I32], &[I32]),
+    ("fd_read", &[I32, I32, I32, I32], &[I32]),
+    ("fd_readdir", &[I32, I32, I32, I64, I32], &[I32]),
+    ("fd_renumber", &[I32, I32], &[I32]),
+    ("fd_seek", &[I32, I64, I32, I32], &[I32]),
+    ("fd_sync", &[I32], &[I32]),
+    ("fd_tell", &[I32, I32], &[I32]),
+    ("fd_write", &[I32, I32, I32, I32], &[I32]),
+    ("path_create_directory", &[I32, I32, I32], &[I32]),
+    ("path_filestat_get", &[I32, I32, I32, I32, I32], &[I32]),
+    ("path_filestat_set_times", &[I32, I32, I32, I32, I64, I64, I32], &[I32]),
+    ("path_link", &[I32, I32, I32, I32, I32, I32, I32], &[I32]),
+    ("path_open", &[I32, I32, I32, I32, I32, I64, I64, I32, I32], &[I32]),
+    ("path_readlink", &[I32, I32, I32, I32, I32, I32], &[I32]),
+    ("path_remove_directory", &[I32, I32, I32], &[I32]),
+    ("path_rename", &[I32, I32, I32, I32, I32, I32], &[I32]),
+    ("path_symlink", &[I32, I32, I32, I32, I32], &[I32]),
+    ("path_unlink_file", &[I32, I32, I32], &[I32]),
+    ("poll_oneoff", &[I32, I32, I32, I32], &[I32]),
+    ("proc_exit", &[I32], &[]),
+    ("proc_raise", &[I32], &[I32]),
+    ("sched_yield", &[], &[I32]),
+    ("random_get", &[I32, I32], &[I32]),
+    ("sock_accept", &[I32, I32, I32], &[I32]),
+    ("sock_recv", &[I32, I32, I32, I32, I32, I32], &[I32]),
+    ("sock_send", &[I32, I32, I32, I32, I32], &[I32]),
+    ("sock_shutdown", &[I32, I32], &[I32]),
+];
+
+/// Checks that every import from WASI preview1 in the core module `wasm`
+/// matches the signature WASI defines, reporting all mismatches found.
+fn check_wasi_imports(wasm: &[u8]) -> Result<()> {
+    let mut types = Vec::new();
+    let mut errors = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::Version {
+                encoding: Encoding::Component,
+                ..
+            } => bail!("`--wasi` does not support components"),
+            Payload::TypeSection(reader) => {
+                for ty in reader {
+                    let Type::Func(ty) = ty?;
+                    types.push(ty);
+                }
+            }
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    if import.module.starts_with("wasi:") {
+                        log::debug!("not checking import `{}::{}`", import.module, import.name);
+                        continue;
+                    }
+                    if import.module != WASI_PREVIEW1 {
+                        continue;
+                    }
+                    let expected = WASI_PREVIEW1_FUNCTIONS
+                        .iter()
+                        .find(|(name, _, _)| *name == import.name);
+                    let (_, params, results) = match expected {
+                        Some(expected) => expected,
+                        None => {
+                            errors.push(format!(
+                                "unknown WASI import `{}::{}`",
+                                import.module, import.name
+                            ));
+                            continue;
+                        }
+                    };
+                    let expected = func_type_string(params, results);
+                    let actual = match import.ty {
+                        TypeRef::Func(ty) => match types.get(ty as usize) {
+                            Some(ty) => func_type_string(ty.params(), ty.results()),
+                            None => bail!("import `{}` has an invalid type", import.name),
+                        },
+                        other => format!("{:?}", other),
+                    };
+                    if actual != expected {
+                        errors.push(format!(
+                            "import `{}::{}` has type `{}` but WASI expects `{}`",
+                            import.module, import.name, actual, expected
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !errors.is_empty() {
+        bail!("module doesn't conform to WASI:\n  {}", errors.join("\n  "));
+    }
+    Ok(())
+}
+
+/// The name of the custom section of branch hints.
+const BRANCH_HINT_SECTION: &str = "metadata.code.branch_hint";
+
+/// Checks that each hint of the branch hint section of the core module
+/// `wasm`, if it has one, points at a `br_if` or `if` instruction, reporting
+/// every hint which doesn't.
+///
+/// The section is a vector of functions, each with a vector of hints. Each
+/// hint is the offset of an instruction relative to the start of the body
+/// of the function, followed by the size of the hint, which is always 1, and
+/// the hint itself, which is 0 if the branch is unlikely to be taken and 1 if
+/// it's likely to be taken.
+fn check_branch_hints(wasm: &[u8]) -> Result<()> {
+    let mut imported_funcs = 0;
+    let mut bodies = Vec::new();
+    let mut sections = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::Version {
+                encoding: Encoding::Component,
+                ..
+            } => bail!("`--branch-hints` does not support components"),
+            Payload::ImportSection(reader) => {
+                imported_funcs = ImportCounts::from_reader(reader)?.funcs;
+            }
+            Payload::CodeSectionEntry(body) => bodies.push(body),
+            Payload::CustomSection(c) if c.name() == BRANCH_HINT_SECTION => sections.push(c),
+            _ => {}
+        }
+    }
+
+    let mut errors = Vec::new();
+    for section in sections {
+        let mut reader = BinaryReader::new_with_offset(section.data(), section.data_offset());
+        for _ in 0..reader.read_var_u32()? {
+            let func = reader.read_var_u32()?;
+            let body = match func.checked_sub(imported_funcs) {
+                Some(i) => match bodies.get(i as usize) {
+                    Some(body) => Some(body),
+                    None => {
+                        errors.push(format!("func {} has branch hints but doesn't exist", func));
+                        None
+                    }
+                },
+                None => {
+                    errors.push(format!("func {} has branch hints but is imported", func));
+                    None
+                }
+            };
+
+            // The operators of the function keyed by their offset relative
+            // to the start of its body.
+            let mut ops = HashMap::new();
+            if let Some(body) = body {
+                let mut ops_reader = body.get_operators_reader()?;
+                ops_reader.allow_memarg64(true);
+                while !ops_reader.eof() {
+                    let (op, offset) = ops_reader.read_with_offset()?;
+                    ops.insert(offset - body.range().start, op);
+                }
+            }
+
+            for _ in 0..reader.read_var_u32()? {
+                let offset = reader.read_var_u32()? as usize;
+                let size = reader.read_var_u32()?;
+                let hint = reader.read_bytes(size as usize)?;
+                if size != 1 {
+                    errors.push(format!(
+                        "func {} at offset {:#x}: branch hint has size {} instead of 1",
+                        func, offset, size
+                    ));
+                } else if hint[0] > 1 {
+                    errors.push(format!(
+                        "func {} at offset {:#x}: branch hint has invalid value {}",
+                        func, offset, hint[0]
+                    ));
+                }
+                if body.is_none() {
+                    continue;
+                }
+                match ops.get(&offset) {
+                    Some(Operator::If { .. } | Operator::BrIf { .. }) => {}
+                    Some(op) => errors.push(format!(
+                        "func {} at offset {:#x}: branch hint points at `{}` instead of \
+                         `br_if` or `if`",
+                        func,
+                        offset,
+                        operator_name(op)
+                    )),
+                    None => errors.push(format!(
+                        "func {} at offset {:#x}: branch hint doesn't point at an instruction",
+                        func, offset
+                    )),
+                }
+            }
+        }
+        if !reader.eof() {
+            bail!(
+                "unexpected data at the end of the `{}` section (at offset {:#x})",
+                BRANCH_HINT_SECTION,
+                reader.original_position()
+            );
+        }
+    }
+
+    if !errors.is_empty() {
+        bail!("module has stale branch hints:\n  {}", errors.join("\n  "));
+    }
+    Ok(())
+}
+
+/// Returns the text format name of `op`, such as `i32.add`.
+fn operator_name(op: &Operator<'_>) -> String {
+    const PREFIXES: &[&str] = &[
+        "i32", "i64", "f32", "f64", "v128", "i8x16", "i16x8", "i32x4", "i64x2", "f32x4", "f64x2",
+        "local", "global", "memory", "table", "ref", "data", "elem",
+    ];
+    let (visit, _) = operator_info(op);
+    match visit.split_once('_') {
+        Some((prefix, rest)) if PREFIXES.contains(&prefix) => format!("{}.{}", prefix, rest),
+        _ => visit.to_string(),
+    }
+}
+
+/// The operators reported by `--deterministic`, other than those of the
+/// relaxed SIMD proposal, by the names of their visitor methods without the
+/// `visit_` prefix.
+#[rustfmt::skip]
+const NON_DETERMINISTIC_OPERATORS: &[(&str, &str)] = &[
+    ("f32_add", NAN), ("f32_sub", NAN), ("f32_mul", NAN), ("f32_div", NAN),
+    ("f32_sqrt", NAN), ("f32_min", NAN), ("f32_max", NAN), ("f32_ceil", NAN),
+    ("f32_floor", NAN), ("f32_trunc", NAN), ("f32_nearest", NAN),
+    ("f64_add", NAN), ("f64_sub", NAN), ("f64_mul", NAN), ("f64_div", NAN),
+    ("f64_sqrt", NAN), ("f64_min", NAN), ("f64_max", NAN), ("f64_ceil", NAN),
+    ("f64_floor", NAN), ("f64_trunc", NAN), ("f64_nearest", NAN),
+    ("f32_demote_f64", NAN), ("f64_promote_f32", NAN),
+    ("f32x4_add", NAN), ("f32x4_sub", NAN), ("f32x4_mul", NAN), ("f32x4_div", NAN),
+    ("f32x4_sqrt", NAN), ("f32x4_min", NAN), ("f32x4_max", NAN), ("f32x4_ceil", NAN),
+    ("f32x4_floor", NAN), ("f32x4_trunc", NAN), ("f32x4_nearest", NAN),
+    ("f64x2_add", NAN), ("f64x2_sub", NAN), ("f64x2_mul", NAN), ("f64x2_div", NAN),
+    ("f64x2_sqrt", NAN), ("f64x2_min", NAN), ("f64x2_max", NAN), ("f64x2_ceil", NAN),
+    ("f64x2_floor", NAN), ("f64x2_trunc", NAN), ("f64x2_nearest", NAN),
+    ("f32x4_demote_f64x2_zero", NAN), ("f64x2_promote_low_f32x4", NAN),
+    ("memory_grow", GROW), ("table_grow", GROW),
+];
+
+const NAN: &str = "may produce a NaN with a non-deterministic bit pattern";
+const GROW: &str = "may fail depending on the resources of the host";
+const RELAXED: &str = "has implementation-defined results";
+
+/// Returns the name of the visitor method for `op` without its `visit_`
+/// prefix, such as `f32_add`, and whether it's part of the relaxed SIMD
+/// proposal.
+fn operator_info(op: &Operator<'_>) -> (&'static str, bool) {
+    macro_rules! operator_info {
+        ($(@$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident)*) => {
+            match op {
+                $(
+                    Operator::$op { .. } => (
+                        &stringify!($visit)["visit_".len()..],
+                        stringify!($proposal) == "relaxed_simd",
+                    ),
+                )*
+            }
+        }
+    }
+    wasmparser::for_each_operator!(operator_info)
+}
+
+/// Checks that the functions of the core module `wasm` only use operators
+/// which execute deterministically, reporting every use of one which doesn't.
+fn check_deterministic(wasm: &[u8]) -> Result<()> {
+    let mut func = 0;
+    let mut errors = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::Version {
+                encoding: Encoding::Component,
+                ..
+            } => bail!("`--deterministic` does not support components"),
+            Payload::ImportSection(reader) => func = ImportCounts::from_reader(reader)?.funcs,
+            Payload::CodeSectionEntry(body) => {
+                let mut reader = body.get_operators_reader()?;
+                reader.allow_memarg64(true);
+                while !reader.eof() {
+                    let (op, offset) = reader.read_with_offset()?;
+                    let (visit, relaxed) = operator_info(&op);
+                    let reason = if relaxed {
+                        RELAXED
+                    } else {
+                        match NON_DETERMINISTIC_OPERATORS
+                            .iter()
+                            .find(|(name, _)| *name == visit)
+                        {
+                            Some((_, reason)) => reason,
+                            None => continue,
+                        }
+                    };
+                    // Text format names replace the first `_` of the visitor
+                    // method's name with a `.`.
+                    errors.push(format!(
+                        "func {} at offset {:#x}: `{}` {}",
+                        func,
+                        offset,
+                        visit.replacen('_', ".", 1),
+                        reason
+                    ));
+                }
+                func += 1;
+            }
+            _ => {}
+        }
+    }
+
+    if !errors.is_empty() {
+        bail!("module isn't deterministic:\n  {}", errors.join("\n  "));
+    }
+    Ok(())
+}
+
+/// The imports allowed by `--allowed-imports`.
+#[derive(Clone, Debug)]
+struct AllowedImports(Vec<AllowedImport>);
+
+#[derive(Clone, Debug)]
+struct AllowedImport {
+    module: String,
+    name: String,
+    ty: ImportType,
+}
+
+/// An entry of the file passed to `--allowed-imports`.
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AllowedImportEntry {
+    module: String,
+    name: String,
+    #[serde(rename = "type")]
+    ty: String,
+}
+
+/// The type of an import with any type indices resolved to the type they
+/// refer to.
+#[derive(Clone, Debug, PartialEq)]
+enum ImportType {
+    Func(FuncType),
+    Table(TableType),
+    Memory(MemoryType),
+    Global(GlobalType),
+    Tag(FuncType),
+}
+
+impl ImportType {
+    fn new(ty: TypeRef, types: &[FuncType]) -> Result<ImportType> {
+        let func_type = |idx: u32| match types.get(idx as usize) {
+            Some(ty) => Ok(ty.clone()),
+            None => bail!("type index {} is out of bounds", idx),
+        };
+        Ok(match ty {
+            TypeRef::Func(idx) => ImportType::Func(func_type(idx)?),
+            TypeRef::Table(ty) => ImportType::Table(ty),
+            TypeRef::Memory(ty) => ImportType::Memory(ty),
+            TypeRef::Global(ty) => ImportType::Global(ty),
+            TypeRef::Tag(ty) => ImportType::Tag(func_type(ty.func_type_idx)?),
+        })
+    }
+}
+
+impl std::fmt::Display for ImportType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lowercase = |ty: &dyn std::fmt::Debug| format!("{:?}", ty).to_lowercase();
+        match self {
+            ImportType::Func(ty) => f.write_str(&func_type_string(ty.params(), ty.results())),
+            ImportType::Tag(ty) => {
+                let func = func_type_string(ty.params(), ty.results());
+                write!(f, "(tag{}", &func["(func".len()..])
+            }
+            ImportType::Table(ty) => {
+                write!(f, "(table {}", ty.initial)?;
+                if let Some(max) = ty.maximum {
+                    write!(f, " {}", max)?;
+                }
+                write!(f, " {})", lowercase(&ty.element_type))
+            }
+            ImportType::Memory(ty) => {
+                f.write_str("(memory")?;
+                if ty.memory64 {
+                    f.write_str(" i64")?;
+                }
+                write!(f, " {}", ty.initial)?;
+                if let Some(max) = ty.maximum {
+                    write!(f, " {}", max)?;
+                }
+                if ty.shared {
+                    f.write_str(" shared")?;
+                }
+                if let Some(log2) = ty.page_size_log2 {
+                    match 1u64.checked_shl(log2) {
+                        Some(size) => write!(f, " (pagesize {})", size)?,
+                        None => write!(f, " (pagesize 2**{})", log2)?,
+                    }
+                }
+                f.write_str(")")
+            }
+            ImportType::Global(ty) if ty.mutable => {
+                write!(f, "(global (mut {}))", lowercase(&ty.content_type))
+            }
+            ImportType::Global(ty) => write!(f, "(global {})", lowercase(&ty.content_type)),
+        }
+    }
+}
+
+fn parse_allowed_imports(arg: &str) -> Result<AllowedImports> {
+    // Errors are flattened into a single message since clap only prints the
+    // outermost context of an error.
+    read_allowed_imports(arg).map_err(|e| anyhow!("{:#}", e))
+}
+
+fn read_allowed_imports(arg: &str) -> Result<AllowedImports> {
+    let contents =
+        std::fs::read_to_string(arg).with_context(|| format!("failed to read `{}`", arg))?;
+    let entries: Vec<AllowedImportEntry> =
+        serde_json::from_str(&contents).with_context(|| format!("failed to parse `{}`", arg))?;
+    let mut allowed = Vec::new();
+    for entry in entries {
+        let ty = parse_import_type(&entry.ty).with_context(|| {
+            format!(
+                "invalid type `{}` of allowed import `{}::{}`",
+                entry.ty, entry.module, entry.name
+            )
+        })?;
+        allowed.push(AllowedImport {
+            module: entry.module,
+            name: entry.name,
+            ty,
+        });
+    }
+    Ok(AllowedImports(allowed))
+}
+
+/// Parses the text format of the type of an import, such as
+/// `(func (param i32))`.
+fn parse_import_type(ty: &str) -> Result<ImportType> {
+    let wasm = wat::parse_str(format!("(module (import \"\" \"\" {}))", ty))?;
+    let mut types = Vec::new();
+    for payload in Parser::new(0).parse_all(&wasm) {
+        match payload? {
+            Payload::TypeSection(reader) => {
+                for ty in reader {
+                    let Type::Func(ty) = ty?;
+                    types.push(ty);
+                }
+            }
+            Payload::ImportSection(reader) => {
+                if let Some(import) = reader.into_iter().next() {
+                    return ImportType::new(import?.ty, &types);
+                }
+            }
+            _ => {}
+        }
+    }
+    bail!("expected the type of an import")
+}
+
+/// Checks that every import of the core module `wasm` is in `allowed`,
+/// reporting all imports which aren't.
+fn check_allowed_imports(wasm: &[u8], allowed: &AllowedImports) -> Result<()> {
+    let mut types = Vec::new();
+    let mut errors = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::Version {
+                encoding: Encoding::Component,
+                ..
+            } => bail!("`--allowed-imports` does not support components"),
+            Payload::TypeSection(reader) => {
+                for ty in reader {
+                    let Type::Func(ty) = ty?;
+                    types.push(ty);
+                }
+            }
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    let ty = ImportType::new(import.ty, &types)?;
+                    let candidates = allowed
+                        .0
+                        .iter()
+                        .filter(|a| a.module == import.module && a.name == import.name)
+                        .collect::<Vec<_>>();
+                    if candidates.iter().any(|a| a.ty == ty) {
+                        continue;
+                    }
+                    if candidates.is_empty() {
+                        errors.push(format!(
+                            "import `{}::{}` of type `{}` isn't allowed",
+                            import.module, import.name, ty
+                        ));
+                    } else {
+                        errors.push(format!(
+                            "import `{}::{}` has type `{}` but only {} is allowed",
+                            import.module,
+                            import.name,
+                            ty,
+                            candidates
+                                .iter()
+                                .map(|a| format!("`{}`", a.ty))
+                                .collect::<Vec<_>>()
+                                .join(" or "),
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !errors.is_empty() {
+        bail!(
+            "module has imports which aren't allowed:\n  {}",
+            errors.join("\n  ")
+        );
+    }
+    Ok(())
+}
+
+/// An overview of the contents of a valid core module.
+#[derive(serde::Serialize, Clone, Default)]
+struct Summary {
+    functions: u32,
+    imports: u32,
+    exports: u32,
+    memories: u32,
+    globals: u32,
+    tables: u32,
+    code_size: u32,
+    start: Option<u32>,
+    features_used: Vec<&'static str>,
+}
+
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "functions: {}", self.functions)?;
+        writeln!(f, "imports: {}", self.imports)?;
+        writeln!(f, "exports: {}", self.exports)?;
+        writeln!(f, "memories: {}", self.memories)?;
+        writeln!(f, "globals: {}", self.globals)?;
+        writeln!(f, "tables: {}", self.tables)?;
+        writeln!(f, "code size: {} bytes", self.code_size)?;
+        match self.start {
+            Some(start) => writeln!(f, "start function: {}", start)?,
+            None => writeln!(f, "start function: none")?,
+        }
+        match self.features_used.len() {
+            0 => writeln!(f, "features used: none"),
+            _ => writeln!(f, "features used: {}", self.features_used.join(", ")),
+        }
+    }
+}
 
-    const FEATURES: &[(&str, fn(&mut WasmFeatures) -> &mut bool)] = &[
-        ("reference-types", |f| &mut f.reference_types),
-        ("simd", |f| &mut f.simd),
-        ("threads", |f| &mut f.threads),
-        ("bulk-memory", |f| &mut f.bulk_memory),
-        ("multi-value", |f| &mut f.multi_value),
-        ("tail-call", |f| &mut f.tail_call),
-        ("component-model", |f| &mut f.component_model),
-        ("multi-memory", |f| &mut f.multi_memory),
-        ("exception-handling", |f| &mut f.exceptions),
-        ("memory64", |f| &mut f.memory64),
-        ("extended-const", |f| &mut f.extended_const),
-        ("deterministic", |f| &mut f.deterministic_only),
-        ("saturating-float-to-int", |f| {
-            &mut f.saturating_float_to_int
-        }),
-        ("sign-extension", |f| &mut f.sign_extension),
-        ("mutable-global", |f| &mut f.mutable_global),
-        ("relaxed-simd", |f| &mut f.relaxed_simd),
+/// Summarizes the contents of the valid core module `wasm`.
+fn summarize(wasm: &[u8]) -> Result<Summary> {
+    let mut summary = Summary::default();
+    let mut used = UsedFeatures::default();
+    let mut func_types = Vec::new();
+    let mut mutable_globals = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::Version {
+                encoding: Encoding::Component,
+                ..
+            } => bail!("`--summary` does not support components"),
+            Payload::TypeSection(reader) => {
+                for ty in reader {
+                    let Type::Func(ty) = ty?;
+                    if ty.results().len() > 1 {
+                        used.insert("multi-value");
+                    }
+                    for ty in ty.params().iter().chain(ty.results()) {
+                        used.val_type(*ty);
+                    }
+                    func_types.push(ty);
+                }
+            }
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    summary.imports += 1;
+                    match import?.ty {
+                        TypeRef::Func(_) => summary.functions += 1,
+                        TypeRef::Memory(ty) => {
+                            summary.memories += 1;
+                            used.memory_type(&ty);
+                        }
+                        TypeRef::Global(ty) => {
+                            summary.globals += 1;
+                            if ty.mutable {
+                                used.insert("mutable-global");
+                            }
+                            used.val_type(ty.content_type);
+                            mutable_globals.push(ty.mutable);
+                        }
+                        TypeRef::Table(ty) => {
+                            summary.tables += 1;
+                            used.val_type(ty.element_type);
+                        }
+                        TypeRef::Tag(_) => used.insert("exception-handling"),
+                    }
+                }
+            }
+            Payload::FunctionSection(reader) => summary.functions += reader.get_count(),
+            Payload::MemorySection(reader) => {
+                summary.memories += reader.get_count();
+                for ty in reader {
+                    used.memory_type(&ty?);
+                }
+            }
+            Payload::GlobalSection(reader) => {
+                summary.globals += reader.get_count();
+                for global in reader {
+                    let global = global?;
+                    used.val_type(global.ty.content_type);
+                    used.const_expr(global.init_expr.get_operators_reader())?;
+                    mutable_globals.push(global.ty.mutable);
+                }
+            }
+            Payload::TableSection(reader) => {
+                summary.tables += reader.get_count();
+                for ty in reader {
+                    used.val_type(ty?.element_type);
+                }
+            }
+            Payload::TagSection(_) => used.insert("exception-handling"),
+            Payload::ExportSection(reader) => {
+                summary.exports += reader.get_count();
+                for export in reader {
+                    let export = export?;
+                    if export.kind == ExternalKind::Global && mutable_globals[export.index as usize]
+                    {
+                        used.insert("mutable-global");
+                    }
+                }
+            }
+            Payload::StartSection { func, .. } => summary.start = Some(func),
+            Payload::ElementSection(reader) => {
+                for element in reader {
+                    let element = element?;
+                    used.val_type(element.ty);
+                    match element.kind {
+                        ElementKind::Active { offset_expr, .. } => {
+                            used.const_expr(offset_expr.get_operators_reader())?
+                        }
+                        ElementKind::Passive | ElementKind::Declared => used.insert("bulk-memory"),
+                    }
+                    for item in element.items.get_items_reader()? {
+                        if let ElementItem::Expr(expr) = item? {
+                            used.const_expr(expr.get_operators_reader())?;
+                        }
+                    }
+                }
+            }
+            Payload::DataCountSection { .. } => used.insert("bulk-memory"),
+            Payload::DataSection(reader) => {
+                for data in reader {
+                    match data?.kind {
+                        DataKind::Active { offset_expr, .. } => {
+                            used.const_expr(offset_expr.get_operators_reader())?
+                        }
+                        DataKind::Passive => used.insert("bulk-memory"),
+                    }
+                }
+            }
+            Payload::CodeSectionStart { range, .. } => summary.code_size = range.len() as u32,
+            Payload::CodeSectionEntry(body) => {
+                for local in body.get_locals_reader()? {
+                    used.val_type(local?.1);
+                }
+                let mut reader = body.get_operators_reader()?;
+                reader.allow_memarg64(true);
+                for op in reader {
+                    used.operator(&op?, &func_types);
+                }
+            }
+            _ => {}
+        }
+    }
+    if summary.memories > 1 {
+        used.insert("multi-memory");
+    }
+    if summary.tables > 1 {
+        used.insert("reference-types");
+    }
+
+    summary.features_used = FEATURES
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| used.0.contains(name))
+        .collect();
+    Ok(summary)
+}
+
+/// The names, as in `FEATURES`, of the proposals whose types, operators, or
+/// other constructs are used by a module.
+#[derive(Default)]
+struct UsedFeatures(HashSet<&'static str>);
+
+impl UsedFeatures {
+    fn insert(&mut self, name: &'static str) {
+        self.0.insert(name);
+    }
+
+    fn val_type(&mut self, ty: ValType) {
+        match ty {
+            ValType::V128 => self.insert("simd"),
+            ValType::FuncRef | ValType::ExternRef => self.insert("reference-types"),
+            _ => {}
+        }
+    }
+
+    fn memory_type(&mut self, ty: &MemoryType) {
+        if ty.memory64 {
+            self.insert("memory64");
+        }
+        if ty.shared {
+            self.insert("threads");
+        }
+        if ty.page_size_log2.is_some() {
+            self.insert("custom-page-sizes");
+        }
+    }
+
+    fn const_expr(&mut self, reader: OperatorsReader<'_>) -> Result<()> {
+        for op in reader {
+            let op = op?;
+            match op {
+                Operator::I32Add
+                | Operator::I32Sub
+                | Operator::I32Mul
+                | Operator::I64Add
+                | Operator::I64Sub
+                | Operator::I64Mul => self.insert("extended-const"),
+                _ => self.operator(&op, &[]),
+            }
+        }
+        Ok(())
+    }
+
+    fn operator(&mut self, op: &Operator<'_>, func_types: &[FuncType]) {
+        macro_rules! operator_proposal {
+            ($(@$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident)*) => {
+                match op {
+                    $(Operator::$op { .. } => stringify!($proposal),)*
+                }
+            }
+        }
+        let feature = match wasmparser::for_each_operator!(operator_proposal) {
+            "bulk_memory" => "bulk-memory",
+            "exceptions" => "exception-handling",
+            "reference_types" => "reference-types",
+            "relaxed_simd" => "relaxed-simd",
+            "saturating_float_to_int" => "saturating-float-to-int",
+            "sign_extension" => "sign-extension",
+            "simd" => "simd",
+            "tail_call" => "tail-call",
+            "threads" => "threads",
+            _ => "",
+        };
+        if !feature.is_empty() {
+            self.insert(feature);
+        }
+
+        // Some operators of the MVP use proposals through their immediates.
+        match op {
+            Operator::Block { blockty }
+            | Operator::Loop { blockty }
+            | Operator::If { blockty }
+            | Operator::Try { blockty } => match blockty {
+                BlockType::Empty => {}
+                BlockType::Type(ty) => self.val_type(*ty),
+                BlockType::FuncType(idx) => {
+                    self.insert("multi-value");
+                    let ty = &func_types[*idx as usize];
+                    for ty in ty.params().iter().chain(ty.results()) {
+                        self.val_type(*ty);
+                    }
+                }
+            },
+            Operator::CallIndirect { table_index, .. } if *table_index != 0 => {
+                self.insert("reference-types")
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Formats a function type as it would appear in the text format.
+fn func_type_string(params: &[ValType], results: &[ValType]) -> String {
+    let mut ret = String::from("(func");
+    for (desc, tys) in [("param", params), ("result", results)] {
+        if tys.is_empty() {
+            continue;
+        }
+        ret.push_str(&format!(" ({}", desc));
+        for ty in tys {
+            ret.push_str(&format!(" {}", format!("{:?}", ty).to_lowercase()));
+        }
+        ret.push(')');
+    }
+    ret.push(')');
+    ret
+}
+
+type FeatureAccessor = fn(&mut WasmFeatures) -> &mut bool;
+type LimitAccessor = fn(&mut ValidatorLimits) -> &mut Option<u32>;
+type Limit = (LimitAccessor, u32);
+
+fn parse_limit(arg: &str) -> Result<Limit> {
+    const LIMITS: &[(&str, LimitAccessor)] = &[
+        ("functions", |l| &mut l.functions),
+        ("globals", |l| &mut l.globals),
+        ("tables", |l| &mut l.tables),
+        ("memories", |l| &mut l.memories),
+        ("types", |l| &mut l.types),
+        ("elements", |l| &mut l.element_segments),
+        ("data", |l| &mut l.data_segments),
     ];
 
+    let (name, max) = match arg.split_once('=') {
+        Some(pair) => pair,
+        None => return Err(anyhow!("limit `{}` is not of the form `NAME=MAX`", arg)),
+    };
+    let max = max
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid maximum for limit `{}`", name))?;
+    let (_, accessor) = LIMITS
+        .iter()
+        .find(|(n, _)| *n == name.trim())
+        .ok_or_else(|| anyhow!("unknown limit `{}`", name))?;
+    Ok((*accessor, max))
+}
+
+fn parse_duration(arg: &str) -> Result<Duration> {
+    let arg = arg.trim();
+    let split = arg.find(|c: char| !c.is_ascii_digit()).unwrap_or(arg.len());
+    let (value, unit) = arg.split_at(split);
+    let value: u64 = value
+        .parse()
+        .with_context(|| format!("invalid duration `{}`", arg))?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(value)),
+        "s" | "" => Ok(Duration::from_secs(value)),
+        "m" => Ok(Duration::from_secs(value.saturating_mul(60))),
+        _ => bail!(
+            "unknown unit `{}` in duration `{}`, expected `ms`, `s`, or `m`",
+            unit,
+            arg
+        ),
+    }
+}
+
+fn parse_operator(arg: &str) -> Result<String> {
+    let op = arg.trim();
+    if !Validator::new().forbid_operator(op) {
+        bail!("unknown operator `{}`", op);
+    }
+    Ok(op.to_string())
+}
+
+/// The names of the features accepted by `--features`.
+const FEATURES: &[(&str, FeatureAccessor)] = &[
+    ("reference-types", |f| &mut f.reference_types),
+    ("simd", |f| &mut f.simd),
+    ("threads", |f| &mut f.threads),
+    ("bulk-memory", |f| &mut f.bulk_memory),
+    ("multi-value", |f| &mut f.multi_value),
+    ("tail-call", |f| &mut f.tail_call),
+    ("component-model", |f| &mut f.component_model),
+    ("multi-memory", |f| &mut f.multi_memory),
+    ("exception-handling", |f| &mut f.exceptions),
+    ("memory64", |f| &mut f.memory64),
+    ("extended-const", |f| &mut f.extended_const),
+    ("deterministic", |f| &mut f.deterministic_only),
+    ("saturating-float-to-int", |f| {
+        &mut f.saturating_float_to_int
+    }),
+    ("sign-extension", |f| &mut f.sign_extension),
+    ("mutable-global", |f| &mut f.mutable_global),
+    ("relaxed-simd", |f| &mut f.relaxed_simd),
+    ("custom-page-sizes", |f| &mut f.custom_page_sizes),
+];
+
+fn parse_features(arg: &str) -> Result<WasmFeatures> {
+    let mut ret = WasmFeatures::default();
+
     for part in arg.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
         let (enable, part) = if let Some(part) = part.strip_prefix("-") {
             (false, part)
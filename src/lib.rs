@@ -5,6 +5,9 @@ use std::fs::File;
 use std::io::{BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 
+#[cfg(feature = "wasm-mutate")]
+pub use wasm_mutate::translate;
+
 // Implements the verbosity flag for the CLI commands.
 #[derive(clap::Parser)]
 pub struct Verbosity {
@@ -41,6 +44,9 @@ pub struct InputOutput {
     /// If not provided or if this is `-` then stdin is read entirely and
     /// processed. Note that for most subcommands this input can either be a
     /// binary `*.wasm` file or a textual format `*.wat` file.
+    ///
+    /// When built with the `net` feature this may also be an `http://` or
+    /// `https://` URL, which is downloaded with `curl`.
     input: Option<PathBuf>,
 
     #[clap(flatten)]
@@ -64,6 +70,15 @@ pub enum Output<'a> {
 impl InputOutput {
     pub fn parse_input_wasm(&self) -> Result<Vec<u8>> {
         if let Some(path) = &self.input {
+            #[cfg(feature = "net")]
+            if let Some(url) = url(path) {
+                let bytes = fetch(url)?;
+                let bytes = wat::parse_bytes(&bytes).map_err(|mut e| {
+                    e.set_path(url);
+                    e
+                })?;
+                return Ok(bytes.into_owned());
+            }
             if path != Path::new("-") {
                 let bytes = wat::parse_file(path)?;
                 return Ok(bytes);
@@ -80,6 +95,33 @@ impl InputOutput {
         Ok(bytes.into_owned())
     }
 
+    /// Reads the raw contents of the input without interpreting them,
+    /// returning them along with the path they were read from if the input
+    /// isn't stdin.
+    pub fn read_input(&self) -> Result<(Vec<u8>, Option<&Path>)> {
+        if let Some(path) = &self.input {
+            #[cfg(feature = "net")]
+            if let Some(url) = url(path) {
+                return Ok((fetch(url)?, Some(path)));
+            }
+            if path != Path::new("-") {
+                let bytes = std::fs::read(path)
+                    .with_context(|| format!("failed to read `{}`", path.display()))?;
+                return Ok((bytes, Some(path)));
+            }
+        }
+        let mut stdin = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut stdin)
+            .context("failed to read <stdin>")?;
+        Ok((stdin, None))
+    }
+
+    /// Returns the path of the input file, or `None` if stdin is read.
+    pub fn input_path(&self) -> Option<&Path> {
+        self.input.as_deref().filter(|path| *path != Path::new("-"))
+    }
+
     pub fn output(&self, bytes: Output<'_>) -> Result<()> {
         self.output.output(bytes)
     }
@@ -129,6 +171,11 @@ impl OutputArg {
         Ok(())
     }
 
+    /// Returns the path passed with `-o`, if any.
+    pub fn output_path(&self) -> Option<&Path> {
+        self.output.as_deref()
+    }
+
     pub fn output_writer(&self) -> Result<Box<dyn Write>> {
         match &self.output {
             Some(output) => Ok(Box::new(BufWriter::new(File::create(&output)?))),
@@ -136,3 +183,319 @@ impl OutputArg {
         }
     }
 }
+
+/// The maximum time, in seconds, to spend downloading an input URL.
+#[cfg(feature = "net")]
+const FETCH_TIMEOUT_SECS: u32 = 60;
+
+/// The maximum size, in bytes, of an input downloaded from a URL.
+#[cfg(feature = "net")]
+const FETCH_MAX_BYTES: u64 = 1 << 30;
+
+/// Returns `path` as a URL if it's an `http://` or `https://` URL.
+#[cfg(feature = "net")]
+fn url(path: &Path) -> Option<&str> {
+    let path = path.to_str()?;
+    if path.starts_with("http://") || path.starts_with("https://") {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Downloads the contents of `url` with `curl`, failing if the download takes
+/// longer than `FETCH_TIMEOUT_SECS` or is larger than `FETCH_MAX_BYTES`.
+#[cfg(feature = "net")]
+fn fetch(url: &str) -> Result<Vec<u8>> {
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .arg("--max-time")
+        .arg(FETCH_TIMEOUT_SECS.to_string())
+        .arg("--max-filesize")
+        .arg(FETCH_MAX_BYTES.to_string())
+        .arg("--")
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to run `curl` to download the input")?;
+
+    // `--max-filesize` is only enforced if the size is known in advance, so
+    // the size of the download is also limited here.
+    let mut bytes = Vec::new();
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .take(FETCH_MAX_BYTES + 1)
+        .read_to_end(&mut bytes)
+        .with_context(|| format!("failed to download `{}`", url))?;
+    if bytes.len() as u64 > FETCH_MAX_BYTES {
+        drop(child.kill());
+        drop(child.wait());
+        bail!(
+            "failed to download `{}`: it's larger than the maximum of {} bytes",
+            url,
+            FETCH_MAX_BYTES
+        );
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "failed to download `{}`: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(bytes)
+}
+
+/// Deterministically expands `seed` into `len` bytes of input for generators
+/// such as `wasm-smith`.
+///
+/// This uses the SplitMix64 generator so that the bytes are the same on every
+/// platform and across releases.
+pub fn seed_bytes(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed;
+    let mut bytes = Vec::with_capacity(len + 8);
+    while bytes.len() < len {
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^= z >> 31;
+        bytes.extend_from_slice(&z.to_le_bytes());
+    }
+    bytes.truncate(len);
+    bytes
+}
+
+/// Returns the features needed to validate any core wasm module.
+///
+/// This is used to validate the output of subcommands which rewrite a module
+/// without changing which proposals it uses.
+#[cfg(feature = "wasmparser")]
+pub fn all_core_features() -> wasmparser::WasmFeatures {
+    wasmparser::WasmFeatures {
+        reference_types: true,
+        multi_value: true,
+        bulk_memory: true,
+        component_model: false,
+        custom_page_sizes: true,
+        simd: true,
+        relaxed_simd: true,
+        threads: true,
+        tail_call: true,
+        multi_memory: true,
+        exceptions: true,
+        memory64: true,
+        extended_const: true,
+        deterministic_only: false,
+        mutable_global: true,
+        saturating_float_to_int: true,
+        sign_extension: true,
+    }
+}
+
+/// The names of sections accepted by [`SectionFilter`].
+#[cfg(feature = "wasmparser")]
+const SECTION_NAMES: &[&str] = &[
+    "types",
+    "imports",
+    "functions",
+    "tables",
+    "memories",
+    "tags",
+    "globals",
+    "exports",
+    "start",
+    "elements",
+    "data-count",
+    "code",
+    "data",
+    "custom",
+    "core-instances",
+    "core-types",
+    "component-instances",
+    "component-aliases",
+    "component-types",
+    "canonical-functions",
+    "component-start",
+    "component-imports",
+    "component-exports",
+];
+
+// This is intended to be included in a struct as:
+//
+//      #[clap(flatten)]
+//      sections: wasm_tools::SectionFilter,
+//
+// and then `matches` is used to determine which payloads to process.
+#[cfg(feature = "wasmparser")]
+#[derive(clap::Parser, Clone)]
+pub struct SectionFilter {
+    /// Only include the specified comma-separated list of sections in the
+    /// output, such as `types,imports`.
+    ///
+    /// Nested modules and components are still printed, but only with the
+    /// sections specified.
+    #[clap(
+        long = "only-section",
+        alias = "only",
+        value_name = "SECTIONS",
+        value_delimiter = ',',
+        value_parser = clap::builder::PossibleValuesParser::new(SECTION_NAMES),
+    )]
+    only_sections: Vec<String>,
+}
+
+#[cfg(feature = "wasmparser")]
+impl SectionFilter {
+    /// Returns whether the section named `name`, one of [`SECTION_NAMES`], is
+    /// included by this filter.
+    pub fn includes(&self, name: &str) -> bool {
+        self.only_sections.is_empty() || self.only_sections.iter().any(|s| s == name)
+    }
+
+    /// Returns whether `payload` should be included in the output.
+    ///
+    /// Payloads which aren't sections, such as the header or end of a module,
+    /// are always included.
+    pub fn matches(&self, payload: &wasmparser::Payload<'_>) -> bool {
+        use wasmparser::Payload::*;
+        let name = match payload {
+            TypeSection(_) => "types",
+            ImportSection(_) => "imports",
+            FunctionSection(_) => "functions",
+            TableSection(_) => "tables",
+            MemorySection(_) => "memories",
+            TagSection(_) => "tags",
+            GlobalSection(_) => "globals",
+            ExportSection(_) => "exports",
+            StartSection { .. } => "start",
+            ElementSection(_) => "elements",
+            DataCountSection { .. } => "data-count",
+            CodeSectionStart { .. } | CodeSectionEntry(_) => "code",
+            DataSection(_) => "data",
+            CustomSection(_) => "custom",
+            InstanceSection(_) => "core-instances",
+            CoreTypeSection(_) => "core-types",
+            ComponentInstanceSection(_) => "component-instances",
+            ComponentAliasSection(_) => "component-aliases",
+            ComponentTypeSection(_) => "component-types",
+            ComponentCanonicalSection(_) => "canonical-functions",
+            ComponentStartSection(_) => "component-start",
+            ComponentImportSection(_) => "component-imports",
+            ComponentExportSection(_) => "component-exports",
+            Version { .. }
+            | ModuleSection { .. }
+            | ComponentSection { .. }
+            | UnknownSection { .. }
+            | End(_) => return true,
+        };
+        self.includes(name)
+    }
+}
+
+// This is intended to be included by subcommands which move code around as:
+//
+//      #[clap(flatten)]
+//      debug: wasm_tools::DebugInfo,
+//
+// and then `check` is used on the transformed module.
+#[cfg(all(feature = "wasm-encoder", feature = "wasmparser"))]
+#[derive(clap::Parser)]
+pub struct DebugInfo {
+    /// Fail instead of emitting stale DWARF debug information.
+    ///
+    /// The `.debug_*` custom sections of the input are copied to the output
+    /// as-is, and their code offsets are wrong if the code section changed.
+    /// By default a warning is printed in this case, and with this flag it's
+    /// an error instead.
+    #[clap(long, conflicts_with = "strip_debug")]
+    preserve_debug: bool,
+
+    /// Remove DWARF debug information, the `.debug_*` custom sections, from
+    /// the output if the code section changed and it's stale.
+    #[clap(long)]
+    strip_debug: bool,
+}
+
+#[cfg(all(feature = "wasm-encoder", feature = "wasmparser"))]
+impl DebugInfo {
+    /// Checks the DWARF debug information copied from the core wasm module
+    /// `input` to its transformed version `output`.
+    ///
+    /// DWARF refers to code by offsets from the start of the code section, so
+    /// it's still accurate if the code section is unchanged and `output` is
+    /// returned as-is. Otherwise the debug sections are removed, a warning is
+    /// printed, or an error is returned, depending on the flags passed.
+    pub fn check(&self, input: &[u8], output: Vec<u8>) -> Result<Vec<u8>> {
+        if !has_debug_sections(input)? || code_section(input)? == code_section(&output)? {
+            return Ok(output);
+        }
+        if self.strip_debug {
+            return strip_debug_sections(&output);
+        }
+        if self.preserve_debug {
+            bail!(
+                "the code section was changed and the DWARF debug information in the \
+                 `.debug_*` sections can't be updated to match, pass `--strip-debug` \
+                 to remove it instead"
+            );
+        }
+        log::warn!(
+            "the code section was changed and the DWARF debug information in the `.debug_*` \
+             sections is stale, pass `--strip-debug` to remove it"
+        );
+        Ok(output)
+    }
+}
+
+#[cfg(all(feature = "wasm-encoder", feature = "wasmparser"))]
+fn is_debug_section(payload: &wasmparser::Payload<'_>) -> bool {
+    matches!(payload, wasmparser::Payload::CustomSection(c) if c.name().starts_with(".debug_"))
+}
+
+#[cfg(all(feature = "wasm-encoder", feature = "wasmparser"))]
+fn has_debug_sections(wasm: &[u8]) -> Result<bool> {
+    for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+        if is_debug_section(&payload?) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Returns the contents of the code section of `wasm`, if it has one.
+#[cfg(all(feature = "wasm-encoder", feature = "wasmparser"))]
+fn code_section(wasm: &[u8]) -> Result<Option<&[u8]>> {
+    for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+        if let wasmparser::Payload::CodeSectionStart { range, .. } = payload? {
+            return Ok(Some(&wasm[range]));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(all(feature = "wasm-encoder", feature = "wasmparser"))]
+fn strip_debug_sections(wasm: &[u8]) -> Result<Vec<u8>> {
+    let mut module = wasm_encoder::Module::new();
+    for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+        let payload = payload?;
+        if is_debug_section(&payload) {
+            continue;
+        }
+        if let Some((id, range)) = payload.as_section() {
+            module.section(&wasm_encoder::RawSection {
+                id,
+                data: &wasm[range],
+            });
+        }
+    }
+    Ok(module.finish())
+}
@@ -0,0 +1,2535 @@
+//! End-to-end tests of `wasm-tools` subcommands.
+//!
+//! Each test runs the compiled `wasm-tools` binary on inputs written to a
+//! temporary directory and inspects its output.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use tempfile::TempDir;
+
+struct Test {
+    dir: TempDir,
+}
+
+impl Test {
+    fn new() -> Test {
+        Test {
+            dir: TempDir::new().unwrap(),
+        }
+    }
+
+    /// Writes `contents` to a file named `name` in this test's directory.
+    fn file(&self, name: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.path(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.path().join(name)
+    }
+
+    /// Runs `wasm-tools` with `args`, asserting that it succeeds.
+    fn run(&self, args: &[&str]) -> Output {
+        let output = self.run_unchecked(args);
+        assert!(
+            output.status.success(),
+            "`wasm-tools {}` failed:\n{}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr),
+        );
+        output
+    }
+
+    fn run_unchecked(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_wasm-tools"))
+            .args(args)
+            .current_dir(self.dir.path())
+            .output()
+            .unwrap()
+    }
+
+    /// Runs `wasm-tools` with `args` and `stdin` written to its stdin.
+    fn run_with_stdin(&self, args: &[&str], stdin: &[u8]) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_wasm-tools"))
+            .args(args)
+            .current_dir(self.dir.path())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(stdin).unwrap();
+        child.wait_with_output().unwrap()
+    }
+}
+
+fn read(path: impl AsRef<Path>) -> Vec<u8> {
+    std::fs::read(path).unwrap()
+}
+
+#[test]
+fn normalize_leb() {
+    let t = Test::new();
+    let padded = wat::parse_str(
+        r#"
+            (module binary
+                "\00asm" "\01\00\00\00"
+
+                "\01"                       ;; type section
+                "\84\80\80\80\00"           ;; size of section (padded)
+                "\01\60\00\00"              ;; one function type, no params/results
+
+                "\03"                       ;; function section
+                "\85\80\80\80\00"           ;; size of section (padded)
+                "\81\00"                    ;; one function (padded)
+                "\80\80\00"                 ;; type 0 (padded)
+
+                "\0a"                       ;; code section
+                "\8a\80\80\80\00"           ;; size of section (padded)
+                "\01"                       ;; one function
+                "\87\00"                    ;; size of function (padded)
+                "\00"                       ;; no locals
+                "\41\80\80\00"              ;; i32.const 0 (padded)
+                "\1a"                       ;; drop
+                "\0b"                       ;; end
+            )
+        "#,
+    )
+    .unwrap();
+    let input = t.file("padded.wasm", &padded);
+    let output = t.run(&[
+        "normalize-leb",
+        input.to_str().unwrap(),
+        "-o",
+        "minimal.wasm",
+    ]);
+
+    let expected = wat::parse_str("(module (func i32.const 0 drop))").unwrap();
+    assert_eq!(read(t.path("minimal.wasm")), expected);
+    let saved = padded.len() - expected.len();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(&format!("({} bytes saved)", saved)),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn normalize_leb_is_identity_on_minimal_modules() {
+    let t = Test::new();
+    let wasm = wat::parse_str(
+        r#"
+            (module
+                (import "env" "f" (func $f (param i32)))
+                (memory 1)
+                (global (mut i64) (i64.const -1))
+                (table 2 funcref)
+                (elem (i32.const 0) $f $g)
+                (func $g (export "g") (local i32 f64)
+                    i32.const 100000
+                    call $f)
+                (data (i32.const 8) "hello"))
+        "#,
+    )
+    .unwrap();
+    let input = t.file("input.wasm", &wasm);
+    t.run(&[
+        "normalize-leb",
+        input.to_str().unwrap(),
+        "-o",
+        "output.wasm",
+    ]);
+    assert_eq!(read(t.path("output.wasm")), wasm);
+}
+
+#[test]
+fn normalize_leb_flatten_locals() {
+    let t = Test::new();
+    let grouped = wat::parse_str(
+        r#"
+            (module binary
+                "\00asm" "\01\00\00\00"
+
+                "\01\05"                    ;; type section
+                "\01\60\00\01\7c"            ;; one function type, result f64
+
+                "\03\02\01\00"              ;; one function of type 0
+
+                "\0a\18"                    ;; code section
+                "\01"                        ;; one function
+                "\16"                        ;; size of function
+                "\06"                        ;; six groups of locals
+                "\01\7f" "\00\7c" "\01\7f"    ;; i32, no f64s, i32
+                "\01\7c" "\02\7c" "\01\7f"    ;; f64, f64 f64, i32
+                "\20\01" "\20\05" "\6a" "\1a" ;; (drop (i32.add (local.get 1) (local.get 5)))
+                "\20\04"                    ;; local.get 4
+                "\0b"                        ;; end
+            )
+        "#,
+    )
+    .unwrap();
+    let input = t.file("grouped.wasm", &grouped);
+    t.run(&[
+        "normalize-leb",
+        "--flatten-locals",
+        input.to_str().unwrap(),
+        "-o",
+        "flattened.wasm",
+    ]);
+
+    let output = read(t.path("flattened.wasm"));
+    wasmparser::Validator::new().validate_all(&output).unwrap();
+    let mut locals = Vec::new();
+    for payload in wasmparser::Parser::new(0).parse_all(&output) {
+        if let wasmparser::Payload::CodeSectionEntry(body) = payload.unwrap() {
+            for local in body.get_locals_reader().unwrap() {
+                locals.push(local.unwrap());
+            }
+        }
+    }
+    assert_eq!(
+        locals,
+        [
+            (2, wasmparser::ValType::I32),
+            (3, wasmparser::ValType::F64),
+            (1, wasmparser::ValType::I32),
+        ]
+    );
+
+    // The indices used in the body still refer to locals of the same types.
+    let expected = wat::parse_str(
+        r#"
+            (module
+                (func (result f64) (local i32 i32 f64 f64 f64 i32)
+                    (drop (i32.add (local.get 1) (local.get 5)))
+                    local.get 4))
+        "#,
+    )
+    .unwrap();
+    assert_eq!(output, expected);
+
+    // Without the flag the local declarations are kept as they were.
+    t.run(&[
+        "normalize-leb",
+        input.to_str().unwrap(),
+        "-o",
+        "unflattened.wasm",
+    ]);
+    assert_eq!(read(t.path("unflattened.wasm")), grouped);
+}
+
+#[test]
+fn parse_timings() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"
+            (module
+                (memory 1)
+                (func (export "f") (result i32) i32.const 1)
+                (@custom "hello" "world"))
+        "#,
+    );
+    let output = t.run(&[
+        "parse",
+        "--timings",
+        input.to_str().unwrap(),
+        "-o",
+        "output.wasm",
+    ]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let rows = stderr
+        .lines()
+        .map(|line| line.split_whitespace().next().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(rows[0], "section", "{}", stderr);
+    assert_eq!(rows[rows.len() - 1], "total", "{}", stderr);
+    for section in ["type", "function", "memory", "export", "code", "custom"] {
+        assert!(rows.contains(&section), "{}", stderr);
+    }
+    assert!(stderr.contains("custom \"hello\""), "{}", stderr);
+    assert_eq!(
+        read(t.path("output.wasm")),
+        wat::parse_file(&input).unwrap()
+    );
+}
+
+#[test]
+fn parse_count() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        "(module (func (export \"f\") (result i32) i32.const 1))",
+    );
+    let output = t.run(&["parse", "--count", "5", input.to_str().unwrap()]);
+    assert_eq!(output.stdout, wat::parse_file(&input).unwrap());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(" bytes 5 times: min "), "{}", stderr);
+    assert!(stderr.contains(" MB/s)"), "{}", stderr);
+
+    let output = t.run_unchecked(&["parse", "--count", "0", input.to_str().unwrap()]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn pack_data_merges_adjacent_segments() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"
+            (module
+                (memory 1)
+                (data (i32.const 0) "abc")
+                (data (i32.const 3) "def")
+                (data (i32.const 100) "\00\00\00\00"))
+        "#,
+    );
+    t.run(&["pack-data", input.to_str().unwrap(), "-o", "output.wasm"]);
+    let output = read(t.path("output.wasm"));
+    wasmparser::Validator::new().validate_all(&output).unwrap();
+    let expected = wat::parse_str(
+        r#"
+            (module
+                (memory 1)
+                (data (i32.const 0) "abcdef"))
+        "#,
+    )
+    .unwrap();
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn pack_data_remaps_passive_segments() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"
+            (module
+                (import "env" "memory" (memory 1))
+                (data $a "hello")
+                (data (i32.const 0) "\00\00")
+                (data (i32.const 8) "xy")
+                (data (i32.const 6) "uvw")
+                (data $b "hello")
+                (data $c "world")
+                (func
+                    (memory.init $b (i32.const 0) (i32.const 0) (i32.const 5))
+                    (memory.init $a (i32.const 0) (i32.const 0) (i32.const 5))
+                    (memory.init $c (i32.const 0) (i32.const 0) (i32.const 5))
+                    (data.drop $c)))
+        "#,
+    );
+    t.run(&["pack-data", input.to_str().unwrap(), "-o", "output.wasm"]);
+    let output = read(t.path("output.wasm"));
+    wasmparser::Validator::new().validate_all(&output).unwrap();
+
+    // The zeros aren't removed since the memory is imported, the overlapping
+    // segments are merged with the later one taking precedence, and `$b` is
+    // replaced with `$a`.
+    let expected = wat::parse_str(
+        r#"
+            (module
+                (import "env" "memory" (memory 1))
+                (data $a "hello")
+                (data (i32.const 0) "\00\00")
+                (data (i32.const 6) "uvwy")
+                (data $c "world")
+                (func
+                    (memory.init $a (i32.const 0) (i32.const 0) (i32.const 5))
+                    (memory.init $a (i32.const 0) (i32.const 0) (i32.const 5))
+                    (memory.init $c (i32.const 0) (i32.const 0) (i32.const 5))
+                    (data.drop $c)))
+        "#,
+    )
+    .unwrap();
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn pack_elements_merges_adjacent_segments() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"
+            (module
+                (table 10 funcref)
+                (func $a)
+                (func $b)
+                (elem (i32.const 0) func $a $b)
+                (elem (i32.const 2) func $b $a)
+                (elem (i32.const 5) funcref (ref.null func) (ref.null func))
+                (elem (i32.const 8) func))
+        "#,
+    );
+    t.run(&[
+        "pack-elements",
+        input.to_str().unwrap(),
+        "-o",
+        "output.wasm",
+    ]);
+    let output = read(t.path("output.wasm"));
+    wasmparser::Validator::new().validate_all(&output).unwrap();
+    let expected = wat::parse_str(
+        r#"
+            (module
+                (table 10 funcref)
+                (func $a)
+                (func $b)
+                (elem (i32.const 0) func $a $b $b $a))
+        "#,
+    )
+    .unwrap();
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn pack_elements_remaps_passive_segments() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"
+            (module
+                (import "env" "table" (table 4 funcref))
+                (func $f)
+                (elem $empty func)
+                (elem (i32.const 0) funcref (ref.null func))
+                (elem (i32.const 1) func $f)
+                (elem (i32.const 2) funcref (ref.func $f))
+                (elem $a func $f)
+                (func
+                    (table.init $a (i32.const 0) (i32.const 0) (i32.const 1))
+                    (elem.drop $a)))
+        "#,
+    );
+    t.run(&[
+        "pack-elements",
+        input.to_str().unwrap(),
+        "-o",
+        "output.wasm",
+    ]);
+    let output = read(t.path("output.wasm"));
+    wasmparser::Validator::new().validate_all(&output).unwrap();
+
+    // The null isn't removed since the table is imported, but it's merged
+    // with the following segments using expressions since some of them use
+    // expressions, and `$a` is renumbered after removing the empty segment.
+    let expected = wat::parse_str(
+        r#"
+            (module
+                (import "env" "table" (table 4 funcref))
+                (func $f)
+                (elem (i32.const 0) funcref (ref.null func) (ref.func $f) (ref.func $f))
+                (elem $a func $f)
+                (func
+                    (table.init $a (i32.const 0) (i32.const 0) (i32.const 1))
+                    (elem.drop $a)))
+        "#,
+    )
+    .unwrap();
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn pack_debug_info() {
+    let t = Test::new();
+    let has_debug_info = |wasm: &[u8]| {
+        wasmparser::Parser::new(0).parse_all(wasm).any(|p| {
+            matches!(p.unwrap(), wasmparser::Payload::CustomSection(c) if c.name() == ".debug_info")
+        })
+    };
+
+    // Both inputs have their code changed by renumbering the second segment.
+    let data = t.file(
+        "data.wat",
+        r#"
+            (module
+                (memory 1)
+                (data $a "hello")
+                (data $b "hello")
+                (func (memory.init $b (i32.const 0) (i32.const 0) (i32.const 5)))
+                (@custom ".debug_info" "\01\02\03\04"))
+        "#,
+    );
+    let elements = t.file(
+        "elements.wat",
+        r#"
+            (module
+                (table 1 funcref)
+                (func $f)
+                (elem $empty func)
+                (elem $a func $f)
+                (func (table.init $a (i32.const 0) (i32.const 0) (i32.const 1)))
+                (@custom ".debug_info" "\01\02\03\04"))
+        "#,
+    );
+    for (subcommand, input) in [("pack-data", data), ("pack-elements", elements)] {
+        let input = input.to_str().unwrap();
+        t.run(&[subcommand, input, "--strip-debug", "-o", "stripped.wasm"]);
+        let stripped = read(t.path("stripped.wasm"));
+        wasmparser::validate(&stripped).unwrap();
+        assert!(!has_debug_info(&stripped), "{}", subcommand);
+
+        let output = t.run_unchecked(&[subcommand, input, "--preserve-debug"]);
+        assert!(!output.status.success(), "{}", subcommand);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("DWARF debug information"), "{}", stderr);
+    }
+}
+
+const ONLY_SECTION_INPUT: &str = r#"
+    (module
+        (type (func))
+        (import "a" "b" (func $f))
+        (func $g call $f)
+        (memory 1)
+        (export "g" (func $g))
+        (data (i32.const 0) "hi"))
+"#;
+
+#[test]
+fn print_only_section() {
+    let t = Test::new();
+    let input = t.file("input.wat", ONLY_SECTION_INPUT);
+    let output = t.run(&[
+        "print",
+        input.to_str().unwrap(),
+        "--only-section",
+        "types,imports",
+    ]);
+    let wat = String::from_utf8(output.stdout).unwrap();
+    assert!(wat.contains("(type (;0;) (func))"), "{}", wat);
+    assert!(wat.contains("(import \"a\" \"b\""), "{}", wat);
+    for omitted in ["(func $g", "(memory", "(export", "(data"] {
+        assert!(!wat.contains(omitted), "{}", wat);
+    }
+
+    // Indices and names of items in omitted sections are still tracked.
+    let output = t.run(&["print", input.to_str().unwrap(), "--only", "code"]);
+    let wat = String::from_utf8(output.stdout).unwrap();
+    assert!(wat.contains("(func $g (;1;) (type 0)"), "{}", wat);
+    assert!(wat.contains("call $f"), "{}", wat);
+    assert!(!wat.contains("(import"), "{}", wat);
+}
+
+#[test]
+fn dump_only_section() {
+    let t = Test::new();
+    let input = t.file("input.wat", ONLY_SECTION_INPUT);
+    let output = t.run(&[
+        "dump",
+        input.to_str().unwrap(),
+        "--only-section",
+        "imports,data",
+    ]);
+    let dump = String::from_utf8(output.stdout).unwrap();
+    assert!(dump.contains("version 1 (Module)"), "{}", dump);
+    assert!(dump.contains("import section"), "{}", dump);
+    assert!(dump.contains("data section"), "{}", dump);
+    for omitted in ["type section", "func section", "code section"] {
+        assert!(!dump.contains(omitted), "{}", dump);
+    }
+}
+
+#[test]
+fn only_section_rejects_unknown_sections() {
+    let t = Test::new();
+    let input = t.file("input.wat", ONLY_SECTION_INPUT);
+    for subcommand in ["print", "dump"] {
+        let output = t.run_unchecked(&[
+            subcommand,
+            input.to_str().unwrap(),
+            "--only-section",
+            "types,bogus",
+        ]);
+        assert!(!output.status.success());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("invalid value 'bogus'"), "{}", stderr);
+    }
+}
+
+const COMPONENT_WITH_CORE_MODULES: &str = r#"
+    (component
+        (core module (func (export "f")))
+        (component
+            (core module (memory 1)))
+        (core module))
+"#;
+
+#[test]
+fn component_extract_core() {
+    let t = Test::new();
+    let input = t.file("input.wat", COMPONENT_WITH_CORE_MODULES);
+    let output = t.run(&[
+        "component",
+        "extract-core",
+        input.to_str().unwrap(),
+        "--list",
+    ]);
+    let list = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(list.lines().count(), 3, "{}", list);
+    assert!(
+        list.lines().nth(1).unwrap().ends_with("(depth 1)"),
+        "{}",
+        list
+    );
+
+    t.run(&[
+        "component",
+        "extract-core",
+        input.to_str().unwrap(),
+        "--index",
+        "1",
+        "-o",
+        "core1.wasm",
+    ]);
+    let core = read(t.path("core1.wasm"));
+    wasmparser::Validator::new().validate_all(&core).unwrap();
+    assert_eq!(core, wat::parse_str("(module (memory 1))").unwrap());
+
+    let output = t.run_unchecked(&[
+        "component",
+        "extract-core",
+        input.to_str().unwrap(),
+        "--index",
+        "3",
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("out of bounds"), "{}", stderr);
+}
+
+#[test]
+fn component_types() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"
+        (component
+          (type $point (record (field "x" s32) (field "y" s32)))
+          (import "host" (instance $host
+            (export "log" (func (param "msg" string)))
+            (export "point" (type (eq $point)))
+            (export "now" (func (result u64)))
+            (export "parse" (func (param "input" (list u8)) (result (result u32 (error string)))))
+          ))
+          (import "config" (value $config string))
+          (import "plugin" (component
+            (import "env" (core module (import "a" "b" (func (param i32) (result i64))) (export "m" (memory 1))))
+            (type $shape (variant (case "circle" float32) (case "none")))
+            (export "shape" (type (eq $shape)))
+          ))
+          (core module $m (func (export "f") (param i32) (result i32) local.get 0))
+          (core instance $i (instantiate $m))
+          (func $f (param "x" u32) (result u32) (canon lift (core func $i "f")))
+          (export "run" (func $f))
+          (export "point" (type $point))
+          (type $flags (flags "read" "write"))
+          (export "flags" (type $flags))
+          (export "config" (value $config))
+        )
+        "#,
+    );
+    let output = t.run(&["component", "types", input.to_str().unwrap()]);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        r#"(import "host" (instance
+  (export "log" (func (param "msg" string)))
+  (export "point" (type (eq (record (field "x" s32) (field "y" s32)))))
+  (export "now" (func (result u64)))
+  (export "parse" (func (param "input" (list u8)) (result (result u32 (error string)))))
+))
+(import "config" (value string))
+(import "plugin" (component
+  (import "env" (core module
+    (import "a" "b" (func (param i32) (result i64)))
+    (export "m" (memory 1))
+  ))
+  (export "shape" (type (eq (variant (case "circle" float32) (case "none")))))
+))
+(export "run" (func (param "x" u32) (result u32)))
+(export "point" (type (eq (record (field "x" s32) (field "y" s32)))))
+(export "flags" (type (eq (flags "read" "write"))))
+(export "config" (value string))
+"#
+    );
+
+    let module = t.file("module.wat", "(module)");
+    let output = t.run_unchecked(&["component", "types", module.to_str().unwrap()]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn component_graph() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"
+        (component
+          (import "host" (instance $host (export "now" (func (result u64)))))
+          (core module $a (func (export "f") (result i32) i32.const 1))
+          (core module $b
+            (import "a" "f" (func (result i32)))
+            (import "host" "now" (func (result i64)))
+            (func (export "g") (result i32) call 0))
+          (core instance $a (instantiate $a))
+          (alias export $host "now" (func $now))
+          (core func $now (canon lower (func $now)))
+          (core instance $host (export "now" (func $now)))
+          (core instance $b (instantiate $b (with "a" (instance $a)) (with "host" (instance $host))))
+          (func $g (result u32) (canon lift (core func $b "g")))
+          (component $c
+            (import "g" (func $g (result u32)))
+            (export "g" (func $g)))
+          (instance $c (instantiate $c (with "g" (func $g))))
+          (export "run" (func $c "g"))
+        )
+        "#,
+    );
+    let output = t.run(&["component", "graph", input.to_str().unwrap()]);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        r#"digraph component {
+  n0 [label="import \"host\"", shape=plaintext];
+  n1 [label="module 0", shape=box];
+  n2 [label="module 1", shape=box];
+  n3 [label="core instance 0", shape=ellipse];
+  n4 [label="core instance 1", shape=ellipse];
+  n5 [label="core instance 2", shape=ellipse];
+  n6 [label="component 0", shape=box];
+  n7 [label="instance 1", shape=ellipse];
+  n8 [label="export \"run\"", shape=plaintext];
+  n1 -> n3 [style=dashed];
+  n0 -> n4 [label="now"];
+  n2 -> n5 [style=dashed];
+  n3 -> n5 [label="a"];
+  n4 -> n5 [label="host"];
+  n6 -> n7 [style=dashed];
+  n5 -> n7 [label="g"];
+  n7 -> n8 [label="g as run"];
+}
+"#
+    );
+}
+
+/// Returns the names of the entries in the cache directory `dir`.
+fn cache_entries(dir: &Path) -> Vec<String> {
+    let mut entries = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    entries.sort();
+    entries
+}
+
+#[test]
+fn validate_cache_hits_and_misses() {
+    let t = Test::new();
+    let input = t.file("input.wat", "(module (func (export \"f\")))");
+    let input = input.to_str().unwrap();
+    let validate = |args: &[&str]| {
+        let mut all = vec!["validate", "-v", "--cache-dir", "cache", input];
+        all.extend_from_slice(args);
+        let output = t.run(&all);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        stderr.contains("validation skipped")
+    };
+
+    // The first validation misses and records an entry, which the second
+    // then hits.
+    assert!(!validate(&[]));
+    let entries = cache_entries(&t.path("cache"));
+    assert_eq!(entries.len(), 1);
+    assert!(validate(&[]));
+
+    // A different set of features is a different entry.
+    assert!(!validate(&["--features=-simd"]));
+    assert_eq!(cache_entries(&t.path("cache")).len(), 2);
+    assert!(validate(&["--features=-simd"]));
+
+    // `--no-cache` always validates, rewriting the existing entry.
+    assert!(!validate(&["--no-cache"]));
+    assert_eq!(cache_entries(&t.path("cache")).len(), 2);
+    assert!(cache_entries(&t.path("cache")).contains(&entries[0]));
+}
+
+#[test]
+fn validate_cache_ignores_failures() {
+    let t = Test::new();
+    let input = t.file("input.wat", "(module (func (result i32)))");
+    for _ in 0..2 {
+        let output =
+            t.run_unchecked(&["validate", "--cache-dir", "cache", input.to_str().unwrap()]);
+        assert!(!output.status.success());
+    }
+    assert!(!t.path("cache").exists());
+}
+
+#[test]
+fn validate_limits() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        "(module (func) (func) (global i32 (i32.const 0)))",
+    );
+    let input = input.to_str().unwrap();
+    t.run(&[
+        "validate",
+        "--limit",
+        "functions=2",
+        "--limit",
+        "globals=1",
+        input,
+    ]);
+
+    let output = t.run_unchecked(&["validate", "--limit", "functions=1", input]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("functions count exceeds the configured limit of 1"),
+        "{}",
+        stderr
+    );
+
+    for arg in ["functions", "functions=x", "locals=1"] {
+        let output = t.run_unchecked(&["validate", "--limit", arg, input]);
+        assert!(!output.status.success(), "{}", arg);
+    }
+
+    // Successes are only cached for the limits they were checked against.
+    t.run(&["validate", "--cache-dir", "cache", input]);
+    let output = t.run_unchecked(&[
+        "validate",
+        "--cache-dir",
+        "cache",
+        "--limit",
+        "functions=1",
+        input,
+    ]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn validate_forbid() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"(module
+            (memory 1)
+            (func (result i32) (memory.grow (i32.const 1)))
+            (func (result f32) (f32.add (f32.const 1) (f32.const 2)))
+        )"#,
+    );
+    let input = input.to_str().unwrap();
+    t.run(&["validate", "--forbid", "i32.add,f64.add", input]);
+
+    let output = t.run_unchecked(&["validate", "--forbid", "i32.add,f32.add", input]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("func 1 failed to validate"), "{}", stderr);
+    assert!(
+        stderr.contains("forbidden operator `f32.add` found in function 1 (at offset"),
+        "{}",
+        stderr
+    );
+
+    let output = t.run_unchecked(&["validate", "--forbid", "memory.grow", input]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("forbidden operator `memory.grow` found in function 0"),
+        "{}",
+        stderr
+    );
+
+    let output = t.run_unchecked(&["validate", "--forbid", "i32.frobnicate", input]);
+    assert!(!output.status.success());
+
+    // Successes are only cached for the operators they were checked against.
+    t.run(&["validate", "--cache-dir", "cache", input]);
+    let output = t.run_unchecked(&[
+        "validate",
+        "--cache-dir",
+        "cache",
+        "--forbid",
+        "f32.add",
+        input,
+    ]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn validate_wasi() {
+    let t = Test::new();
+    let valid = t.file(
+        "valid.wat",
+        r#"(module
+            (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+            (import "env" "foo" (func (param i64)))
+        )"#,
+    );
+    t.run(&["validate", "--wasi", valid.to_str().unwrap()]);
+
+    let invalid = t.file(
+        "invalid.wat",
+        r#"(module
+            (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "clock_time_get" (func (param i32 i64 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "not_a_function" (func))
+        )"#,
+    );
+    let invalid = invalid.to_str().unwrap();
+    t.run(&["validate", invalid]);
+    let output = t.run_unchecked(&["validate", "--wasi", invalid]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(
+            "import `wasi_snapshot_preview1::fd_write` has type \
+             `(func (param i32 i32 i32) (result i32))` but WASI expects \
+             `(func (param i32 i32 i32 i32) (result i32))`"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("unknown WASI import `wasi_snapshot_preview1::not_a_function`"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("clock_time_get"), "{}", stderr);
+}
+
+#[test]
+fn validate_allowed_imports() {
+    let t = Test::new();
+    let allowed = t.file(
+        "allowed.json",
+        r#"[
+            { "module": "env", "name": "log", "type": "(func (param i32))" },
+            { "module": "env", "name": "memory", "type": "(memory 1 16)" },
+            { "module": "env", "name": "counter", "type": "(global (mut i64))" }
+        ]"#,
+    );
+    let allowed = allowed.to_str().unwrap();
+    let valid = t.file(
+        "valid.wat",
+        r#"(module
+            (import "env" "log" (func $log (param i32)))
+            (import "env" "memory" (memory 1 16))
+        )"#,
+    );
+    t.run(&[
+        "validate",
+        "--allowed-imports",
+        allowed,
+        valid.to_str().unwrap(),
+    ]);
+
+    let invalid = t.file(
+        "invalid.wat",
+        r#"(module
+            (import "env" "log" (func (param i64)))
+            (import "env" "memory" (memory 1 16))
+            (import "env" "counter" (global i64))
+            (import "env" "exit" (func))
+        )"#,
+    );
+    let invalid = invalid.to_str().unwrap();
+    t.run(&["validate", invalid]);
+    let output = t.run_unchecked(&["validate", "--allowed-imports", allowed, invalid]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(
+            "import `env::log` has type `(func (param i64))` but only \
+             `(func (param i32))` is allowed"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains(
+            "import `env::counter` has type `(global i64)` but only \
+             `(global (mut i64))` is allowed"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("import `env::exit` of type `(func)` isn't allowed"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("env::memory"), "{}", stderr);
+
+    let page_size = t.file(
+        "page-size.wat",
+        r#"(module (import "env" "memory" (memory 1 16 (pagesize 1))))"#,
+    );
+    let output = t.run_unchecked(&[
+        "validate",
+        "--features",
+        "custom-page-sizes",
+        "--allowed-imports",
+        allowed,
+        page_size.to_str().unwrap(),
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(
+            "import `env::memory` has type `(memory 1 16 (pagesize 1))` but \
+             only `(memory 1 16)` is allowed"
+        ),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn validate_branch_hints() {
+    let t = Test::new();
+    // The body of function 1 has `if` at offset 3 and `br_if` at offset 9.
+    let module = |hints: &str| {
+        format!(
+            r#"(module
+                (import "env" "f" (func))
+                (@custom "metadata.code.branch_hint" (before code) "{}")
+                (func (param i32)
+                    local.get 0
+                    if
+                        nop
+                    end
+                    i32.const 0
+                    br_if 0)
+                (func (param i32)
+                    local.get 0
+                    drop)
+            )"#,
+            hints
+        )
+    };
+    let valid = t.file("valid.wat", module(r"\01\01\02\03\01\01\09\01\00"));
+    t.run(&["validate", "--branch-hints", valid.to_str().unwrap()]);
+
+    // Hints for the imported function 0, for the middle of a `local.get` of
+    // function 1, and for a `local.get` along with an invalid hint value in
+    // function 2.
+    let stale = t.file(
+        "stale.wat",
+        module(concat!(
+            r"\03",
+            r"\00\01\00\01\00",
+            r"\01\01\02\01\01",
+            r"\02\02\01\01\00\02\01\02",
+        )),
+    );
+    let stale = stale.to_str().unwrap();
+    t.run(&["validate", stale]);
+    let output = t.run_unchecked(&["validate", "--branch-hints", stale]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    for error in [
+        "func 0 has branch hints but is imported",
+        "func 1 at offset 0x2: branch hint doesn't point at an instruction",
+        "func 2 at offset 0x1: branch hint points at `local.get` instead of `br_if` or `if`",
+        "func 2 at offset 0x2: branch hint has invalid value 2",
+    ] {
+        assert!(stderr.contains(error), "{}", stderr);
+    }
+
+    // A memory64 offset of 2**32 before the hinted `if` at offset 10.
+    let memory64 = t.file(
+        "memory64.wat",
+        r#"(module
+            (@custom "metadata.code.branch_hint" (before code) "\01\00\01\0a\01\01")
+            (memory i64 1)
+            (func
+                i64.const 0
+                i32.load offset=0x100000000
+                if
+                    nop
+                end)
+        )"#,
+    );
+    t.run(&[
+        "validate",
+        "--features",
+        "memory64",
+        "--branch-hints",
+        memory64.to_str().unwrap(),
+    ]);
+}
+
+#[test]
+fn validate_deterministic() {
+    let t = Test::new();
+    let integer = t.file(
+        "integer.wat",
+        r#"(module
+            (memory 1)
+            (func (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.add
+                i32.load)
+            (func (param f32) (result f32)
+                local.get 0
+                f32.neg)
+        )"#,
+    );
+    t.run(&["validate", "--deterministic", integer.to_str().unwrap()]);
+
+    let float = t.file(
+        "float.wat",
+        r#"(module
+            (import "env" "f" (func))
+            (memory 1)
+            (func (param f32 f64) (result f32)
+                local.get 0
+                f64.promote_f32
+                drop
+                local.get 0
+                local.get 0
+                f32.add)
+            (func (param v128) (result v128)
+                i32.const 1
+                memory.grow
+                drop
+                local.get 0
+                local.get 0
+                f32x4.mul)
+        )"#,
+    );
+    let float = float.to_str().unwrap();
+    t.run(&["validate", float]);
+    let output = t.run_unchecked(&["validate", "--deterministic", float]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    for expected in [
+        "func 1 at offset 0x35: `f64.promote_f32` may produce a NaN",
+        "func 1 at offset 0x3b: `f32.add` may produce a NaN",
+        "func 2 at offset 0x41: `memory.grow` may fail",
+        "func 2 at offset 0x48: `f32x4.mul` may produce a NaN",
+    ] {
+        assert!(stderr.contains(expected), "{}", stderr);
+    }
+
+    let memory64 = t.file(
+        "memory64.wat",
+        r#"(module
+            (memory i64 1)
+            (func (result i32)
+                i64.const 0
+                i32.load offset=0x100000000)
+        )"#,
+    );
+    t.run(&[
+        "validate",
+        "--features",
+        "memory64",
+        "--deterministic",
+        memory64.to_str().unwrap(),
+    ]);
+}
+
+#[test]
+fn validate_timeout() {
+    let t = Test::new();
+    let small = t.file("small.wat", "(module (func))");
+    t.run(&["validate", "--timeout", "1m", small.to_str().unwrap()]);
+
+    let mut wat = String::from("(module");
+    for _ in 0..50_000 {
+        wat.push_str("(func (param i32) (result i32) local.get 0 i32.const 1 i32.add)");
+    }
+    wat.push(')');
+    let large = t.file("large.wat", &wat);
+    let large = large.to_str().unwrap();
+    t.run(&["validate", large]);
+    let output = t.run_unchecked(&["validate", "--timeout", "1ms", large]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("validation timed out after 1ms"),
+        "{}",
+        stderr
+    );
+
+    let output = t.run_unchecked(&["validate", "--timeout", "5h", large]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("unknown unit `h`"), "{}", stderr);
+}
+
+#[test]
+fn validate_serve() {
+    fn frame(contents: &[u8]) -> Vec<u8> {
+        let mut frame = (contents.len() as u32).to_le_bytes().to_vec();
+        frame.extend_from_slice(contents);
+        frame
+    }
+
+    let t = Test::new();
+    let mut stdin = Vec::new();
+    stdin.extend(frame(&wat::parse_str("(module (func))").unwrap()));
+    stdin.extend(frame(b"(module (func (result i32)))"));
+    stdin.extend(frame(&[0; 100]));
+    stdin.extend(frame(b"(module)"));
+    stdin.extend(frame(b"not wasm"));
+    let output = t.run_with_stdin(&["validate", "--serve", "--max-frame-size", "64"], &stdin);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let mut results = Vec::new();
+    let mut stdout = &output.stdout[..];
+    while !stdout.is_empty() {
+        let status = stdout[0];
+        let len = u32::from_le_bytes(stdout[1..5].try_into().unwrap()) as usize;
+        let message = String::from_utf8(stdout[5..][..len].to_vec()).unwrap();
+        stdout = &stdout[5 + len..];
+        results.push((status, message));
+    }
+    assert_eq!(results.len(), 5, "{:?}", results);
+    assert_eq!(results[0], (0, String::new()));
+    assert_eq!(results[1].0, 1);
+    assert!(results[1].1.contains("type mismatch"), "{}", results[1].1);
+    assert_eq!(results[2].0, 2);
+    assert!(
+        results[2]
+            .1
+            .contains("frame 2 is 100 bytes which exceeds the maximum of 64"),
+        "{}",
+        results[2].1
+    );
+    assert_eq!(results[3], (0, String::new()));
+    assert_eq!(results[4].0, 1);
+
+    // A frame cut short by the end of the input is an error.
+    let output = t.run_with_stdin(&["validate", "--serve"], &[1, 0]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn validate_json() {
+    let t = Test::new();
+    let valid = t.file("valid.wat", "(module (func))");
+    let output = t.run(&[
+        "validate",
+        "--json",
+        "--features=-all,mutable-global,simd",
+        valid.to_str().unwrap(),
+    ]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "valid": true,
+            "features": ["simd", "mutable-global"],
+        })
+    );
+
+    let invalid = t.file(
+        "invalid.wasm",
+        wat::parse_str("(module (func (result i32)))").unwrap(),
+    );
+    let output = t.run_unchecked(&["validate", "--json", invalid.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(output.stderr.is_empty());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["valid"], false);
+    let message = json["error"]["message"].as_str().unwrap();
+    assert!(message.contains("func 0 failed to validate"), "{}", message);
+    assert!(message.contains("type mismatch"), "{}", message);
+    assert_eq!(json["error"]["offset"], 24);
+    assert!(json["features"]
+        .as_array()
+        .unwrap()
+        .contains(&"sign-extension".into()));
+}
+
+#[test]
+fn validate_summary() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"(module
+            (import "env" "log" (func (param i32)))
+            (memory (export "memory") 1)
+            (func $start)
+            (func (export "extend") (param i32) (result i32)
+                local.get 0
+                i32.extend8_s)
+            (start $start)
+        )"#,
+    );
+    let input = input.to_str().unwrap();
+    let output = t.run(&["validate", "--summary", input]);
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "functions: 3
+imports: 1
+exports: 2
+memories: 1
+globals: 0
+tables: 0
+code size: 10 bytes
+start function: 1
+features used: sign-extension
+"
+    );
+
+    let output = t.run(&["validate", "--summary", "--json", input]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        json["summary"],
+        serde_json::json!({
+            "functions": 3,
+            "imports": 1,
+            "exports": 2,
+            "memories": 1,
+            "globals": 0,
+            "tables": 0,
+            "code_size": 10,
+            "start": 1,
+            "features_used": ["sign-extension"],
+        })
+    );
+
+    // Features used outside of instructions are found too.
+    let features = t.file(
+        "features.wat",
+        r#"(module
+            (memory 1 1 shared)
+            (global (export "g") (mut i32) (i32.const 0))
+            (func (result i32 i32)
+                i32.const 0
+                i32.const 1)
+        )"#,
+    );
+    let output = t.run(&[
+        "validate",
+        "--summary",
+        "--features=all",
+        features.to_str().unwrap(),
+    ]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("features used: threads, multi-value, mutable-global\n"),
+        "{}",
+        stderr
+    );
+
+    let invalid = t.file("invalid.wat", "(module (func (result i32)))");
+    let output = t.run_unchecked(&["validate", "--summary", invalid.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!stderr.contains("functions:"), "{}", stderr);
+}
+
+#[test]
+fn validate_mmap() {
+    let t = Test::new();
+    let valid = t.file("valid.wasm", wat::parse_str("(module (func))").unwrap());
+    t.run(&["validate", "--mmap", valid.to_str().unwrap()]);
+
+    let invalid = t.file(
+        "invalid.wasm",
+        wat::parse_str("(module (func (result i32)))").unwrap(),
+    );
+    let output = t.run_unchecked(&["validate", "--mmap", invalid.to_str().unwrap()]);
+    assert!(!output.status.success());
+
+    // The text format and stdin can't be mapped.
+    let text = t.file("input.wat", "(module)");
+    let output = t.run_unchecked(&["validate", "--mmap", text.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let output = t.run_unchecked(&["validate", "--mmap"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn metrics() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"
+            (module
+                (import "a" "b" (func))
+                (func $simple (param i32) (local i64 i64))
+                (func $branchy (param i32) (result i32)
+                    (block
+                        (block
+                            (br_table 0 1 1 (local.get 0))))
+                    (if (local.get 0) (then (return (i32.const 1))))
+                    (loop
+                        (br_if 0 (local.get 0)))
+                    i32.const 0))
+        "#,
+    );
+    let output = t.run(&[
+        "metrics",
+        input.to_str().unwrap(),
+        "--json",
+        "--sort-by",
+        "complexity",
+    ]);
+    let metrics: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        metrics,
+        serde_json::json!([
+            {
+                "index": 2,
+                "name": "branchy",
+                "instructions": 17,
+                // One path for each of the two distinct `br_table` targets,
+                // plus one for each of the `if` and `br_if`.
+                "complexity": 4,
+                "max_depth": 2,
+                "locals": 1,
+            },
+            {
+                "index": 1,
+                "name": "simple",
+                "instructions": 1,
+                "complexity": 1,
+                "max_depth": 0,
+                "locals": 3,
+            },
+        ])
+    );
+
+    let output = t.run(&["metrics", input.to_str().unwrap()]);
+    let table = String::from_utf8(output.stdout).unwrap();
+    let rows = table.lines().collect::<Vec<_>>();
+    assert_eq!(rows.len(), 3, "{}", table);
+    assert!(rows[1].ends_with("simple"), "{}", table);
+    assert!(rows[2].ends_with("branchy"), "{}", table);
+}
+
+#[test]
+fn parse_link_wast() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wast",
+        r#"
+            (module $a
+                (func (export "f") (result i32) i32.const 42))
+            (register "a" $a)
+            (module
+                (import "a" "f" (func $f (result i32)))
+                (func (export "g") (result i32) call $f))
+            (assert_return (invoke "g") (i32.const 42))
+        "#,
+    );
+    t.run(&[
+        "parse",
+        "--link",
+        input.to_str().unwrap(),
+        "-o",
+        "linked.wasm",
+    ]);
+    let linked = read(t.path("linked.wasm"));
+    let features = wasmparser::WasmFeatures {
+        component_model: true,
+        ..Default::default()
+    };
+    wasmparser::Validator::new_with_features(features)
+        .validate_all(&linked)
+        .unwrap();
+    let wat = wasmprinter::print_bytes(&linked).unwrap();
+    assert!(
+        wat.contains("(core instance (;0;) (instantiate 0))"),
+        "{}",
+        wat
+    );
+    assert!(wat.contains("(with \"a\" (instance 0))"), "{}", wat);
+
+    let input = t.file(
+        "unresolved.wast",
+        r#"
+            (module (func (export "f")))
+            (register "a")
+            (module (import "a" "missing" (func)))
+        "#,
+    );
+    let output = t.run_unchecked(&["parse", "--link", input.to_str().unwrap(), "-t"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("unresolved import `a::missing`"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn smith_corpus_is_reproducible() {
+    let t = Test::new();
+    for dir in ["corpus1", "corpus2"] {
+        t.run(&[
+            "smith",
+            "--seed",
+            "10",
+            "--count",
+            "5",
+            "--seed-len",
+            "512",
+            "--max-funcs",
+            "5",
+            "-o",
+            dir,
+        ]);
+    }
+    for seed in 10..15 {
+        let name = format!("{}.wasm", seed);
+        let wasm = read(t.path("corpus1").join(&name));
+        wasmparser::Validator::new().validate_all(&wasm).unwrap();
+        assert_eq!(wasm, read(t.path("corpus2").join(&name)), "{}", name);
+    }
+
+    // A single seed generates the same module as the corpus does.
+    t.run(&[
+        "smith",
+        "--seed",
+        "12",
+        "--seed-len",
+        "512",
+        "--max-funcs",
+        "5",
+        "-o",
+        "single.wasm",
+    ]);
+    assert_eq!(
+        read(t.path("single.wasm")),
+        read(t.path("corpus1").join("12.wasm"))
+    );
+}
+
+#[test]
+fn smith_corpus_parallel() {
+    let t = Test::new();
+    for (dir, parallel) in [("serial", "1"), ("parallel", "3")] {
+        t.run(&[
+            "smith",
+            "--seed",
+            "20",
+            "--count",
+            "10",
+            "--parallel",
+            parallel,
+            "--seed-len",
+            "512",
+            "--max-funcs",
+            "5",
+            "-o",
+            dir,
+        ]);
+    }
+    for seed in 20..30 {
+        let name = format!("{}.wasm", seed);
+        assert_eq!(
+            read(t.path("serial").join(&name)),
+            read(t.path("parallel").join(&name)),
+            "{}",
+            name
+        );
+    }
+    assert_eq!(std::fs::read_dir(t.path("parallel")).unwrap().count(), 10);
+}
+
+#[test]
+fn coredump_dump() {
+    let t = Test::new();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/coredump");
+    let output = t.run(&[
+        "coredump-dump",
+        dir.join("core.wat").to_str().unwrap(),
+        "--module",
+        dir.join("module.wat").to_str().unwrap(),
+    ]);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "\
+process `module.wasm`
+modules:
+  0: module.wasm
+instances:
+  0: module 0, memories [0], globals [0, 1]
+globals:
+  0: i32 42
+  1: i64 -7
+thread `main`:
+  #0   func 1 `inner` in instance 0 at offset 0x5
+        locals: i32 3, <missing>
+        stack:  i64 8
+  #1   func 0 `outer` in instance 0 at offset 0xa
+        locals: (none)
+        stack:  f32 1.5
+"
+    );
+
+    // Modules which aren't coredumps are rejected.
+    let output = t.run_unchecked(&["coredump-dump", dir.join("module.wat").to_str().unwrap()]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn fuzz_mutate() {
+    let t = Test::new();
+    let output = t.run(&[
+        "fuzz-mutate",
+        "--seed",
+        "0",
+        "--count",
+        "5",
+        "--seed-len",
+        "512",
+    ]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("from 5 inputs, all valid"), "{}", stderr);
+
+    // Inputs can also be replayed from a file.
+    t.file("input.bin", wasm_tools::seed_bytes(3, 512));
+    t.run(&["fuzz-mutate", "input.bin"]);
+}
+
+#[test]
+fn add_names() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"
+            (module
+                (import "env" "f" (func $imported (param i32)))
+                (func (param i32) (local i64))
+                (func $kept (param f32)))
+        "#,
+    );
+    // Entries are deliberately out of order to check they're sorted.
+    let names = t.file(
+        "names.json",
+        r#"{
+            "module": "m",
+            "functions": { "2": "second", "1": "first" },
+            "locals": { "1": { "1": "tmp", "0": "arg" } }
+        }"#,
+    );
+    let output = t.run(&[
+        "add-names",
+        input.to_str().unwrap(),
+        "--names",
+        names.to_str().unwrap(),
+        "-t",
+    ]);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        r#"(module $m
+  (type (;0;) (func (param i32)))
+  (type (;1;) (func (param f32)))
+  (import "env" "f" (func $imported (;0;) (type 0)))
+  (func $first (;1;) (type 0) (param $arg i32)
+    (local $tmp i64)
+  )
+  (func $second (;2;) (type 1) (param f32))
+)"#
+    );
+
+    // Existing names are discarded with `--replace`.
+    let output = t.run(&[
+        "add-names",
+        input.to_str().unwrap(),
+        "--names",
+        names.to_str().unwrap(),
+        "--replace",
+        "-t",
+    ]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("(import \"env\" \"f\" (func (;0;) (type 0)))"));
+
+    // Names must refer to items in the module.
+    let names = t.file("globals.json", r#"{ "globals": { "0": "g" } }"#);
+    let output = t.run_unchecked(&[
+        "add-names",
+        input.to_str().unwrap(),
+        "--names",
+        names.to_str().unwrap(),
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("cannot name global 0"), "{}", stderr);
+}
+
+#[test]
+fn atomics() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"
+            (module
+                (import "env" "mem" (memory 1 1 shared))
+                (memory 1)
+                (func $worker (param i32) (result i32)
+                    (drop (i32.atomic.rmw.add (local.get 0) (i32.const 1)))
+                    atomic.fence
+                    (i32.store (i32.const 0) (i32.const 1))
+                    (i32.store 1 (i32.const 0) (i32.const 1))
+                    (i32.load (local.get 0)))
+                (func $unshared
+                    (i64.store 1 (i32.const 0) (i64.const 0)))
+                (func $notify (result i32)
+                    (memory.atomic.notify 1 (i32.const 0) (i32.const 1))))
+        "#,
+    );
+    let output = t.run(&["atomics", input.to_str().unwrap()]);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "\
+shared memories: 0
+func 0 `worker`:
+  0x3a: i32.atomic.rmw.add (memory 0)
+  0x3f: atomic.fence
+  0x46: warning: non-atomic i32.store of shared memory 0
+  0x53: warning: non-atomic i32.load of shared memory 0
+func 2 `notify`:
+  0x68: memory.atomic.notify (memory 1)
+3 atomic instructions, 2 non-atomic accesses of shared memories
+"
+    );
+
+    let output = t.run(&["atomics", input.to_str().unwrap(), "--json"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["shared_memories"], serde_json::json!([0]));
+    assert_eq!(json["functions"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        json["functions"][0]["non_atomic_shared_accesses"][1]["instruction"],
+        "i32.load"
+    );
+}
+
+#[test]
+fn rename_custom_section() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wasm",
+        wat::parse_str(
+            r#"
+                (module
+                    (@custom "before" (before first) "1")
+                    (func)
+                    (@custom "meta" "contents")
+                    (@custom "after" (after last) "2"))
+            "#,
+        )
+        .unwrap(),
+    );
+    t.run(&[
+        "rename-custom-section",
+        input.to_str().unwrap(),
+        "--rename",
+        "meta=ns:meta",
+        "--rename",
+        "before=after-renamed",
+        "-o",
+        "renamed.wasm",
+    ]);
+
+    let renamed = read(t.path("renamed.wasm"));
+    let mut sections = Vec::new();
+    for payload in wasmparser::Parser::new(0).parse_all(&renamed) {
+        match payload.unwrap() {
+            wasmparser::Payload::CustomSection(c) => sections.push(format!(
+                "{}={}",
+                c.name(),
+                String::from_utf8_lossy(c.data())
+            )),
+            wasmparser::Payload::CodeSectionStart { .. } => sections.push("code".to_string()),
+            _ => {}
+        }
+    }
+    assert_eq!(
+        sections,
+        ["after-renamed=1", "code", "ns:meta=contents", "after=2"]
+    );
+
+    // Renaming the sections back produces the original module.
+    t.run(&[
+        "rename-custom-section",
+        "renamed.wasm",
+        "--rename",
+        "ns:meta=meta",
+        "--rename",
+        "after-renamed=before",
+        "-o",
+        "roundtrip.wasm",
+    ]);
+    assert_eq!(read(t.path("roundtrip.wasm")), read(&input));
+
+    // Sections can't be renamed to the name of another section.
+    let output = t.run_unchecked(&[
+        "rename-custom-section",
+        input.to_str().unwrap(),
+        "--rename",
+        "meta=after",
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("multiple custom sections named `after`"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn build_id() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"(module (@custom "build_id" "\04\de\ad\be\ef"))"#,
+    );
+    let output = t.run(&["build-id", input.to_str().unwrap()]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "deadbeef\n");
+
+    let input = t.file("none.wat", "(module)");
+    let output = t.run_unchecked(&["build-id", input.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("no build_id custom section found"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn subset() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"
+            (module
+              (import "env" "unused" (func $unused_import (param i32)))
+              (import "env" "log" (func $log (param i32)))
+              (type $unused_ty (func (param f64)))
+              (memory 1)
+              (table 2 funcref)
+              (global $counter (mut i32) (i32.const 0))
+              (global $unused_global i32 (i32.const 7))
+              (elem (i32.const 0) func $indirect)
+              (elem declare func $by_ref $dead)
+              (data (i32.const 0) "hello")
+              (func $dead (param f64)
+                i32.const 1
+                call $unused_import)
+              (func $helper (param i32) (result i32)
+                global.get $counter
+                local.get 0
+                i32.add)
+              (func $indirect (result i32)
+                i32.const 42)
+              (func $run (export "run") (param i32)
+                local.get 0
+                call $helper
+                call $log
+                i32.const 0
+                call_indirect (result i32)
+                drop
+                ref.func $by_ref
+                drop)
+              (func $by_ref)
+              (func $other (export "other")
+                f64.const 0
+                call $dead)
+            )
+        "#,
+    );
+    let input = input.to_str().unwrap();
+
+    t.run(&["subset", input, "--export", "run", "-o", "min.wasm"]);
+    wasmparser::validate(&read(t.path("min.wasm"))).unwrap();
+    let output = t.run(&["print", "min.wasm"]);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim(),
+        r#"(module
+  (type (;0;) (func (param i32)))
+  (type (;1;) (func (param i32) (result i32)))
+  (type (;2;) (func (result i32)))
+  (type (;3;) (func))
+  (import "env" "log" (func $log (;0;) (type 0)))
+  (func $helper (;1;) (type 1) (param i32) (result i32)
+    global.get $counter
+    local.get 0
+    i32.add
+  )
+  (func $indirect (;2;) (type 2) (result i32)
+    i32.const 42
+  )
+  (func $run (;3;) (type 0) (param i32)
+    local.get 0
+    call $helper
+    call $log
+    i32.const 0
+    call_indirect (type 2)
+    drop
+    ref.func $by_ref
+    drop
+  )
+  (func $by_ref (;4;) (type 3))
+  (table (;0;) 2 funcref)
+  (global $counter (;0;) (mut i32) i32.const 0)
+  (export "run" (func $run))
+  (elem (;0;) (i32.const 0) func $indirect)
+  (elem (;1;) declare func $by_ref)
+)"#
+    );
+
+    let output = t.run(&[
+        "subset", input, "--export", "run", "--export", "other", "-t",
+    ]);
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        text.contains("(export \"other\" (func $other))"),
+        "{}",
+        text
+    );
+    assert!(text.contains("call $dead"), "{}", text);
+
+    let output = t.run_unchecked(&["subset", input, "--export", "missing"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("no export named `missing`"), "{}", stderr);
+}
+
+#[test]
+fn subset_declares_functions_without_element_section() {
+    let t = Test::new();
+    // `$f` is only declared by its export, which is removed, and there's no
+    // element section to add the declaration to.
+    let input = t.file(
+        "input.wat",
+        r#"
+            (module
+              (memory 1)
+              (func $f (export "f"))
+              (func $run (export "run")
+                ref.func $f
+                drop
+                i32.const 0
+                i32.load
+                drop)
+              (data (i32.const 0) "hi")
+            )
+        "#,
+    );
+    t.run(&[
+        "subset",
+        input.to_str().unwrap(),
+        "--export",
+        "run",
+        "-o",
+        "min.wasm",
+    ]);
+    wasmparser::validate(&read(t.path("min.wasm"))).unwrap();
+    let output = t.run(&["print", "min.wasm"]);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim(),
+        r#"(module
+  (type (;0;) (func))
+  (func $f (;0;) (type 0))
+  (func $run (;1;) (type 0)
+    ref.func $f
+    drop
+    i32.const 0
+    i32.load
+    drop
+  )
+  (memory (;0;) 1)
+  (export "run" (func $run))
+  (elem (;0;) declare func $f)
+  (data (;0;) (i32.const 0) "hi")
+)"#
+    );
+}
+
+#[test]
+fn subset_debug_info() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"
+            (module
+              (func (export "a"))
+              (func (export "b") (result i32) i32.const 1)
+              (@custom ".debug_info" "\01\02\03\04")
+            )
+        "#,
+    );
+    let input = input.to_str().unwrap();
+    let has_debug_info = |wasm: &[u8]| {
+        wasmparser::Parser::new(0).parse_all(wasm).any(|p| {
+            matches!(p.unwrap(), wasmparser::Payload::CustomSection(c) if c.name() == ".debug_info")
+        })
+    };
+
+    // Keeping every function leaves the code section, and so the debug
+    // information, unchanged.
+    let output = t.run(&[
+        "subset", input, "--export", "a", "--export", "b", "-o", "all.wasm",
+    ]);
+    assert!(!String::from_utf8(output.stderr).unwrap().contains("stale"));
+    assert!(has_debug_info(&read(t.path("all.wasm"))));
+
+    let output = t.run(&["subset", input, "--export", "a", "-o", "a.wasm"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("DWARF debug information"), "{}", stderr);
+    assert!(has_debug_info(&read(t.path("a.wasm"))));
+
+    t.run(&[
+        "subset",
+        input,
+        "--export",
+        "a",
+        "--strip-debug",
+        "-o",
+        "stripped.wasm",
+    ]);
+    let stripped = read(t.path("stripped.wasm"));
+    wasmparser::validate(&stripped).unwrap();
+    assert!(!has_debug_info(&stripped));
+
+    let output = t.run_unchecked(&["subset", input, "--export", "a", "--preserve-debug"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("can't be updated"), "{}", stderr);
+}
+
+#[cfg(feature = "net")]
+#[test]
+fn input_url() {
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    let wasm = wat::parse_str("(module (func (export \"f\")))").unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || {
+        for (status, body) in [("200 OK", &wasm[..]), ("404 Not Found", &b""[..])] {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(body).unwrap();
+        }
+    });
+
+    let t = Test::new();
+    let url = format!("http://{}/module.wasm", addr);
+    let output = t.run(&["print", &url]);
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.contains("(export \"f\" (func 0))"), "{}", text);
+
+    let output = t.run_unchecked(&["print", &url]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("failed to download"), "{}", stderr);
+    server.join().unwrap();
+}
+
+#[test]
+fn dedup() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"
+            (module
+              (import "env" "log" (func $log (param i32)))
+              (table 2 funcref)
+              (elem (i32.const 0) func $b $a)
+              (func $a (export "a") (param i32) (result i32)
+                local.get 0
+                i32.const 1
+                i32.add)
+              (func $b (export "b") (param i32) (result i32)
+                local.get 0
+                i32.const 1
+                i32.add)
+              (func $call_a (param i32)
+                local.get 0
+                call $a
+                call $log)
+              (func $call_b (param i32)
+                local.get 0
+                call $b
+                call $log)
+              (func $run (export "run")
+                i32.const 0
+                call $call_a
+                i32.const 1
+                call $call_b
+                ref.func $b
+                drop)
+              (start $run)
+            )
+        "#,
+    );
+    let input = input.to_str().unwrap();
+
+    // `$a` and `$b` are merged, which makes `$call_a` and `$call_b`
+    // identical too.
+    let output = t.run(&["dedup", input, "-o", "out.wasm"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("removed 2 duplicate functions"),
+        "{}",
+        stderr
+    );
+    wasmparser::validate(&read(t.path("out.wasm"))).unwrap();
+    let output = t.run(&["print", "out.wasm"]);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim(),
+        r#"(module
+  (type (;0;) (func (param i32)))
+  (type (;1;) (func (param i32) (result i32)))
+  (type (;2;) (func))
+  (import "env" "log" (func $log (;0;) (type 0)))
+  (func $a (;1;) (type 1) (param i32) (result i32)
+    local.get 0
+    i32.const 1
+    i32.add
+  )
+  (func $call_a (;2;) (type 0) (param i32)
+    local.get 0
+    call $a
+    call $log
+  )
+  (func $run (;3;) (type 2)
+    i32.const 0
+    call $call_a
+    i32.const 1
+    call $call_a
+    ref.func $a
+    drop
+  )
+  (table (;0;) 2 funcref)
+  (export "a" (func $a))
+  (export "b" (func $a))
+  (export "run" (func $run))
+  (start $run)
+  (elem (;0;) (i32.const 0) func $a $a)
+)"#
+    );
+}
+
+#[test]
+fn lint() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"
+            (module
+              (import "env" "abort" (func $abort))
+              (memory 1)
+              (data $d "abcd")
+              (func $div (param i32) (result i32)
+                local.get 0
+                i32.const 0
+                i32.div_u)
+              (func $trap (param i32)
+                block
+                  local.get 0
+                  drop
+                end
+                unreachable)
+              (func $init
+                i32.const 0
+                i32.const 2
+                i32.const 3
+                memory.init $d)
+              (func $fine (param i32) (result i32)
+                local.get 0
+                i32.const 2
+                i32.div_s
+                i32.const 0
+                i32.const 1
+                i32.const 3
+                memory.init $d
+                call $abort
+                unreachable)
+              (func $branch (param i32)
+                local.get 0
+                br_if 0
+                unreachable)
+            )
+        "#,
+    );
+    let output = t.run(&["lint", input.to_str().unwrap()]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+        stderr.lines().collect::<Vec<_>>(),
+        [
+            "warning: func 1 at offset 0x3f: `i32.div_u` divides by a constant zero",
+            "warning: func 2 at offset 0x49: `unreachable` is reached on every path through \
+             the function",
+            "warning: func 3 at offset 0x53: `memory.init` of 3 bytes at offset 2 is out of \
+             bounds of data segment 0 of 4 bytes",
+        ],
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn lint_memory_bounds() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"
+            (module
+              (memory 1 2)
+              (memory $big i64 2)
+              (data $d "abcd")
+              (func $fill
+                i32.const 65000
+                i32.const 0
+                i32.const 1000
+                memory.fill)
+              (func $local (local i32)
+                i32.const 65536
+                local.set 0
+                local.get 0
+                i32.const 0
+                i32.const 1
+                memory.fill)
+              (func $copy
+                i32.const 0
+                i32.const 65535
+                i32.const 2
+                memory.copy)
+              (func $init
+                i32.const 65535
+                i32.const 0
+                i32.const 2
+                memory.init $d)
+              (func $big
+                i64.const 131072
+                i32.const 0
+                i64.const 1
+                memory.fill $big)
+              (func $fine (param i32) (local i32)
+                i32.const 65535
+                i32.const 0
+                i32.const 1
+                memory.fill
+                local.get 0
+                i32.const 0
+                i32.const 100000
+                memory.fill
+                i32.const 70000
+                local.set 1
+                loop
+                  local.get 1
+                  i32.const 0
+                  i32.const 1
+                  memory.fill
+                end)
+            )
+        "#,
+    );
+    let input = input.to_str().unwrap();
+    let output = t.run(&["lint", input]);
+    assert!(output.stderr.is_empty());
+
+    let output = t.run(&["lint", "--memory-bounds", input]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+        stderr.lines().collect::<Vec<_>>(),
+        [
+            "warning: func 0 at offset 0x34: `memory.fill` of 1000 bytes at offset 65000 is out \
+             of bounds of the initial 65536 bytes of memory 0",
+            "warning: func 1 at offset 0x48: `memory.fill` of 1 bytes at offset 65536 is out of \
+             bounds of the initial 65536 bytes of memory 0",
+            "warning: func 2 at offset 0x56: `memory.copy` of 2 bytes from offset 65535 is out \
+             of bounds of the initial 65536 bytes of memory 0",
+            "warning: func 3 at offset 0x65: `memory.init` of 2 bytes to offset 65535 is out of \
+             bounds of the initial 65536 bytes of memory 0",
+            "warning: func 4 at offset 0x74: `memory.fill` of 1 bytes at offset 131072 is out of \
+             bounds of the initial 131072 bytes of memory 1",
+        ],
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn parse_name_locations() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"(module
+  (import "env" "a" (func))
+  (func $b (import "env" "b"))
+
+  (func $c)
+  (memory 1)
+  (func $d
+    call $c)
+)
+"#,
+    );
+    t.run(&[
+        "parse",
+        "--name-locations",
+        input.to_str().unwrap(),
+        "-o",
+        "out.wasm",
+    ]);
+    let wasm = read(t.path("out.wasm"));
+    wasmparser::validate(&wasm).unwrap();
+    let data = wasmparser::Parser::new(0)
+        .parse_all(&wasm)
+        .find_map(|p| match p.unwrap() {
+            wasmparser::Payload::CustomSection(c) if c.name() == "function-locations" => {
+                Some(c.data().to_vec())
+            }
+            _ => None,
+        })
+        .unwrap();
+    let mut reader = wasmparser::BinaryReader::new(&data);
+    let mut locations = Vec::new();
+    for _ in 0..reader.read_var_u32().unwrap() {
+        let index = reader.read_var_u32().unwrap();
+        let line = reader.read_var_u32().unwrap();
+        locations.push((index, line));
+    }
+    assert!(reader.eof());
+    assert_eq!(locations, [(0, 2), (1, 3), (2, 5), (3, 7)]);
+
+    let binary = t.file("input.wasm", &wasm);
+    let output = t.run_unchecked(&["parse", "--name-locations", binary.to_str().unwrap()]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn link() {
+    let t = Test::new();
+    let math = t.file(
+        "math.wat",
+        r#"(module
+  (import "env" "log" (func (param i32)))
+  (global (export "base") i32 (i32.const 10))
+  (func (export "add") (param i32 i32) (result i32)
+    local.get 0
+    local.get 1
+    i32.add)
+)
+"#,
+    );
+    let app = t.file(
+        "app.wat",
+        r#"(module
+  (import "env" "print" (func $print (param i32)))
+  (import "math" "add" (func $add (param i32 i32) (result i32)))
+  (import "math" "base" (global $base i32))
+  (func (export "run") (result i32)
+    global.get $base
+    call $print
+    i32.const 1
+    i32.const 2
+    call $add)
+)
+"#,
+    );
+    t.run(&[
+        "link",
+        math.to_str().unwrap(),
+        app.to_str().unwrap(),
+        "-o",
+        "out.wasm",
+    ]);
+    let wasm = read(t.path("out.wasm"));
+    wasmparser::validate(&wasm).unwrap();
+    let output = t.run(&["print", t.path("out.wasm").to_str().unwrap()]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"(module
+  (type (;0;) (func (param i32)))
+  (type (;1;) (func (param i32 i32) (result i32)))
+  (type (;2;) (func (param i32)))
+  (type (;3;) (func (param i32 i32) (result i32)))
+  (type (;4;) (func (result i32)))
+  (import "env" "log" (func (;0;) (type 0)))
+  (import "env" "print" (func (;1;) (type 2)))
+  (func (;2;) (type 1) (param i32 i32) (result i32)
+    local.get 0
+    local.get 1
+    i32.add
+  )
+  (func (;3;) (type 4) (result i32)
+    global.get 0
+    call 1
+    i32.const 1
+    i32.const 2
+    call 2
+  )
+  (global (;0;) i32 i32.const 10)
+  (export "base" (global 0))
+  (export "add" (func 2))
+  (export "run" (func 3))
+)"#
+    );
+
+    // Imports which don't match an export are an error.
+    let bad = t.file(
+        "bad.wat",
+        r#"(module (import "math" "sub" (func (param i32 i32) (result i32))))"#,
+    );
+    let output = t.run_unchecked(&["link", math.to_str().unwrap(), bad.to_str().unwrap()]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn strip_debug() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"(module
+  (@custom ".debug_info" "a")
+  (@custom "producers" "b")
+  (@custom "name" "")
+  (@custom ".debug_line" "c")
+)"#,
+    );
+    let custom_sections = |wasm: &[u8]| {
+        wasmparser::Parser::new(0)
+            .parse_all(wasm)
+            .filter_map(|p| match p.unwrap() {
+                wasmparser::Payload::CustomSection(c) => Some(c.name().to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    t.run(&[
+        "strip",
+        "--debug",
+        input.to_str().unwrap(),
+        "-o",
+        "out.wasm",
+    ]);
+    assert_eq!(
+        custom_sections(&read(t.path("out.wasm"))),
+        ["producers", "name"]
+    );
+
+    t.run(&[
+        "strip",
+        "--debug",
+        "--delete",
+        "^name$",
+        input.to_str().unwrap(),
+        "-o",
+        "out.wasm",
+    ]);
+    assert_eq!(custom_sections(&read(t.path("out.wasm"))), ["producers"]);
+}
+
+#[test]
+fn strip_preserves_section_order() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        r#"(module
+  (@custom "first" (before first) "")
+  (@custom ".debug_info" (before first) "")
+  (@custom "after-type" (after type) "")
+  (@custom ".debug_line" (after type) "")
+  (@custom "after-func" (after func) "")
+  (@custom "last" (after last) "")
+  (@custom ".debug_str" (after last) "")
+  (type (func))
+  (func (type 0))
+  (memory 1)
+)"#,
+    );
+    // Each section as its name if it's a custom section, or its id if not.
+    let sections = |wasm: &[u8]| {
+        wasmparser::Parser::new(0)
+            .parse_all(wasm)
+            .filter_map(|p| match p.unwrap() {
+                wasmparser::Payload::CustomSection(c) => Some(c.name().to_string()),
+                p => p.as_section().map(|(id, _)| id.to_string()),
+            })
+            .collect::<Vec<_>>()
+    };
+    let wasm = read(t.path("input.wat"));
+    let wasm = wat::parse_bytes(&wasm).unwrap();
+    let expected = sections(&wasm)
+        .into_iter()
+        .filter(|s| !s.starts_with(".debug_"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        expected,
+        [
+            "first",
+            "1",
+            "after-type",
+            "3",
+            "after-func",
+            "5",
+            "10",
+            "last"
+        ]
+    );
+
+    t.run(&[
+        "strip",
+        "--debug",
+        input.to_str().unwrap(),
+        "-o",
+        "out.wasm",
+    ]);
+    assert_eq!(sections(&read(t.path("out.wasm"))), expected);
+}
+
+#[test]
+fn dump_verify_offsets() {
+    let t = Test::new();
+    let input = t.file(
+        "input.wat",
+        "(module (func) (memory 1) (data (i32.const 0) \"x\"))",
+    );
+    t.run(&["dump", "--verify-offsets", input.to_str().unwrap()]);
+
+    // A type section which is two bytes larger than its single type, leaving
+    // a gap before the function section.
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    wasm.extend_from_slice(&[0x01, 0x06, 0x01, 0x60, 0x00, 0x00, 0x00, 0x00]);
+    wasm.extend_from_slice(&[0x03, 0x02, 0x01, 0x00]);
+    let input = t.file("gap.wasm", &wasm);
+    let output = t.run_unchecked(&["dump", "--verify-offsets", input.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "2 bytes at offset 0xe aren't part of any item before the end of the type section at \
+         offset 0x10\n"
+    );
+}
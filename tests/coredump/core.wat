@@ -0,0 +1,23 @@
+;; A coredump of `module.wat` trapping in `$inner`, called from `$outer`.
+(module
+  (memory 1)
+  (global i32 (i32.const 42))
+  (global i64 (i64.const -7))
+
+  ;; process-info: the executable name
+  (@custom "core" "\00\0bmodule.wasm")
+
+  ;; one module
+  (@custom "coremodules" "\01\00\0bmodule.wasm")
+
+  ;; one instance of module 0 with memory 0 and globals 0 and 1
+  (@custom "coreinstances" "\01\00\00\01\00\02\00\01")
+
+  ;; thread "main" with two frames, innermost first
+  (@custom "corestack"
+    "\00\04main\02"
+    ;; func 1 at offset 0x5, locals `i32 3` and a missing value, stack `i64 8`
+    "\00\00\01\05\02\7f\03\01\01\7e\08"
+    ;; func 0 at offset 0xa, no locals, stack `f32 1.5`
+    "\00\00\00\0a\00\01\7d\00\00\c0\3f")
+)
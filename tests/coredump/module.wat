@@ -0,0 +1,11 @@
+;; The program that crashed to produce `core.wat`: `$outer` calls `$inner`,
+;; which traps.
+(module
+  (memory (export "memory") 1)
+  (global (export "counter") (mut i32) (i32.const 0))
+  (global (export "total") (mut i64) (i64.const 0))
+  (func $outer (export "run")
+    (call $inner (i32.const 3)))
+  (func $inner (param i32) (local i32)
+    (drop (i64.const 8))
+    unreachable))
@@ -49,7 +49,8 @@ fn main() {
 
 fn run_test(test: &Path, bless: bool) -> Result<()> {
     let wasm = wat::parse_file(test)?;
-    let assert = test.with_extension("wat.dump");
+    let extension = test.extension().unwrap().to_str().unwrap();
+    let assert = test.with_extension(format!("{}.dump", extension));
     let dump =
         wasmparser_dump::dump_wasm(&wasm).with_context(|| format!("failed to dump {:?}", test))?;
     if bless {
@@ -102,7 +103,9 @@ fn find_tests(path: &Path, tests: &mut Vec<PathBuf>) {
             continue;
         }
         match f.path().extension().and_then(|s| s.to_str()) {
-            Some("wat") => {}
+            // Object files, such as those produced by clang, are checked in
+            // as binaries rather than text.
+            Some("wat") | Some("o") => {}
             _ => continue,
         }
         tests.push(f.path());
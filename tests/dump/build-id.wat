@@ -0,0 +1,3 @@
+(module
+  (@custom "build_id" "\08\01\23\45\67\89\ab\cd\ef")
+)
@@ -0,0 +1,31 @@
+; The `linking.o` object file next to this file is compiled from this source with:
+;
+;     llc -mtriple=wasm32-unknown-unknown -filetype=obj linking.ll -o linking.o
+
+target datalayout = "e-m:e-p:32:32-i64:64-n32:64-S128"
+target triple = "wasm32-unknown-unknown"
+
+@counter = hidden global i32 0, align 4
+@message = internal constant [6 x i8] c"hello\00", align 1
+@fallback = weak global i32 7, align 4
+
+declare i32 @external(i8*)
+
+define hidden i32 @bump() {
+  %1 = load i32, i32* @counter, align 4
+  %2 = add i32 %1, 1
+  store i32 %2, i32* @counter, align 4
+  ret i32 %2
+}
+
+define internal i32 @helper() {
+  %1 = call i32 @external(i8* getelementptr ([6 x i8], [6 x i8]* @message, i32 0, i32 0))
+  ret i32 %1
+}
+
+define weak i32 @entry() {
+  %1 = call i32 @helper()
+  %2 = load i32, i32* @fallback, align 4
+  %3 = add i32 %1, %2
+  ret i32 %3
+}
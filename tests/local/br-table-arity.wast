@@ -0,0 +1,44 @@
+;; All targets of a `br_table`, including the default, must have the same
+;; number of types.
+
+(module
+  (func (param i32) (result i32)
+    block (result i32)
+      block (result i32)
+        i32.const 1
+        local.get 0
+        br_table 0 1 0
+      end
+    end
+  )
+)
+
+(assert_invalid
+  (module
+    (func (param i32) (result i32)
+      block (result i32)
+        block
+          i32.const 1
+          local.get 0
+          br_table 1 0 1
+        end
+        i32.const 0
+      end
+    )
+  )
+  "br_table target labels have different number of types: target 1 (depth 0) has 0 but the default (depth 1) has 1")
+
+(assert_invalid
+  (module
+    (func (param i32)
+      block
+        block (result i32)
+          i32.const 1
+          local.get 0
+          br_table 0 1
+        end
+        drop
+      end
+    )
+  )
+  "br_table target labels have different number of types: target 0 (depth 0) has 1 but the default (depth 1) has 0")
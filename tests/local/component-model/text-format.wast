@@ -0,0 +1,31 @@
+;; Exercises the abbreviations and sugared forms of the component text format.
+
+(component $C
+  (core type $mt (module
+    (import "" "f" (func (param i32) (result i32)))
+    (export "run" (func))
+  ))
+  (core type $ft (func (param i32) (result i32)))
+  (type $t (func (param "a" u32) (result u32)))
+  (import "a" (func $a (type $t)))
+  (import "i" (instance $i (export "f" (func (type $t))) (export "m" (core module (type $mt)))))
+  (import "m" (core module $m (type $mt)))
+  (core module $lib (func (export "add") (param i32) (result i32) local.get 0))
+  (core instance $lib (instantiate $lib))
+  (func $b (export "b") (type $t) (canon lift (core func $lib "add")))
+  (func (export "c") (param "a" u32) (result u32) (canon lift (core func $lib "add")))
+  (core func $lower (canon lower (func $i "f")))
+  (core instance $imp (instantiate (module $i "m") (with "" (instance (export "f" (func $lower))))))
+  (component $D
+    (import "x" (func $x (type $t)))
+    (alias outer $C $t (type $t2))
+    (import "z" (func (type $t2)))
+    (export "y" (func $x))
+  )
+  (instance $d (instantiate $D (with "x" (func $a)) (with "z" (func $b))))
+  (export "d" (instance $d))
+  (export "y" (func $d "y"))
+  (import "v" (value $v u32))
+  (start $a (value $v) (result (value $r)))
+  (export "r" (value $r))
+)
@@ -0,0 +1,40 @@
+(module
+  (memory (import "env" "m") 1 2 (pagesize 1))
+  (memory $one 65536 (pagesize 1))
+  (memory $default 1 (pagesize 65536))
+  (memory $wide i64 0x10000 (pagesize 1))
+  (memory $inline (pagesize 1) (data "hello"))
+
+  (func (result i32) (memory.size $one))
+  (func (result i32) (memory.grow $one (i32.const 1)))
+  (func (result i64) (memory.size $wide)))
+
+;; A 32-bit memory of 1-byte pages may span the whole 32-bit index space.
+(module (memory 0xffffffff (pagesize 1)))
+
+(module binary
+  "\00asm\01\00\00\00"
+  "\05\04\01"       ;; a memory section with 1 entry
+  "\08\00\00"       ;; a memory of no pages with a custom page size of 2**0
+)
+
+(assert_invalid
+  (module (memory 1 (pagesize 2)))
+  "invalid custom page size")
+
+(assert_invalid
+  (module (memory 1 (pagesize 4096)))
+  "invalid custom page size")
+
+(assert_malformed
+  (module quote "(memory 1 (pagesize 3))")
+  "invalid custom page size")
+
+(assert_invalid
+  (module binary
+    "\00asm\01\00\00\00"
+    "\05\04\01"       ;; a memory section with 1 entry
+    "\08\00\40"       ;; a memory with a custom page size of 2**64
+  )
+  "invalid custom page size")
+
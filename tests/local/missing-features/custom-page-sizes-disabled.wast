@@ -0,0 +1,7 @@
+(assert_invalid
+  (module (memory 1 (pagesize 1)))
+  "the custom page sizes proposal must be enabled")
+
+(assert_invalid
+  (module (memory 1 (pagesize 65536)))
+  "the custom page sizes proposal must be enabled")
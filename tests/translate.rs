@@ -0,0 +1,123 @@
+//! Tests of the `wasm_tools::translate` module used by rewriting subcommands.
+
+use std::collections::HashMap;
+use wasm_tools::translate;
+use wasmparser::{Name, NameSectionReader, Parser, Payload};
+
+/// Returns the contents of the `name` section of `wasm`.
+fn name_section(wasm: &[u8]) -> NameSectionReader<'_> {
+    for payload in Parser::new(0).parse_all(wasm) {
+        if let Payload::CustomSection(c) = payload.unwrap() {
+            if c.name() == "name" {
+                return NameSectionReader::new(c.data(), c.data_offset()).unwrap();
+            }
+        }
+    }
+    panic!("no name section found")
+}
+
+/// Returns a module containing only the `section` provided.
+fn module_with(section: &wasm_encoder::NameSection) -> Vec<u8> {
+    let mut module = wasm_encoder::Module::new();
+    module.section(section);
+    module.finish()
+}
+
+type Names = Vec<(u32, String)>;
+
+/// Returns the function names and local names found in `wasm`, in the
+/// order they're encoded.
+fn function_names(wasm: &[u8]) -> (Names, Vec<(u32, Names)>) {
+    let mut funcs = Vec::new();
+    let mut locals = Vec::new();
+    for name in name_section(wasm) {
+        match name.unwrap() {
+            Name::Function(map) => {
+                let mut map = map.get_map().unwrap();
+                for _ in 0..map.get_count() {
+                    let naming = map.read().unwrap();
+                    funcs.push((naming.index, naming.name.to_string()));
+                }
+            }
+            Name::Local(map) => {
+                let mut map = map.get_indirect_map().unwrap();
+                for _ in 0..map.get_indirect_count() {
+                    let naming = map.read().unwrap();
+                    let mut inner = naming.get_map().unwrap();
+                    let mut names = Vec::new();
+                    for _ in 0..inner.get_count() {
+                        let naming = inner.read().unwrap();
+                        names.push((naming.index, naming.name.to_string()));
+                    }
+                    locals.push((naming.indirect_index, names));
+                }
+            }
+            _ => {}
+        }
+    }
+    (funcs, locals)
+}
+
+#[test]
+fn remap_names_follows_functions() {
+    let wasm = wat::parse_str(
+        r#"
+            (module $m
+                (func $a (param $x i32))
+                (func $b)
+                (func $c (param $y i32))
+                (func $d)
+                (memory $mem 1))
+        "#,
+    )
+    .unwrap();
+
+    // Reverse the order of `$a`, `$b`, and `$c`, and remove `$d`.
+    let old_to_new = HashMap::from([(0, 2), (1, 1), (2, 0)]);
+    let section = translate::remap_names(name_section(&wasm), &old_to_new).unwrap();
+
+    let (funcs, locals) = function_names(&module_with(&section));
+    assert_eq!(
+        funcs,
+        [
+            (0, "c".to_string()),
+            (1, "b".to_string()),
+            (2, "a".to_string()),
+        ]
+    );
+    assert_eq!(
+        locals,
+        [
+            (0, vec![(0, "y".to_string())]),
+            (2, vec![(0, "x".to_string())]),
+        ]
+    );
+}
+
+#[test]
+fn remap_names_preserves_other_names() {
+    let wasm = wat::parse_str(
+        r#"
+            (module $m
+                (func $f)
+                (memory $mem 1)
+                (global $g i32 (i32.const 0)))
+        "#,
+    )
+    .unwrap();
+    let old_to_new = HashMap::from([(0, 0)]);
+    let section = translate::remap_names(name_section(&wasm), &old_to_new).unwrap();
+
+    let printed = wasmprinter::print_bytes(module_with(&section)).unwrap();
+    assert!(printed.contains("(module $m"), "{}", printed);
+    assert_eq!(
+        function_names(&module_with(&section)).0,
+        [(0, "f".to_string())]
+    );
+
+    // Nothing was renumbered, so the subsections naming the memory and global
+    // are re-encoded unchanged.
+    let original = name_section(&wasm);
+    let data = &wasm[original.original_position()..];
+    assert!(module_with(&section).ends_with(data));
+}